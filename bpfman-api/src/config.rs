@@ -1,17 +1,32 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    path::PathBuf,
+    str::FromStr,
+};
 
 use aya::programs::XdpFlags;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::ProgramType;
+
 #[derive(Debug, Deserialize, Default, Clone)]
 pub struct Config {
     pub interfaces: Option<HashMap<String, InterfaceConfig>>,
     #[serde(default)]
     pub signing: Option<SigningConfig>,
+    #[serde(default)]
+    pub bpfman_ns: Option<BpfmanNsConfig>,
+    #[serde(default)]
+    pub tcp: Option<TcpConfig>,
+    #[serde(default)]
+    pub authorization: Option<AuthorizationConfig>,
+    #[serde(default)]
+    pub audit: Option<AuditConfig>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -28,6 +43,103 @@ impl Default for SigningConfig {
     }
 }
 
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct BpfmanNsConfig {
+    /// Location of the bpfman-ns executable. When not set, bpfman-ns is
+    /// looked up in `PATH` and then alongside the bpfman executable.
+    pub path: Option<PathBuf>,
+}
+
+/// An additional gRPC listener on a TCP port, which only accepts clients that
+/// present a certificate signed by the bpfman CA.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TcpConfig {
+    #[serde(default = "default_tcp_address")]
+    pub address: IpAddr,
+    #[serde(default = "default_tcp_port")]
+    pub port: u16,
+}
+
+impl TcpConfig {
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.address, self.port)
+    }
+}
+
+fn default_tcp_address() -> IpAddr {
+    IpAddr::V6(Ipv6Addr::UNSPECIFIED)
+}
+
+fn default_tcp_port() -> u16 {
+    50051
+}
+
+/// Limits what callers of the bpfman API may do. Without this section every
+/// caller that can reach the API may do anything. With it, root may still do
+/// anything, and every other caller may only do what the rules that match it
+/// allow.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct AuthorizationConfig {
+    #[serde(default)]
+    pub rules: Vec<AuthorizationRule>,
+}
+
+/// Permissions granted to the callers a rule matches. Unix socket callers are
+/// matched by `users` and `groups`, as names or numeric ids, and TCP callers
+/// by the `subjects` of their certificates. A list that is not set places no
+/// limit, and the permissions of all the rules that match a caller are added
+/// together.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct AuthorizationRule {
+    #[serde(default)]
+    pub users: Vec<String>,
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub subjects: Vec<String>,
+    /// The program types that may be loaded.
+    pub program_types: Option<Vec<ProgramType>>,
+    /// The interfaces XDP and TC programs may be attached to.
+    pub interfaces: Option<Vec<String>>,
+    /// The functions kprobe, uprobe, fentry and fexit programs may be
+    /// attached to. Uprobes without a function name are matched on their
+    /// target.
+    pub functions: Option<Vec<String>>,
+    /// The binaries and libraries uprobes may be attached to, as named by
+    /// their target.
+    pub uprobe_targets: Option<Vec<String>>,
+    /// Whether uprobes may run in every process that uses their target, rather
+    /// than only in a process of the caller's given by its pid.
+    #[serde(default)]
+    pub uprobe_all_processes: bool,
+    /// Whether perf event programs may sample every process, either on each
+    /// CPU without a pid or given the pid of a process that isn't the
+    /// caller's, rather than only a process of the caller's given by its pid.
+    #[serde(default)]
+    pub perf_event_all_processes: bool,
+    /// The tracepoints tracepoint, raw tracepoint and tp_btf programs may be
+    /// attached to. Raw tracepoints and tp_btf tracepoints are named without
+    /// their category.
+    pub tracepoints: Option<Vec<String>>,
+    /// The security hooks LSM programs may be attached to.
+    pub lsm_hooks: Option<Vec<String>>,
+    /// The cgroup v2 paths cgroup programs may be attached to.
+    pub cgroups: Option<Vec<String>>,
+    /// Whether programs loaded by other callers may be unloaded, updated,
+    /// replaced, attached, detached, have their maps read or changed and have
+    /// their maps shared, and whether Apply may be used.
+    #[serde(default)]
+    pub manage_all_programs: bool,
+}
+
+/// Where the audit log of the requests that change programs is written.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "output", rename_all = "lowercase")]
+pub enum AuditConfig {
+    Journal,
+    File { path: PathBuf },
+}
+
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error("Error parsing config file: {0}")]
@@ -126,4 +238,83 @@ mod test {
             None => panic!("expected interfaces to be present"),
         }
     }
+
+    #[test]
+    fn test_config_bpfman_ns_path() {
+        let input = r#"
+        [bpfman_ns]
+        path = "/usr/sbin/bpfman-ns"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert_eq!(
+            config.bpfman_ns.unwrap().path,
+            Some(PathBuf::from("/usr/sbin/bpfman-ns"))
+        );
+    }
+
+    #[test]
+    fn test_config_tcp() {
+        let input = r#"
+        [tcp]
+        address = "10.0.0.1"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert_eq!(
+            config.tcp.unwrap().socket_addr(),
+            "10.0.0.1:50051".parse().unwrap()
+        );
+
+        let config: Config = toml::from_str("").expect("error parsing toml input");
+        assert!(config.tcp.is_none());
+    }
+
+    #[test]
+    fn test_config_authorization() {
+        let input = r#"
+        [[authorization.rules]]
+        groups = ["netdev"]
+        program_types = ["Xdp", "Tc"]
+        interfaces = ["eth0"]
+
+        [[authorization.rules]]
+        users = ["0", "admin"]
+        manage_all_programs = true
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let rules = config.authorization.unwrap().rules;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].groups, vec!["netdev"]);
+        assert_eq!(
+            rules[0].program_types,
+            Some(vec![ProgramType::Xdp, ProgramType::Tc])
+        );
+        assert_eq!(rules[0].interfaces, Some(vec!["eth0".to_string()]));
+        assert!(rules[0].functions.is_none());
+        assert!(!rules[0].manage_all_programs);
+        assert_eq!(rules[1].users, vec!["0", "admin"]);
+        assert!(rules[1].manage_all_programs);
+    }
+
+    #[test]
+    fn test_config_audit() {
+        let input = r#"
+        [audit]
+        output = "file"
+        path = "/var/log/bpfman/audit.log"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert_eq!(
+            config.audit,
+            Some(AuditConfig::File {
+                path: PathBuf::from("/var/log/bpfman/audit.log")
+            })
+        );
+
+        let input = r#"
+        [audit]
+        output = "journal"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert_eq!(config.audit, Some(AuditConfig::Journal));
+    }
 }
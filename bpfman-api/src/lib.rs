@@ -2,6 +2,7 @@
 // Copyright Authors of bpfman
 
 pub mod config;
+pub mod links;
 pub mod util;
 #[path = "bpfman.v1.rs"]
 #[rustfmt::skip]
@@ -10,10 +11,15 @@ pub mod v1;
 use std::iter::FromIterator;
 
 use clap::ValueEnum;
+use prost::Message;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use url::ParseError as urlParseError;
-use v1::bytecode_location::Location;
+use v1::{
+    attach_info::Info, bytecode_location::Location, BtfTracepointAttachInfo, CgroupAttachInfo,
+    FentryAttachInfo, FexitAttachInfo, KprobeAttachInfo, LsmAttachInfo, PerfEventAttachInfo,
+    RawTracepointAttachInfo, TcAttachInfo, TracepointAttachInfo, UprobeAttachInfo, XdpAttachInfo,
+};
 
 #[derive(Error, Debug)]
 pub enum ParseError {
@@ -31,6 +37,16 @@ pub enum ParseError {
     InvalidBytecodeImagePullPolicy { pull_policy: String },
     #[error("{probe} is not a valid probe type")]
     InvalidProbeType { probe: String },
+    #[error("{attach_type} is not a valid cgroup attach type")]
+    InvalidCgroupAttachType { attach_type: String },
+    #[error("{event_type} is not a valid perf event type")]
+    InvalidPerfEventType { event_type: String },
+    #[error("{event} is not a valid {event_type} perf event")]
+    InvalidPerfEvent { event_type: String, event: String },
+    #[error("{event} is not a valid program event type")]
+    InvalidProgramEventType { event: String },
+    #[error("{action} is not a valid apply action")]
+    InvalidApplyAction { action: String },
 }
 
 #[derive(ValueEnum, Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
@@ -289,6 +305,235 @@ impl std::fmt::Display for ProbeType {
     }
 }
 
+/// Where a cgroup program is attached to its cgroup, which also decides the
+/// type of the program. The names match the sections the programs are
+/// written in, such as `cgroup/connect4`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum CgroupAttachType {
+    Ingress,
+    Egress,
+    SockCreate,
+    SockRelease,
+    PostBind4,
+    PostBind6,
+    Bind4,
+    Bind6,
+    Connect4,
+    Connect6,
+    GetPeername4,
+    GetPeername6,
+    GetSockname4,
+    GetSockname6,
+    Sendmsg4,
+    Sendmsg6,
+    Recvmsg4,
+    Recvmsg6,
+    Getsockopt,
+    Setsockopt,
+    Sysctl,
+}
+
+impl CgroupAttachType {
+    pub fn program_type(&self) -> ProgramType {
+        match self {
+            CgroupAttachType::Ingress | CgroupAttachType::Egress => ProgramType::CgroupSkb,
+            CgroupAttachType::SockCreate
+            | CgroupAttachType::SockRelease
+            | CgroupAttachType::PostBind4
+            | CgroupAttachType::PostBind6 => ProgramType::CgroupSock,
+            CgroupAttachType::Bind4
+            | CgroupAttachType::Bind6
+            | CgroupAttachType::Connect4
+            | CgroupAttachType::Connect6
+            | CgroupAttachType::GetPeername4
+            | CgroupAttachType::GetPeername6
+            | CgroupAttachType::GetSockname4
+            | CgroupAttachType::GetSockname6
+            | CgroupAttachType::Sendmsg4
+            | CgroupAttachType::Sendmsg6
+            | CgroupAttachType::Recvmsg4
+            | CgroupAttachType::Recvmsg6 => ProgramType::CgroupSockAddr,
+            CgroupAttachType::Getsockopt | CgroupAttachType::Setsockopt => {
+                ProgramType::CgroupSockopt
+            }
+            CgroupAttachType::Sysctl => ProgramType::CgroupSysctl,
+        }
+    }
+}
+
+impl TryFrom<String> for CgroupAttachType {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Ok(match value.as_str() {
+            "ingress" => CgroupAttachType::Ingress,
+            "egress" => CgroupAttachType::Egress,
+            "sock_create" => CgroupAttachType::SockCreate,
+            "sock_release" => CgroupAttachType::SockRelease,
+            "post_bind4" => CgroupAttachType::PostBind4,
+            "post_bind6" => CgroupAttachType::PostBind6,
+            "bind4" => CgroupAttachType::Bind4,
+            "bind6" => CgroupAttachType::Bind6,
+            "connect4" => CgroupAttachType::Connect4,
+            "connect6" => CgroupAttachType::Connect6,
+            "getpeername4" => CgroupAttachType::GetPeername4,
+            "getpeername6" => CgroupAttachType::GetPeername6,
+            "getsockname4" => CgroupAttachType::GetSockname4,
+            "getsockname6" => CgroupAttachType::GetSockname6,
+            "sendmsg4" => CgroupAttachType::Sendmsg4,
+            "sendmsg6" => CgroupAttachType::Sendmsg6,
+            "recvmsg4" => CgroupAttachType::Recvmsg4,
+            "recvmsg6" => CgroupAttachType::Recvmsg6,
+            "getsockopt" => CgroupAttachType::Getsockopt,
+            "setsockopt" => CgroupAttachType::Setsockopt,
+            "sysctl" => CgroupAttachType::Sysctl,
+            attach_type => {
+                return Err(ParseError::InvalidCgroupAttachType {
+                    attach_type: attach_type.to_string(),
+                })
+            }
+        })
+    }
+}
+
+impl From<CgroupAttachType> for String {
+    fn from(value: CgroupAttachType) -> Self {
+        value.to_string()
+    }
+}
+
+impl std::fmt::Display for CgroupAttachType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let v = match self {
+            CgroupAttachType::Ingress => "ingress",
+            CgroupAttachType::Egress => "egress",
+            CgroupAttachType::SockCreate => "sock_create",
+            CgroupAttachType::SockRelease => "sock_release",
+            CgroupAttachType::PostBind4 => "post_bind4",
+            CgroupAttachType::PostBind6 => "post_bind6",
+            CgroupAttachType::Bind4 => "bind4",
+            CgroupAttachType::Bind6 => "bind6",
+            CgroupAttachType::Connect4 => "connect4",
+            CgroupAttachType::Connect6 => "connect6",
+            CgroupAttachType::GetPeername4 => "getpeername4",
+            CgroupAttachType::GetPeername6 => "getpeername6",
+            CgroupAttachType::GetSockname4 => "getsockname4",
+            CgroupAttachType::GetSockname6 => "getsockname6",
+            CgroupAttachType::Sendmsg4 => "sendmsg4",
+            CgroupAttachType::Sendmsg6 => "sendmsg6",
+            CgroupAttachType::Recvmsg4 => "recvmsg4",
+            CgroupAttachType::Recvmsg6 => "recvmsg6",
+            CgroupAttachType::Getsockopt => "getsockopt",
+            CgroupAttachType::Setsockopt => "setsockopt",
+            CgroupAttachType::Sysctl => "sysctl",
+        };
+        write!(f, "{v}")
+    }
+}
+
+/// The kind of event a perf_event program is run on.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PerfEventType {
+    Software,
+    Hardware,
+    Tracepoint,
+}
+
+impl PerfEventType {
+    /// Returns the perf config of a software or hardware event. They are named
+    /// after their PERF_COUNT_SW_ and PERF_COUNT_HW_ constants in lower case,
+    /// such as `cpu_clock`. Tracepoint events are named `category/name`, and
+    /// their config is the tracepoint's id, which only the kernel knows, so
+    /// None is returned for them.
+    pub fn event_config(&self, event: &str) -> Result<Option<u64>, ParseError> {
+        let config = match (self, event) {
+            (PerfEventType::Software, "cpu_clock") => 0,
+            (PerfEventType::Software, "task_clock") => 1,
+            (PerfEventType::Software, "page_faults") => 2,
+            (PerfEventType::Software, "context_switches") => 3,
+            (PerfEventType::Software, "cpu_migrations") => 4,
+            (PerfEventType::Software, "page_faults_min") => 5,
+            (PerfEventType::Software, "page_faults_maj") => 6,
+            (PerfEventType::Software, "alignment_faults") => 7,
+            (PerfEventType::Software, "emulation_faults") => 8,
+            (PerfEventType::Software, "dummy") => 9,
+            (PerfEventType::Software, "bpf_output") => 10,
+            (PerfEventType::Software, "cgroup_switches") => 11,
+            (PerfEventType::Hardware, "cpu_cycles") => 0,
+            (PerfEventType::Hardware, "instructions") => 1,
+            (PerfEventType::Hardware, "cache_references") => 2,
+            (PerfEventType::Hardware, "cache_misses") => 3,
+            (PerfEventType::Hardware, "branch_instructions") => 4,
+            (PerfEventType::Hardware, "branch_misses") => 5,
+            (PerfEventType::Hardware, "bus_cycles") => 6,
+            (PerfEventType::Hardware, "stalled_cycles_frontend") => 7,
+            (PerfEventType::Hardware, "stalled_cycles_backend") => 8,
+            (PerfEventType::Hardware, "ref_cpu_cycles") => 9,
+            (PerfEventType::Tracepoint, tracepoint) if is_tracepoint_name(tracepoint) => {
+                return Ok(None)
+            }
+            _ => {
+                return Err(ParseError::InvalidPerfEvent {
+                    event_type: self.to_string(),
+                    event: event.to_string(),
+                })
+            }
+        };
+        Ok(Some(config))
+    }
+}
+
+fn is_tracepoint_name(tracepoint: &str) -> bool {
+    match tracepoint.split_once('/') {
+        Some((category, name)) => !category.is_empty() && !name.is_empty() && !name.contains('/'),
+        None => false,
+    }
+}
+
+impl TryFrom<String> for PerfEventType {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Ok(match value.as_str() {
+            "software" => PerfEventType::Software,
+            "hardware" => PerfEventType::Hardware,
+            "tracepoint" => PerfEventType::Tracepoint,
+            event_type => {
+                return Err(ParseError::InvalidPerfEventType {
+                    event_type: event_type.to_string(),
+                })
+            }
+        })
+    }
+}
+
+impl From<PerfEventType> for String {
+    fn from(value: PerfEventType) -> Self {
+        value.to_string()
+    }
+}
+
+impl std::fmt::Display for PerfEventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let v = match self {
+            PerfEventType::Software => "software",
+            PerfEventType::Hardware => "hardware",
+            PerfEventType::Tracepoint => "tracepoint",
+        };
+        write!(f, "{v}")
+    }
+}
+
+/// The result of attaching a program inside a container, written by bpfman-ns
+/// to its stdout as JSON so that bpfman can report what happened.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ContainerAttachResult {
+    pub link_id: Option<u32>,
+    pub error: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
 pub enum XdpProceedOnEntry {
     Aborted,
@@ -609,6 +854,97 @@ impl TryFrom<i32> for ImagePullPolicy {
     }
 }
 
+/// The kind of program lifecycle event sent on the Watch stream.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ProgramEventType {
+    Loaded,
+    Unloaded,
+    Attached,
+    Detached,
+    Updated,
+    Replaced,
+    DispatcherRebuilt,
+}
+
+impl std::fmt::Display for ProgramEventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let v = match self {
+            ProgramEventType::Loaded => "loaded",
+            ProgramEventType::Unloaded => "unloaded",
+            ProgramEventType::Attached => "attached",
+            ProgramEventType::Detached => "detached",
+            ProgramEventType::Updated => "updated",
+            ProgramEventType::Replaced => "replaced",
+            ProgramEventType::DispatcherRebuilt => "dispatcher_rebuilt",
+        };
+        write!(f, "{v}")
+    }
+}
+
+impl TryFrom<i32> for ProgramEventType {
+    type Error = ParseError;
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => ProgramEventType::Loaded,
+            1 => ProgramEventType::Unloaded,
+            2 => ProgramEventType::Attached,
+            3 => ProgramEventType::Detached,
+            4 => ProgramEventType::Updated,
+            5 => ProgramEventType::Replaced,
+            6 => ProgramEventType::DispatcherRebuilt,
+            event => {
+                return Err(ParseError::InvalidProgramEventType {
+                    event: event.to_string(),
+                })
+            }
+        })
+    }
+}
+
+/// What the Apply command did with a program.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ApplyAction {
+    Unchanged,
+    Loaded,
+    Updated,
+    Reloaded,
+    Unloaded,
+    Failed,
+}
+
+impl std::fmt::Display for ApplyAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let v = match self {
+            ApplyAction::Unchanged => "unchanged",
+            ApplyAction::Loaded => "loaded",
+            ApplyAction::Updated => "updated",
+            ApplyAction::Reloaded => "reloaded",
+            ApplyAction::Unloaded => "unloaded",
+            ApplyAction::Failed => "failed",
+        };
+        write!(f, "{v}")
+    }
+}
+
+impl TryFrom<i32> for ApplyAction {
+    type Error = ParseError;
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => ApplyAction::Unchanged,
+            1 => ApplyAction::Loaded,
+            2 => ApplyAction::Updated,
+            3 => ApplyAction::Reloaded,
+            4 => ApplyAction::Unloaded,
+            5 => ApplyAction::Failed,
+            action => {
+                return Err(ParseError::InvalidApplyAction {
+                    action: action.to_string(),
+                })
+            }
+        })
+    }
+}
+
 impl TryFrom<&str> for ImagePullPolicy {
     type Error = ParseError;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
@@ -635,6 +971,181 @@ impl From<ImagePullPolicy> for i32 {
     }
 }
 
+/// Where a program is attached, as shown by `bpfman get -o json` and recorded in
+/// the audit log. Scripts depend on its JSON form, so fields may be added but
+/// existing fields must keep their names and meaning.
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AttachOutput {
+    Xdp {
+        iface: String,
+        priority: i32,
+        position: i32,
+        proceed_on: Vec<String>,
+    },
+    Tc {
+        iface: String,
+        direction: String,
+        priority: i32,
+        position: i32,
+        proceed_on: Vec<String>,
+    },
+    Tracepoint {
+        tracepoint: String,
+    },
+    #[serde(rename = "raw_tracepoint")]
+    RawTracepoint {
+        tracepoint: String,
+    },
+    #[serde(rename = "tp_btf")]
+    TpBtf {
+        tracepoint: String,
+    },
+    Kprobe {
+        fn_name: String,
+        offset: u64,
+        retprobe: bool,
+        container_pid: Option<i32>,
+    },
+    Uprobe {
+        fn_name: Option<String>,
+        offset: u64,
+        target: String,
+        retprobe: bool,
+        pid: Option<i32>,
+        container_pid: Option<i32>,
+    },
+    Fentry {
+        fn_name: String,
+    },
+    Fexit {
+        fn_name: String,
+    },
+    Lsm {
+        hook: String,
+    },
+    Cgroup {
+        cgroup_path: String,
+        attach_type: String,
+        priority: i32,
+        position: i32,
+    },
+    #[serde(rename = "perf_event")]
+    PerfEvent {
+        event_type: String,
+        event: String,
+        sample_period: Option<u64>,
+        sample_frequency: Option<u64>,
+        cpu: Option<u32>,
+        pid: Option<i32>,
+    },
+}
+
+impl TryFrom<Info> for AttachOutput {
+    type Error = ParseError;
+
+    fn try_from(info: Info) -> Result<Self, Self::Error> {
+        Ok(match info {
+            Info::XdpAttachInfo(XdpAttachInfo {
+                priority,
+                iface,
+                position,
+                proceed_on,
+            }) => AttachOutput::Xdp {
+                iface,
+                priority,
+                position,
+                proceed_on: proceed_on
+                    .into_iter()
+                    .map(|p| XdpProceedOnEntry::try_from(p).map(|e| e.to_string()))
+                    .collect::<Result<_, _>>()?,
+            },
+            Info::TcAttachInfo(TcAttachInfo {
+                priority,
+                iface,
+                position,
+                direction,
+                proceed_on,
+            }) => AttachOutput::Tc {
+                iface,
+                direction,
+                priority,
+                position,
+                proceed_on: proceed_on
+                    .into_iter()
+                    .map(|p| TcProceedOnEntry::try_from(p).map(|e| e.to_string()))
+                    .collect::<Result<_, _>>()?,
+            },
+            Info::TracepointAttachInfo(TracepointAttachInfo { tracepoint }) => {
+                AttachOutput::Tracepoint { tracepoint }
+            }
+            Info::RawTracepointAttachInfo(RawTracepointAttachInfo { tracepoint }) => {
+                AttachOutput::RawTracepoint { tracepoint }
+            }
+            Info::BtfTracepointAttachInfo(BtfTracepointAttachInfo { tracepoint }) => {
+                AttachOutput::TpBtf { tracepoint }
+            }
+            Info::KprobeAttachInfo(KprobeAttachInfo {
+                fn_name,
+                offset,
+                retprobe,
+                container_pid,
+            }) => AttachOutput::Kprobe {
+                fn_name,
+                offset,
+                retprobe,
+                container_pid,
+            },
+            Info::UprobeAttachInfo(UprobeAttachInfo {
+                fn_name,
+                offset,
+                target,
+                retprobe,
+                pid,
+                container_pid,
+            }) => AttachOutput::Uprobe {
+                fn_name,
+                offset,
+                target,
+                retprobe,
+                pid,
+                container_pid,
+            },
+            Info::FentryAttachInfo(FentryAttachInfo { fn_name }) => {
+                AttachOutput::Fentry { fn_name }
+            }
+            Info::FexitAttachInfo(FexitAttachInfo { fn_name }) => AttachOutput::Fexit { fn_name },
+            Info::LsmAttachInfo(LsmAttachInfo { hook }) => AttachOutput::Lsm { hook },
+            Info::CgroupAttachInfo(CgroupAttachInfo {
+                cgroup_path,
+                attach_type,
+                priority,
+                position,
+            }) => AttachOutput::Cgroup {
+                cgroup_path,
+                attach_type,
+                priority,
+                position,
+            },
+            Info::PerfEventAttachInfo(PerfEventAttachInfo {
+                event_type,
+                event,
+                sample_period,
+                sample_frequency,
+                cpu,
+                pid,
+            }) => AttachOutput::PerfEvent {
+                event_type,
+                event,
+                sample_period,
+                sample_frequency,
+                cpu,
+                pid,
+            },
+        })
+    }
+}
+
 impl ToString for Location {
     fn to_string(&self) -> String {
         match &self {
@@ -648,3 +1159,18 @@ impl ToString for Location {
         }
     }
 }
+
+impl v1::VerifierLog {
+    /// Returns an aborted status carrying the verifier log in its details.
+    pub fn into_status(self, message: String) -> tonic::Status {
+        tonic::Status::with_details(tonic::Code::Aborted, message, self.encode_to_vec().into())
+    }
+
+    /// Returns the verifier log carried in the details of a status, if any.
+    pub fn from_status(status: &tonic::Status) -> Option<Self> {
+        if status.details().is_empty() {
+            return None;
+        }
+        Self::decode(status.details()).ok()
+    }
+}
@@ -0,0 +1,322 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! The bpf link operations that bpfman and bpfman-ns need and aya doesn't
+//! provide: reading the id of a pinned link, switching a link to another
+//! program, listing the programs on a cgroup hook in the order they run and
+//! attaching a cgroup program before another one.
+//!
+//! Programs are passed in as aya's program fds. Each command only fills in
+//! the leading fields of `union bpf_attr` it uses, which the kernel accepts as
+//! long as the rest would be zero.
+
+use std::{
+    ffi::CString,
+    io,
+    mem::size_of,
+    os::fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+    sync::OnceLock,
+};
+
+use nix::libc;
+
+use crate::CgroupAttachType;
+
+// enum bpf_cmd
+const BPF_OBJ_PIN: libc::c_long = 6;
+const BPF_OBJ_GET: libc::c_long = 7;
+const BPF_OBJ_GET_INFO_BY_FD: libc::c_long = 15;
+const BPF_PROG_QUERY: libc::c_long = 16;
+const BPF_LINK_CREATE: libc::c_long = 28;
+const BPF_LINK_UPDATE: libc::c_long = 29;
+
+// Flags of BPF_LINK_CREATE for cgroups.
+const BPF_F_BEFORE: u32 = 1 << 3;
+const BPF_F_ID: u32 = 1 << 5;
+
+// enum bpf_attach_type
+const BPF_CGROUP_INET_INGRESS: u32 = 0;
+const BPF_CGROUP_INET_EGRESS: u32 = 1;
+const BPF_CGROUP_INET_SOCK_CREATE: u32 = 2;
+const BPF_CGROUP_INET4_BIND: u32 = 8;
+const BPF_CGROUP_INET6_BIND: u32 = 9;
+const BPF_CGROUP_INET4_CONNECT: u32 = 10;
+const BPF_CGROUP_INET6_CONNECT: u32 = 11;
+const BPF_CGROUP_INET4_POST_BIND: u32 = 12;
+const BPF_CGROUP_INET6_POST_BIND: u32 = 13;
+const BPF_CGROUP_UDP4_SENDMSG: u32 = 14;
+const BPF_CGROUP_UDP6_SENDMSG: u32 = 15;
+const BPF_CGROUP_SYSCTL: u32 = 18;
+const BPF_CGROUP_UDP4_RECVMSG: u32 = 19;
+const BPF_CGROUP_UDP6_RECVMSG: u32 = 20;
+const BPF_CGROUP_GETSOCKOPT: u32 = 21;
+const BPF_CGROUP_SETSOCKOPT: u32 = 22;
+const BPF_CGROUP_INET4_GETPEERNAME: u32 = 29;
+const BPF_CGROUP_INET6_GETPEERNAME: u32 = 30;
+const BPF_CGROUP_INET4_GETSOCKNAME: u32 = 31;
+const BPF_CGROUP_INET6_GETSOCKNAME: u32 = 32;
+const BPF_CGROUP_INET_SOCK_RELEASE: u32 = 34;
+
+// BPF_OBJ_PIN and BPF_OBJ_GET.
+#[repr(C)]
+#[derive(Default)]
+struct ObjAttr {
+    pathname: u64,
+    bpf_fd: u32,
+    file_flags: u32,
+}
+
+// BPF_OBJ_GET_INFO_BY_FD.
+#[repr(C)]
+#[derive(Default)]
+struct InfoAttr {
+    bpf_fd: u32,
+    info_len: u32,
+    info: u64,
+}
+
+// The leading fields of struct bpf_link_info, the kernel only fills in as much
+// as info_len allows.
+#[repr(C)]
+#[derive(Default)]
+struct LinkInfo {
+    link_type: u32,
+    id: u32,
+    prog_id: u32,
+}
+
+// BPF_PROG_QUERY.
+#[repr(C)]
+#[derive(Default)]
+struct ProgQueryAttr {
+    target_fd: u32,
+    attach_type: u32,
+    query_flags: u32,
+    attach_flags: u32,
+    prog_ids: u64,
+    prog_cnt: u32,
+    _pad: u32,
+}
+
+// BPF_LINK_CREATE, with the cgroup member of its union.
+#[repr(C)]
+#[derive(Default)]
+struct LinkCreateAttr {
+    prog_fd: u32,
+    target_fd: u32,
+    attach_type: u32,
+    flags: u32,
+    relative_id: u32,
+    _pad: u32,
+    expected_revision: u64,
+}
+
+// BPF_LINK_UPDATE.
+#[repr(C)]
+#[derive(Default)]
+struct LinkUpdateAttr {
+    link_fd: u32,
+    new_prog_fd: u32,
+    flags: u32,
+    old_prog_fd: u32,
+}
+
+/// Returns the id of the link pinned at `link_pin_path`.
+pub fn link_id(link_pin_path: &str) -> io::Result<u32> {
+    let link = obj_get(link_pin_path)?;
+    let mut info = LinkInfo::default();
+    let mut attr = InfoAttr {
+        bpf_fd: link.as_raw_fd() as u32,
+        info_len: size_of::<LinkInfo>() as u32,
+        info: &mut info as *mut _ as u64,
+    };
+    sys_bpf(BPF_OBJ_GET_INFO_BY_FD, &mut attr)?;
+    Ok(info.id)
+}
+
+/// Switches the link pinned at `link_pin_path` to `program`. The link stays
+/// attached throughout, so its hook runs either the old program or the new
+/// one, never both or neither. Only some link types can be updated, such as
+/// cgroup links.
+pub fn update_link(link_pin_path: &str, program: BorrowedFd<'_>) -> io::Result<()> {
+    let link = obj_get(link_pin_path)?;
+    let mut attr = LinkUpdateAttr {
+        link_fd: link.as_raw_fd() as u32,
+        new_prog_fd: program.as_raw_fd() as u32,
+        ..Default::default()
+    };
+    sys_bpf(BPF_LINK_UPDATE, &mut attr)?;
+    Ok(())
+}
+
+/// Returns the ids of the programs attached to `cgroup` with `attach_type`,
+/// in the order the kernel runs them.
+pub fn cgroup_programs(
+    cgroup: BorrowedFd<'_>,
+    attach_type: CgroupAttachType,
+) -> io::Result<Vec<u32>> {
+    let mut ids = vec![0u32; 64];
+    loop {
+        let mut attr = ProgQueryAttr {
+            target_fd: cgroup.as_raw_fd() as u32,
+            attach_type: bpf_attach_type(attach_type),
+            prog_ids: ids.as_mut_ptr() as u64,
+            prog_cnt: ids.len() as u32,
+            ..Default::default()
+        };
+        match sys_bpf(BPF_PROG_QUERY, &mut attr) {
+            Ok(_) => {
+                ids.truncate(attr.prog_cnt as usize);
+                return Ok(ids);
+            }
+            // The kernel says how many programs there are when they don't fit.
+            Err(e)
+                if e.raw_os_error() == Some(libc::ENOSPC) && attr.prog_cnt as usize > ids.len() =>
+            {
+                ids.resize(attr.prog_cnt as usize, 0);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Returns whether the kernel can attach a cgroup program before another
+/// with [`attach_cgroup_before`]. `program` is any cgroup program loaded for
+/// `attach_type`.
+///
+/// The kernel is asked once, with a link that can't be created as it has no
+/// cgroup: kernels that can order cgroup programs get as far as looking up the
+/// cgroup, and older ones reject the flags before that.
+pub fn cgroup_ordering_supported(program: BorrowedFd<'_>, attach_type: CgroupAttachType) -> bool {
+    static SUPPORTED: OnceLock<bool> = OnceLock::new();
+    if let Some(supported) = SUPPORTED.get() {
+        return *supported;
+    }
+    let mut attr = LinkCreateAttr {
+        prog_fd: program.as_raw_fd() as u32,
+        target_fd: u32::MAX,
+        attach_type: bpf_attach_type(attach_type),
+        flags: BPF_F_BEFORE,
+        ..Default::default()
+    };
+    let supported = match sys_bpf(BPF_LINK_CREATE, &mut attr) {
+        Err(e) if e.raw_os_error() == Some(libc::EBADF) => true,
+        Err(e) if e.raw_os_error() == Some(libc::EINVAL) => false,
+        // Anything else says nothing about the kernel, so it's asked again
+        // next time.
+        Ok(fd) => {
+            // SAFETY: on success BPF_LINK_CREATE returns a new file
+            // descriptor that nothing else owns.
+            drop(unsafe { OwnedFd::from_raw_fd(fd as RawFd) });
+            return false;
+        }
+        Err(_) => return false,
+    };
+    *SUPPORTED.get_or_init(|| supported)
+}
+
+/// Attaches `program` to `cgroup` with `attach_type` so it runs just before
+/// the program with the id `before`, and pins the new link at
+/// `link_pin_path`. Needs a kernel where [`cgroup_ordering_supported`].
+pub fn attach_cgroup_before(
+    program: BorrowedFd<'_>,
+    cgroup: BorrowedFd<'_>,
+    attach_type: CgroupAttachType,
+    before: u32,
+    link_pin_path: &str,
+) -> io::Result<()> {
+    let mut attr = LinkCreateAttr {
+        prog_fd: program.as_raw_fd() as u32,
+        target_fd: cgroup.as_raw_fd() as u32,
+        attach_type: bpf_attach_type(attach_type),
+        flags: BPF_F_BEFORE | BPF_F_ID,
+        relative_id: before,
+        ..Default::default()
+    };
+    let ret = sys_bpf(BPF_LINK_CREATE, &mut attr)?;
+    // SAFETY: on success BPF_LINK_CREATE returns a new file descriptor that
+    // nothing else owns.
+    let link = unsafe { OwnedFd::from_raw_fd(ret as RawFd) };
+
+    // The link is detached when it's closed unless the pin holds it.
+    let pathname = CString::new(link_pin_path)?;
+    let mut attr = ObjAttr {
+        pathname: pathname.as_ptr() as u64,
+        bpf_fd: link.as_raw_fd() as u32,
+        ..Default::default()
+    };
+    sys_bpf(BPF_OBJ_PIN, &mut attr)?;
+    Ok(())
+}
+
+fn bpf_attach_type(attach_type: CgroupAttachType) -> u32 {
+    match attach_type {
+        CgroupAttachType::Ingress => BPF_CGROUP_INET_INGRESS,
+        CgroupAttachType::Egress => BPF_CGROUP_INET_EGRESS,
+        CgroupAttachType::SockCreate => BPF_CGROUP_INET_SOCK_CREATE,
+        CgroupAttachType::SockRelease => BPF_CGROUP_INET_SOCK_RELEASE,
+        CgroupAttachType::PostBind4 => BPF_CGROUP_INET4_POST_BIND,
+        CgroupAttachType::PostBind6 => BPF_CGROUP_INET6_POST_BIND,
+        CgroupAttachType::Bind4 => BPF_CGROUP_INET4_BIND,
+        CgroupAttachType::Bind6 => BPF_CGROUP_INET6_BIND,
+        CgroupAttachType::Connect4 => BPF_CGROUP_INET4_CONNECT,
+        CgroupAttachType::Connect6 => BPF_CGROUP_INET6_CONNECT,
+        CgroupAttachType::GetPeername4 => BPF_CGROUP_INET4_GETPEERNAME,
+        CgroupAttachType::GetPeername6 => BPF_CGROUP_INET6_GETPEERNAME,
+        CgroupAttachType::GetSockname4 => BPF_CGROUP_INET4_GETSOCKNAME,
+        CgroupAttachType::GetSockname6 => BPF_CGROUP_INET6_GETSOCKNAME,
+        CgroupAttachType::Sendmsg4 => BPF_CGROUP_UDP4_SENDMSG,
+        CgroupAttachType::Sendmsg6 => BPF_CGROUP_UDP6_SENDMSG,
+        CgroupAttachType::Recvmsg4 => BPF_CGROUP_UDP4_RECVMSG,
+        CgroupAttachType::Recvmsg6 => BPF_CGROUP_UDP6_RECVMSG,
+        CgroupAttachType::Getsockopt => BPF_CGROUP_GETSOCKOPT,
+        CgroupAttachType::Setsockopt => BPF_CGROUP_SETSOCKOPT,
+        CgroupAttachType::Sysctl => BPF_CGROUP_SYSCTL,
+    }
+}
+
+// Opens the link pinned at the given path.
+fn obj_get(path: &str) -> io::Result<OwnedFd> {
+    let pathname = CString::new(path)?;
+    let mut attr = ObjAttr {
+        pathname: pathname.as_ptr() as u64,
+        ..Default::default()
+    };
+    let ret = sys_bpf(BPF_OBJ_GET, &mut attr)?;
+    // SAFETY: on success BPF_OBJ_GET returns a new file descriptor that
+    // nothing else owns.
+    Ok(unsafe { OwnedFd::from_raw_fd(ret as RawFd) })
+}
+
+fn sys_bpf<T>(cmd: libc::c_long, attr: &mut T) -> io::Result<libc::c_long> {
+    // SAFETY: attr is one of the bpf_attr layouts above, and the path or
+    // buffer it may point to outlives the call.
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            cmd,
+            attr as *mut T,
+            size_of::<T>() as libc::c_uint,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The sizes of the kernel's layouts, up to the last field used here.
+    #[test]
+    fn test_attr_layouts() {
+        assert_eq!(size_of::<ObjAttr>(), 16);
+        assert_eq!(size_of::<InfoAttr>(), 16);
+        assert_eq!(size_of::<LinkInfo>(), 12);
+        assert_eq!(size_of::<ProgQueryAttr>(), 32);
+        assert_eq!(size_of::<LinkCreateAttr>(), 32);
+        assert_eq!(size_of::<LinkUpdateAttr>(), 16);
+    }
+}
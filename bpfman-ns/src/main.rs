@@ -4,7 +4,10 @@
 use std::{fs::File, process};
 
 use anyhow::{bail, Context};
-use aya::programs::{links::FdLink, uprobe::UProbeLink, ProbeKind, UProbe};
+use aya::programs::{
+    kprobe::KProbeLink, links::FdLink, uprobe::UProbeLink, KProbe, ProbeKind, UProbe,
+};
+use bpfman_api::{links::link_id, ContainerAttachResult};
 use clap::{Args, Parser, Subcommand};
 use log::debug;
 use nix::sched::{setns, CloneFlags};
@@ -20,7 +23,8 @@ struct Cli {
 enum Commands {
     /// Attach a uprobe program in the given container.
     Uprobe(UprobeArgs),
-    // TODO: add additional commands: Kprobe, etc.
+    /// Attach a kprobe program in the given container.
+    Kprobe(KprobeArgs),
 }
 
 #[derive(Debug, Args)]
@@ -59,6 +63,42 @@ struct UprobeArgs {
     /// Required: Host PID of the container to attach the uprobe in.
     #[clap(short, long)]
     container_pid: i32,
+
+    /// Optional: path to pin the created link at on a bpffs.
+    /// [default: <PROGRAM_PIN_PATH>_link]
+    #[clap(short, long, verbatim_doc_comment)]
+    link_pin_path: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct KprobeArgs {
+    /// Required: path to pinned entry for bpf program on a bpffs.
+    #[clap(short, long, verbatim_doc_comment)]
+    program_pin_path: String,
+
+    /// Required: Function to attach the kprobe to.
+    #[clap(short, long)]
+    fn_name: String,
+
+    /// Required: Offset added to the address of the function for kprobe.
+    /// Not allowed for kretprobes. This should be set to zero (0) if no
+    /// offset is wanted.
+    #[clap(short, long, verbatim_doc_comment)]
+    offset: u64,
+
+    /// Optional: Whether the program is a kretprobe.
+    /// [default: false]
+    #[clap(short, long, verbatim_doc_comment)]
+    retprobe: bool,
+
+    /// Required: Host PID of the container to attach the kprobe in.
+    #[clap(short, long)]
+    container_pid: i32,
+
+    /// Optional: path to pin the created link at on a bpffs.
+    /// [default: <PROGRAM_PIN_PATH>_link]
+    #[clap(short, long, verbatim_doc_comment)]
+    link_pin_path: Option<String>,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -71,16 +111,35 @@ fn main() -> anyhow::Result<()> {
     let bpfman_pid = process::id();
 
     let cli = Cli::parse();
-    match cli.command {
+    let result = match cli.command {
         Commands::Uprobe(args) => execute_uprobe_attach(args, bpfman_pid),
+        Commands::Kprobe(args) => execute_kprobe_attach(args, bpfman_pid),
+    };
+
+    // bpfman reads the outcome of the attach from stdout.
+    let response = match result {
+        Ok(link_id) => ContainerAttachResult {
+            link_id: Some(link_id),
+            error: None,
+        },
+        Err(e) => ContainerAttachResult {
+            link_id: None,
+            error: Some(format!("{e:#}")),
+        },
+    };
+    println!("{}", serde_json::to_string(&response)?);
+
+    if response.error.is_some() {
+        process::exit(1);
     }
+    Ok(())
 }
 
 fn has_cap(cset: caps::CapSet, cap: caps::Capability) {
     debug!("Has {}: {}", cap, caps::has_cap(None, cset, cap).unwrap());
 }
 
-fn execute_uprobe_attach(args: UprobeArgs, bpfman_pid: u32) -> anyhow::Result<()> {
+fn execute_uprobe_attach(args: UprobeArgs, bpfman_pid: u32) -> anyhow::Result<u32> {
     debug!(
         "attempting to attach uprobe in container with pid {}",
         args.container_pid
@@ -116,14 +175,85 @@ fn execute_uprobe_attach(args: UprobeArgs, bpfman_pid: u32) -> anyhow::Result<()
 
     let owned_link: UProbeLink = uprobe
         .take_link(link_id)
-        .expect("take_link failed for uprobe");
+        .context("take_link failed for uprobe")?;
     let fd_link: FdLink = owned_link
         .try_into()
-        .expect("unable to get owned uprobe attach link");
+        .context("unable to get owned uprobe attach link")?;
 
-    fd_link.pin(format!("{}_link", args.program_pin_path))?;
+    let link_pin_path = args
+        .link_pin_path
+        .unwrap_or_else(|| format!("{}_link", args.program_pin_path));
+    pin_link(fd_link, link_pin_path)
+}
 
-    Ok(())
+fn execute_kprobe_attach(args: KprobeArgs, bpfman_pid: u32) -> anyhow::Result<u32> {
+    debug!(
+        "attempting to attach kprobe in container with pid {}",
+        args.container_pid
+    );
+
+    let bpfman_mnt_file_path = format!("/proc/{}/ns/mnt", bpfman_pid);
+    let target_mnt_file_path = format!("/proc/{}/ns/mnt", args.container_pid);
+
+    let bpfman_mnt_file = match File::open(bpfman_mnt_file_path) {
+        Ok(file) => file,
+        Err(e) => bail!("error opening bpfman file: {e}"),
+    };
+
+    let target_mnt_file = match File::open(target_mnt_file_path) {
+        Ok(file) => file,
+        Err(e) => bail!("error opening target file: {e}"),
+    };
+
+    let probe_kind = match args.retprobe {
+        true => ProbeKind::KRetProbe,
+        false => ProbeKind::KProbe,
+    };
+
+    let mut kprobe = KProbe::from_pin(args.program_pin_path.clone(), probe_kind)
+        .context("failed to get KProbe from pin file")?;
+
+    // Set namespace to target namespace
+    set_ns(
+        target_mnt_file,
+        CloneFlags::CLONE_NEWNS,
+        args.container_pid as u32,
+    )?;
+
+    let link_id = kprobe.attach(args.fn_name, args.offset)?;
+
+    // Set namespace back to bpfman namespace
+    set_ns(bpfman_mnt_file, CloneFlags::CLONE_NEWNS, bpfman_pid)?;
+
+    let owned_link: KProbeLink = kprobe
+        .take_link(link_id)
+        .context("take_link failed for kprobe")?;
+    let fd_link: FdLink = owned_link
+        .try_into()
+        .context("unable to get owned kprobe attach link")?;
+
+    let link_pin_path = args
+        .link_pin_path
+        .unwrap_or_else(|| format!("{}_link", args.program_pin_path));
+    pin_link(fd_link, link_pin_path)
+}
+
+// Pins the link and returns its id, read from the kernel through the pin while
+// it still holds the link open. The pin is removed again if that fails, so no
+// link is left behind that bpfman doesn't know about.
+fn pin_link(fd_link: FdLink, link_pin_path: String) -> anyhow::Result<u32> {
+    let pinned_link = fd_link
+        .pin(&link_pin_path)
+        .context("unable to pin attach link")?;
+    match link_id(&link_pin_path) {
+        Ok(id) => Ok(id),
+        Err(e) => {
+            if let Err(unpin_err) = pinned_link.unpin() {
+                debug!("unable to unpin link {link_pin_path}: {unpin_err}");
+            }
+            Err(e).with_context(|| format!("unable to get the id of link {link_pin_path}"))
+        }
+    }
 }
 
 fn set_ns(file: File, nstype: CloneFlags, pid: u32) -> anyhow::Result<()> {
@@ -139,3 +269,73 @@ fn set_ns(file: File, nstype: CloneFlags, pid: u32) -> anyhow::Result<()> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_kprobe() {
+        let cli = Cli::try_parse_from([
+            "bpfman-ns",
+            "kprobe",
+            "--program-pin-path",
+            "/run/bpfman/fs/prog_7",
+            "--link-pin-path",
+            "/run/bpfman/fs/prog_7_link_9",
+            "--fn-name",
+            "try_to_wake_up",
+            "--offset",
+            "0",
+            "--container-pid",
+            "4321",
+            "--retprobe",
+        ])
+        .unwrap();
+        let Commands::Kprobe(args) = cli.command else {
+            panic!("expected a kprobe command");
+        };
+        assert_eq!(args.program_pin_path, "/run/bpfman/fs/prog_7");
+        assert_eq!(
+            args.link_pin_path.as_deref(),
+            Some("/run/bpfman/fs/prog_7_link_9")
+        );
+        assert_eq!(args.fn_name, "try_to_wake_up");
+        assert_eq!(args.offset, 0);
+        assert_eq!(args.container_pid, 4321);
+        assert!(args.retprobe);
+
+        // The link pin path is optional, and the function is not.
+        let cli = Cli::try_parse_from([
+            "bpfman-ns",
+            "kprobe",
+            "--program-pin-path",
+            "/run/bpfman/fs/prog_7",
+            "--fn-name",
+            "try_to_wake_up",
+            "--offset",
+            "16",
+            "--container-pid",
+            "4321",
+        ])
+        .unwrap();
+        let Commands::Kprobe(args) = cli.command else {
+            panic!("expected a kprobe command");
+        };
+        assert_eq!(args.link_pin_path, None);
+        assert_eq!(args.offset, 16);
+        assert!(!args.retprobe);
+
+        assert!(Cli::try_parse_from([
+            "bpfman-ns",
+            "kprobe",
+            "--program-pin-path",
+            "/run/bpfman/fs/prog_7",
+            "--offset",
+            "0",
+            "--container-pid",
+            "4321",
+        ])
+        .is_err());
+    }
+}
@@ -78,6 +78,9 @@ sed -i 's#aya = { git = "https://github.com/aya-rs/aya", branch = "main" }#aya =
 install -Dpm 0755 \
     -t %{buildroot}%{_sbindir} \
     ./target/release/bpfman
+install -Dpm 0755 \
+    -t %{buildroot}%{_sbindir} \
+    ./target/release/bpfman-ns
 install -Dpm 644 \
     -t %{buildroot}%{_unitdir} \
     ./scripts/bpfman.service
@@ -99,6 +102,7 @@ install -Dpm 644 \
 %license cargo-vendor.txt
 %doc README.md
 %{_sbindir}/bpfman
+%{_sbindir}/bpfman-ns
 %{_unitdir}/bpfman.service
 
 %changelog
@@ -0,0 +1,451 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! The audit log, which records each request that changes what is loaded or
+//! attached, and each static program bpfman loads or unloads, as a line of
+//! JSON in the journal or in a file.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Context;
+use bpfman_api::{
+    config::AuditConfig,
+    v1::{attach_info::Info, bytecode_location::Location, LoadRequest},
+    ApplyAction, AttachOutput,
+};
+use chrono::Utc;
+use log::{warn, Level, Log, Record};
+use serde::Serialize;
+use systemd_journal_logger::JournalLog;
+
+use crate::{
+    auth::Caller,
+    command::{self, ApplyOutcome, Program},
+};
+
+/// Writes audit records where the config asks for them, if anywhere.
+#[derive(Clone, Default)]
+pub(crate) struct AuditLog {
+    sink: Option<Arc<Sink>>,
+}
+
+enum Sink {
+    Journal(Box<dyn Log>),
+    File(Mutex<File>),
+}
+
+impl AuditLog {
+    pub(crate) fn new(config: Option<&AuditConfig>) -> anyhow::Result<Self> {
+        let sink = match config {
+            None => return Ok(AuditLog::default()),
+            Some(AuditConfig::Journal) => Sink::Journal(Box::new(
+                JournalLog::default().with_extra_fields(vec![("BPFMAN_AUDIT", "1")]),
+            )),
+            Some(AuditConfig::File { path }) => Sink::File(Mutex::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("unable to open audit log {}", path.display()))?,
+            )),
+        };
+        Ok(AuditLog {
+            sink: Some(Arc::new(sink)),
+        })
+    }
+
+    /// Writes the record. Failures are logged rather than failing the
+    /// request, which has already been carried out.
+    pub(crate) fn record(&self, record: &AuditRecord) {
+        let Some(sink) = &self.sink else {
+            return;
+        };
+        let line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Unable to serialize audit record: {e}");
+                return;
+            }
+        };
+
+        match sink.as_ref() {
+            Sink::Journal(journal) => journal.log(
+                &Record::builder()
+                    .level(Level::Info)
+                    .target("bpfman::audit")
+                    .args(format_args!("{line}"))
+                    .build(),
+            ),
+            Sink::File(file) => {
+                let mut file = file.lock().unwrap();
+                if let Err(e) = writeln!(file, "{line}") {
+                    warn!("Unable to write audit record: {e}");
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AuditRequest {
+    Load,
+    Unload,
+    PullBytecode,
+    Attach,
+    Detach,
+    Update,
+    Replace,
+    MapUpdate,
+    MapDelete,
+    Apply,
+}
+
+/// One request, who made it and what became of it.
+#[derive(Debug, Serialize)]
+pub(crate) struct AuditRecord {
+    time: String,
+    request: AuditRequest,
+    /// None for the static programs bpfman loads and unloads itself.
+    caller: Option<Caller>,
+    /// The kernel id of the program the request was for.
+    id: Option<u32>,
+    name: Option<String>,
+    bytecode: Option<AuditBytecode>,
+    attach: Option<AttachOutput>,
+    link_id: Option<u32>,
+    /// The kernel id of the program that replaced it.
+    new_id: Option<u32>,
+    map: Option<String>,
+    results: Option<Vec<AuditApplyResult>>,
+    success: bool,
+    error: Option<String>,
+}
+
+/// What Apply did with one of the programs.
+#[derive(Debug, Serialize)]
+struct AuditApplyResult {
+    uuid: String,
+    action: String,
+    id: u32,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum AuditBytecode {
+    File {
+        path: String,
+    },
+    Image {
+        url: String,
+        /// The digest of the bytecode layer, once the image has been pulled.
+        digest: Option<String>,
+    },
+}
+
+impl AuditRecord {
+    pub(crate) fn new(request: AuditRequest, caller: Caller) -> Self {
+        AuditRecord {
+            time: Utc::now().to_rfc3339(),
+            request,
+            caller: Some(caller),
+            id: None,
+            name: None,
+            bytecode: None,
+            attach: None,
+            link_id: None,
+            new_id: None,
+            map: None,
+            results: None,
+            success: false,
+            error: None,
+        }
+    }
+
+    /// A record of a static program being loaded or unloaded.
+    pub(crate) fn new_static(request: AuditRequest) -> Self {
+        AuditRecord {
+            caller: None,
+            ..AuditRecord::new(request, Caller::Unknown)
+        }
+    }
+
+    /// Records what a Load request asked for.
+    pub(crate) fn with_load_request(mut self, request: &LoadRequest) -> Self {
+        self.name = Some(request.name.clone());
+        self.bytecode = match request.bytecode.as_ref().and_then(|b| b.location.as_ref()) {
+            Some(Location::File(path)) => Some(AuditBytecode::File { path: path.clone() }),
+            Some(Location::Image(image)) => Some(AuditBytecode::Image {
+                url: image.url.clone(),
+                digest: None,
+            }),
+            None => None,
+        };
+        self.attach = request
+            .attach
+            .as_ref()
+            .and_then(|a| a.info.clone())
+            .and_then(|info| AttachOutput::try_from(info).ok());
+        self
+    }
+
+    /// Records the name, bytecode and attach info of a program that is about
+    /// to be loaded.
+    pub(crate) fn with_program(mut self, program: &Program) -> Self {
+        let data = program.get_data();
+        self.name = data.get_name().ok();
+        self.bytecode = match program.location() {
+            Ok(command::Location::File(path)) => Some(AuditBytecode::File { path }),
+            Ok(command::Location::Image(image)) => Some(AuditBytecode::Image {
+                url: image.image_url,
+                digest: None,
+            }),
+            Err(_) => None,
+        };
+        self.attach = program
+            .attach_info()
+            .ok()
+            .flatten()
+            .and_then(|info| AttachOutput::try_from(info).ok());
+        self
+    }
+
+    /// Records where a program was asked to be attached.
+    pub(crate) fn with_attach_info(mut self, info: &Info) -> Self {
+        self.attach = AttachOutput::try_from(info.clone()).ok();
+        self
+    }
+
+    pub(crate) fn with_bytecode(mut self, location: &Location) -> Self {
+        self.bytecode = Some(match location {
+            Location::File(path) => AuditBytecode::File { path: path.clone() },
+            Location::Image(image) => AuditBytecode::Image {
+                url: image.url.clone(),
+                digest: None,
+            },
+        });
+        self
+    }
+
+    pub(crate) fn with_link_id(mut self, link_id: u32) -> Self {
+        self.link_id = Some(link_id);
+        self
+    }
+
+    pub(crate) fn with_map(mut self, map_name: &str) -> Self {
+        self.map = Some(map_name.to_string());
+        self
+    }
+
+    pub(crate) fn with_id(mut self, id: u32) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub(crate) fn with_image(mut self, url: &str) -> Self {
+        self.bytecode = Some(AuditBytecode::Image {
+            url: url.to_string(),
+            digest: None,
+        });
+        self
+    }
+
+    /// Records the program that was loaded, whose name and digest may only
+    /// be known once its image has been pulled.
+    pub(crate) fn loaded(&mut self, program: &Program) {
+        let data = program.get_data();
+        self.success = true;
+        self.id = data.get_id().ok();
+        self.name = data.get_name().ok().or(self.name.take());
+        if let Some(AuditBytecode::Image { digest, .. }) = &mut self.bytecode {
+            *digest = data.get_image_digest().ok().flatten();
+        }
+    }
+
+    /// Records where the updated program is attached, which shows its new
+    /// priority and proceed-on.
+    pub(crate) fn updated(&mut self, program: &Program) {
+        self.success = true;
+        self.name = program.get_data().get_name().ok();
+        self.attach = program
+            .attach_info()
+            .ok()
+            .flatten()
+            .and_then(|info| AttachOutput::try_from(info).ok());
+    }
+
+    /// Records the link that was created.
+    pub(crate) fn attached(&mut self, link_id: u32) {
+        self.success = true;
+        self.link_id = Some(link_id);
+    }
+
+    /// Records the program that replaced the one the request was for.
+    pub(crate) fn replaced(&mut self, program: &Program) {
+        let data = program.get_data();
+        self.success = true;
+        self.new_id = data.get_id().ok();
+        self.name = data.get_name().ok();
+        if let Some(AuditBytecode::Image { digest, .. }) = &mut self.bytecode {
+            *digest = data.get_image_digest().ok().flatten();
+        }
+    }
+
+    /// Records what Apply did with each program. The request only succeeded
+    /// if every program was applied.
+    pub(crate) fn applied(&mut self, outcomes: &[ApplyOutcome]) {
+        self.success = outcomes.iter().all(|o| o.action != ApplyAction::Failed);
+        self.results = Some(
+            outcomes
+                .iter()
+                .map(|o| AuditApplyResult {
+                    uuid: o.uuid.clone(),
+                    action: o.action.to_string(),
+                    id: o.id,
+                    error: o.error.clone(),
+                })
+                .collect(),
+        );
+    }
+
+    /// Records the digest of a pulled image.
+    pub(crate) fn pulled(&mut self, image_digest: String) {
+        self.success = true;
+        if let Some(AuditBytecode::Image { digest, .. }) = &mut self.bytecode {
+            *digest = Some(image_digest);
+        }
+    }
+
+    pub(crate) fn succeeded(&mut self) {
+        self.success = true;
+    }
+
+    pub(crate) fn failed(&mut self, error: &str) {
+        self.success = false;
+        self.error = Some(error.to_string());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bpfman_api::v1::{AttachInfo, BytecodeLocation, XdpAttachInfo};
+
+    use super::*;
+
+    #[test]
+    fn test_audit_record() {
+        let request = LoadRequest {
+            bytecode: Some(BytecodeLocation {
+                location: Some(Location::File("/opt/pass.o".to_string())),
+            }),
+            name: "pass".to_string(),
+            attach: Some(AttachInfo {
+                info: Some(Info::XdpAttachInfo(XdpAttachInfo {
+                    priority: 50,
+                    iface: "eth0".to_string(),
+                    position: 0,
+                    proceed_on: vec![],
+                })),
+            }),
+            ..Default::default()
+        };
+        let caller = Caller::Local {
+            uid: 1000,
+            gid: 1000,
+            pid: Some(4242),
+        };
+
+        let mut record = AuditRecord::new(AuditRequest::Load, caller).with_load_request(&request);
+        record.failed("the BPF_PROG_LOAD syscall failed");
+
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["request"], "Load");
+        assert_eq!(json["caller"]["type"], "local");
+        assert_eq!(json["caller"]["uid"], 1000);
+        assert_eq!(json["caller"]["pid"], 4242);
+        assert_eq!(json["name"], "pass");
+        assert_eq!(json["bytecode"]["type"], "file");
+        assert_eq!(json["bytecode"]["path"], "/opt/pass.o");
+        assert_eq!(json["attach"]["type"], "xdp");
+        assert_eq!(json["attach"]["iface"], "eth0");
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error"], "the BPF_PROG_LOAD syscall failed");
+    }
+
+    #[test]
+    fn test_audit_record_attach() {
+        let info = Info::XdpAttachInfo(XdpAttachInfo {
+            priority: 50,
+            iface: "eth1".to_string(),
+            position: 0,
+            proceed_on: vec![],
+        });
+
+        let mut record = AuditRecord::new(AuditRequest::Attach, Caller::Unknown)
+            .with_id(6213)
+            .with_attach_info(&info);
+        record.attached(42);
+
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["request"], "Attach");
+        assert_eq!(json["id"], 6213);
+        assert_eq!(json["attach"]["iface"], "eth1");
+        assert_eq!(json["link_id"], 42);
+        assert_eq!(json["success"], true);
+
+        let record = AuditRecord::new_static(AuditRequest::Unload).with_id(7);
+        let json = serde_json::to_value(&record).unwrap();
+        assert!(json["caller"].is_null());
+    }
+
+    #[test]
+    fn test_audit_record_apply() {
+        let outcomes = [
+            ApplyOutcome {
+                uuid: "a".to_string(),
+                action: ApplyAction::Loaded,
+                id: 6213,
+                error: None,
+            },
+            ApplyOutcome {
+                uuid: "b".to_string(),
+                action: ApplyAction::Failed,
+                id: 0,
+                error: Some("Invalid Interface".to_string()),
+            },
+        ];
+
+        let mut record = AuditRecord::new(AuditRequest::Apply, Caller::Unknown);
+        record.applied(&outcomes);
+
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["results"][0]["action"], "loaded");
+        assert_eq!(json["results"][1]["action"], "failed");
+        assert_eq!(json["results"][1]["error"], "Invalid Interface");
+        assert_eq!(json["success"], false);
+    }
+
+    #[test]
+    fn test_audit_log_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let log = AuditLog::new(Some(&AuditConfig::File { path: path.clone() })).unwrap();
+
+        let mut record = AuditRecord::new(AuditRequest::Unload, Caller::Unknown).with_id(7);
+        record.succeeded();
+        log.record(&record);
+        log.record(&record);
+
+        let contents = std::fs::read_to_string(path).unwrap();
+        let lines: Vec<_> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let json: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(json["request"], "Unload");
+        assert_eq!(json["id"], 7);
+        assert_eq!(json["success"], true);
+    }
+}
@@ -0,0 +1,628 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! Works out who sent each request and checks it against the
+//! `[authorization]` section of the bpfman config.
+
+use std::{
+    fmt,
+    fs::{self, Metadata},
+    os::unix::fs::MetadataExt,
+};
+
+use bpfman_api::{
+    config::{AuthorizationConfig, AuthorizationRule},
+    v1::attach_info::Info,
+    ProgramType,
+};
+use serde::Serialize;
+use tonic::{transport::server::UdsConnectInfo, Request, Status};
+use users::{get_group_by_name, get_user_by_name, get_user_by_uid, get_user_groups};
+use x509_cert::{der::Decode, Certificate};
+
+use crate::command::Program;
+
+/// The subject of the certificate presented by a client of the TCP listener,
+/// which is added to the extensions of each of its requests.
+#[derive(Debug, Clone)]
+pub(crate) struct ClientIdentity {
+    pub(crate) subject: String,
+}
+
+/// Adds the subject of the client's certificate to the request. The TLS
+/// handshake has already checked the certificate against the bpfman CA.
+pub(crate) fn client_identity(mut request: Request<()>) -> Result<Request<()>, Status> {
+    let certs = request
+        .peer_certs()
+        .ok_or_else(|| Status::unauthenticated("a client certificate is required"))?;
+    let cert = certs
+        .first()
+        .ok_or_else(|| Status::unauthenticated("a client certificate is required"))?;
+    let cert = Certificate::from_der(cert.get_ref())
+        .map_err(|e| Status::unauthenticated(format!("invalid client certificate: {e}")))?;
+
+    let subject = cert.tbs_certificate.subject.to_string();
+    request.extensions_mut().insert(ClientIdentity { subject });
+    Ok(request)
+}
+
+/// The sender of a request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub(crate) enum Caller {
+    /// A process on the unix socket, as reported by `SO_PEERCRED`.
+    Local {
+        uid: u32,
+        gid: u32,
+        pid: Option<i32>,
+    },
+    /// A client of the TCP listener, identified by its certificate.
+    Remote { subject: String },
+    /// A caller whose credentials could not be read.
+    Unknown,
+}
+
+impl Caller {
+    pub(crate) fn from_request<T>(request: &Request<T>) -> Self {
+        if let Some(identity) = request.extensions().get::<ClientIdentity>() {
+            return Caller::Remote {
+                subject: identity.subject.clone(),
+            };
+        }
+        match request
+            .extensions()
+            .get::<UdsConnectInfo>()
+            .and_then(|info| info.peer_cred)
+        {
+            Some(cred) => Caller::Local {
+                uid: cred.uid(),
+                gid: cred.gid(),
+                pid: cred.pid(),
+            },
+            None => Caller::Unknown,
+        }
+    }
+
+    /// The owner recorded on the programs this caller loads. Processes are
+    /// identified by their uid, so programs outlive the process that loaded
+    /// them.
+    pub(crate) fn owner(&self) -> Option<String> {
+        match self {
+            Caller::Local { uid, .. } => Some(format!("uid:{uid}")),
+            Caller::Remote { subject } => Some(format!("subject:{subject}")),
+            Caller::Unknown => None,
+        }
+    }
+}
+
+impl fmt::Display for Caller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Caller::Local {
+                uid,
+                gid,
+                pid: Some(pid),
+            } => write!(f, "uid {uid} gid {gid} pid {pid}"),
+            Caller::Local {
+                uid,
+                gid,
+                pid: None,
+            } => write!(f, "uid {uid} gid {gid}"),
+            Caller::Remote { subject } => write!(f, "{subject}"),
+            Caller::Unknown => write!(f, "unknown caller"),
+        }
+    }
+}
+
+/// Decides which requests each caller may make.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Authorizer {
+    config: Option<AuthorizationConfig>,
+}
+
+impl Authorizer {
+    pub(crate) fn new(config: Option<AuthorizationConfig>) -> Self {
+        Authorizer { config }
+    }
+
+    // Returns the rules that match the caller, or None if the caller isn't
+    // limited at all.
+    fn rules(&self, caller: &Caller) -> Option<Vec<&AuthorizationRule>> {
+        let config = self.config.as_ref()?;
+        if let Caller::Local { uid: 0, .. } = caller {
+            return None;
+        }
+        Some(
+            config
+                .rules
+                .iter()
+                .filter(|r| rule_matches(r, caller))
+                .collect(),
+        )
+    }
+
+    /// Checks that the caller may load the program, or add the link, and
+    /// attach it where it asks to.
+    pub(crate) fn authorize_program(
+        &self,
+        caller: &Caller,
+        program: &Program,
+    ) -> Result<(), Status> {
+        let Some(rules) = self.rules(caller) else {
+            return Ok(());
+        };
+
+        let kind = program.kind();
+        let attach_point = attach_point(caller, program)?;
+        if rules.iter().any(|r| rule_allows(r, kind, &attach_point)) {
+            return Ok(());
+        }
+
+        Err(Status::permission_denied(match attach_point {
+            AttachPoint::None => format!("{caller} may not load {kind} programs"),
+            AttachPoint::Interface(p)
+            | AttachPoint::Function(p)
+            | AttachPoint::Tracepoint(p)
+            | AttachPoint::Hook(p)
+            | AttachPoint::Cgroup(p) => {
+                format!("{caller} may not attach {kind} programs to {p}")
+            }
+            AttachPoint::Uprobe {
+                function, target, ..
+            } => format!("{caller} may not attach {kind} programs to {function} in {target}"),
+            AttachPoint::PerfEvent {
+                tracepoint: Some(p),
+                ..
+            } => {
+                format!("{caller} may not attach {kind} programs to {p} outside its own processes")
+            }
+            AttachPoint::PerfEvent {
+                tracepoint: None, ..
+            } => format!("{caller} may not sample processes it doesn't own with {kind} programs"),
+        }))
+    }
+
+    /// Returns the program types the caller may load, or None if it may load
+    /// any. Fails if the caller may not load programs at all.
+    pub(crate) fn allowed_program_types(
+        &self,
+        caller: &Caller,
+    ) -> Result<Option<Vec<ProgramType>>, Status> {
+        let Some(rules) = self.rules(caller) else {
+            return Ok(None);
+        };
+        if rules.is_empty() {
+            return Err(Status::permission_denied(format!(
+                "{caller} may not load programs"
+            )));
+        }
+
+        let mut types = vec![];
+        for rule in rules {
+            match &rule.program_types {
+                Some(t) => types.extend(t.iter().copied()),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(types))
+    }
+
+    /// Checks that the caller may have bytecode loaded from a file on the
+    /// bpfman host. Local callers may use the files they can read themselves,
+    /// and remote callers that are limited by the rules may only use images.
+    pub(crate) fn authorize_file(&self, caller: &Caller, path: &str) -> Result<(), Status> {
+        if self.rules(caller).is_none() {
+            return Ok(());
+        }
+        let readable = match caller {
+            Caller::Local { uid, gid, .. } => {
+                fs::metadata(path).map_or(false, |m| can_read(&m, *uid, *gid))
+            }
+            Caller::Remote { .. } | Caller::Unknown => false,
+        };
+        if readable {
+            Ok(())
+        } else {
+            Err(Status::permission_denied(format!(
+                "{caller} may not load bytecode from {path}"
+            )))
+        }
+    }
+
+    /// Returns the owner whose programs the caller may change, or None if it
+    /// may change any program.
+    pub(crate) fn owner_filter(&self, caller: &Caller) -> Result<Option<String>, Status> {
+        let Some(rules) = self.rules(caller) else {
+            return Ok(None);
+        };
+        if rules.iter().any(|r| r.manage_all_programs) {
+            return Ok(None);
+        }
+        match caller.owner() {
+            Some(owner) => Ok(Some(owner)),
+            None => Err(Status::permission_denied(format!(
+                "{caller} may not change programs"
+            ))),
+        }
+    }
+
+    /// Checks that the caller may change any program, which Apply needs as
+    /// it unloads whatever isn't in its request.
+    pub(crate) fn authorize_manage_all(&self, caller: &Caller) -> Result<(), Status> {
+        match self.owner_filter(caller)? {
+            None => Ok(()),
+            Some(_) => Err(Status::permission_denied(format!(
+                "{caller} may not change programs loaded by other callers"
+            ))),
+        }
+    }
+}
+
+// Where a program is attached, named the way the authorization rules name it.
+enum AttachPoint {
+    None,
+    Interface(String),
+    Function(String),
+    Tracepoint(String),
+    Hook(String),
+    Cgroup(String),
+    Uprobe {
+        function: String,
+        target: String,
+        // Whether the uprobe only runs in a process the caller owns.
+        own_process: bool,
+    },
+    PerfEvent {
+        // Set for events on a tracepoint.
+        tracepoint: Option<String>,
+        // Whether the program only samples a process the caller owns.
+        own_process: bool,
+    },
+}
+
+fn attach_point(caller: &Caller, program: &Program) -> Result<AttachPoint, Status> {
+    let info = program
+        .attach_info()
+        .map_err(|e| Status::aborted(format!("failed to read attach info: {e}")))?;
+
+    Ok(match info {
+        None => AttachPoint::None,
+        Some(Info::XdpAttachInfo(i)) => AttachPoint::Interface(i.iface),
+        Some(Info::TcAttachInfo(i)) => AttachPoint::Interface(i.iface),
+        Some(Info::TracepointAttachInfo(i)) => AttachPoint::Tracepoint(i.tracepoint),
+        Some(Info::RawTracepointAttachInfo(i)) => AttachPoint::Tracepoint(i.tracepoint),
+        Some(Info::BtfTracepointAttachInfo(i)) => AttachPoint::Tracepoint(i.tracepoint),
+        Some(Info::KprobeAttachInfo(i)) => AttachPoint::Function(i.fn_name),
+        Some(Info::UprobeAttachInfo(i)) => AttachPoint::Uprobe {
+            function: i.fn_name.unwrap_or_else(|| i.target.clone()),
+            target: i.target,
+            own_process: i.pid.map_or(false, |pid| owns_process(caller, pid)),
+        },
+        Some(Info::FentryAttachInfo(i)) => AttachPoint::Function(i.fn_name),
+        Some(Info::FexitAttachInfo(i)) => AttachPoint::Function(i.fn_name),
+        Some(Info::LsmAttachInfo(i)) => AttachPoint::Hook(i.hook),
+        Some(Info::CgroupAttachInfo(i)) => AttachPoint::Cgroup(i.cgroup_path),
+        // Without a pid, a perf event program samples every process.
+        Some(Info::PerfEventAttachInfo(i)) => AttachPoint::PerfEvent {
+            own_process: i.pid.map_or(false, |pid| owns_process(caller, pid)),
+            tracepoint: (i.event_type == "tracepoint").then_some(i.event),
+        },
+    })
+}
+
+fn rule_allows(rule: &AuthorizationRule, kind: ProgramType, attach_point: &AttachPoint) -> bool {
+    if let Some(types) = &rule.program_types {
+        if !types.contains(&kind) {
+            return false;
+        }
+    }
+
+    let (allowed, point) = match attach_point {
+        AttachPoint::None => return true,
+        AttachPoint::Uprobe {
+            function,
+            target,
+            own_process,
+        } => {
+            return (rule.uprobe_all_processes || *own_process)
+                && contains(&rule.functions, function)
+                && contains(&rule.uprobe_targets, target)
+        }
+        AttachPoint::PerfEvent {
+            tracepoint,
+            own_process,
+        } => {
+            return (rule.perf_event_all_processes || *own_process)
+                && tracepoint
+                    .as_ref()
+                    .map_or(true, |t| contains(&rule.tracepoints, t))
+        }
+        AttachPoint::Interface(p) => (&rule.interfaces, p),
+        AttachPoint::Function(p) => (&rule.functions, p),
+        AttachPoint::Tracepoint(p) => (&rule.tracepoints, p),
+        AttachPoint::Hook(p) => (&rule.lsm_hooks, p),
+        AttachPoint::Cgroup(p) => (&rule.cgroups, p),
+    };
+    contains(allowed, point)
+}
+
+// Lists that aren't set don't limit anything.
+fn contains(allowed: &Option<Vec<String>>, point: &str) -> bool {
+    allowed
+        .as_ref()
+        .map_or(true, |allowed| allowed.iter().any(|a| a == point))
+}
+
+// Only callers on the unix socket own processes, which are the processes
+// running as the caller's uid.
+fn owns_process(caller: &Caller, pid: i32) -> bool {
+    match caller {
+        Caller::Local { uid, .. } => {
+            fs::metadata(format!("/proc/{pid}")).map_or(false, |m| m.uid() == *uid)
+        }
+        Caller::Remote { .. } | Caller::Unknown => false,
+    }
+}
+
+fn rule_matches(rule: &AuthorizationRule, caller: &Caller) -> bool {
+    match caller {
+        Caller::Local { uid, gid, .. } => {
+            rule.users.iter().any(|u| is_user(u, *uid))
+                || rule.groups.iter().any(|g| in_group(g, *uid, *gid))
+        }
+        Caller::Remote { subject } => rule.subjects.iter().any(|s| s == subject),
+        Caller::Unknown => false,
+    }
+}
+
+// Follows the permission bits the kernel would check if the caller opened the
+// file itself.
+fn can_read(metadata: &Metadata, uid: u32, gid: u32) -> bool {
+    let mode = metadata.mode();
+    if metadata.uid() == uid {
+        return mode & 0o400 != 0;
+    }
+    if in_group(&metadata.gid().to_string(), uid, gid) {
+        return mode & 0o040 != 0;
+    }
+    mode & 0o004 != 0
+}
+
+// Users and groups in the config are names or numeric ids.
+fn is_user(user: &str, uid: u32) -> bool {
+    match user.parse::<u32>() {
+        Ok(id) => id == uid,
+        Err(_) => get_user_by_name(user).map_or(false, |u| u.uid() == uid),
+    }
+}
+
+fn in_group(group: &str, uid: u32, gid: u32) -> bool {
+    let want = match group.parse::<u32>() {
+        Ok(id) => id,
+        Err(_) => match get_group_by_name(group) {
+            Some(g) => g.gid(),
+            None => return false,
+        },
+    };
+    if want == gid {
+        return true;
+    }
+
+    // SO_PEERCRED only has the primary group, so look up the others.
+    get_user_by_uid(uid)
+        .and_then(|u| get_user_groups(u.name(), u.primary_group_id()))
+        .map_or(false, |groups| groups.iter().any(|g| g.gid() == want))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(input: &str) -> AuthorizationRule {
+        toml::from_str(input).expect("error parsing toml input")
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let r = rule(
+            r#"
+            users = ["1000"]
+            groups = ["2000"]
+            subjects = ["CN=operator"]
+            "#,
+        );
+        let local = |uid, gid| Caller::Local {
+            uid,
+            gid,
+            pid: Some(42),
+        };
+        assert!(rule_matches(&r, &local(1000, 1000)));
+        assert!(rule_matches(&r, &local(1001, 2000)));
+        assert!(!rule_matches(&r, &local(1001, 1001)));
+        assert!(rule_matches(
+            &r,
+            &Caller::Remote {
+                subject: "CN=operator".to_string()
+            }
+        ));
+        assert!(!rule_matches(&r, &Caller::Unknown));
+    }
+
+    #[test]
+    fn test_owns_process() {
+        let uid = fs::metadata("/proc/self").unwrap().uid();
+        let pid = std::process::id() as i32;
+        let local = |uid| Caller::Local {
+            uid,
+            gid: uid,
+            pid: None,
+        };
+        assert!(owns_process(&local(uid), pid));
+        assert!(!owns_process(&local(uid + 1), pid));
+        assert!(!owns_process(
+            &Caller::Remote {
+                subject: "CN=operator".to_string()
+            },
+            pid
+        ));
+    }
+
+    #[test]
+    fn test_rule_allows() {
+        let r = rule(
+            r#"
+            program_types = ["Xdp", "Tc"]
+            interfaces = ["eth0"]
+            "#,
+        );
+        let eth0 = AttachPoint::Interface("eth0".to_string());
+        let eth1 = AttachPoint::Interface("eth1".to_string());
+        assert!(rule_allows(&r, ProgramType::Xdp, &eth0));
+        assert!(!rule_allows(&r, ProgramType::Xdp, &eth1));
+        assert!(!rule_allows(
+            &r,
+            ProgramType::Probe,
+            &AttachPoint::Function("try_to_wake_up".to_string())
+        ));
+
+        // Uprobes are limited by their target, and only run in every process
+        // when the rule allows it.
+        let r = rule(
+            r#"
+            functions = ["malloc"]
+            uprobe_targets = ["libc"]
+            "#,
+        );
+        let uprobe = |target: &str, own_process| AttachPoint::Uprobe {
+            function: "malloc".to_string(),
+            target: target.to_string(),
+            own_process,
+        };
+        assert!(rule_allows(&r, ProgramType::Probe, &uprobe("libc", true)));
+        assert!(!rule_allows(&r, ProgramType::Probe, &uprobe("libc", false)));
+        assert!(!rule_allows(
+            &r,
+            ProgramType::Probe,
+            &uprobe("/usr/bin/sshd", true)
+        ));
+        let r = rule("uprobe_all_processes = true");
+        assert!(rule_allows(&r, ProgramType::Probe, &uprobe("libc", false)));
+
+        // Perf events only sample the caller's own process unless the rule
+        // allows every process, and tracepoint events are limited by their
+        // tracepoint.
+        let r = rule(r#"tracepoints = ["sched/sched_switch"]"#);
+        let perf_event = |tracepoint: Option<&str>, own_process| AttachPoint::PerfEvent {
+            tracepoint: tracepoint.map(str::to_string),
+            own_process,
+        };
+        assert!(rule_allows(
+            &r,
+            ProgramType::PerfEvent,
+            &perf_event(None, true)
+        ));
+        assert!(!rule_allows(
+            &r,
+            ProgramType::PerfEvent,
+            &perf_event(None, false)
+        ));
+        assert!(rule_allows(
+            &r,
+            ProgramType::PerfEvent,
+            &perf_event(Some("sched/sched_switch"), true)
+        ));
+        assert!(!rule_allows(
+            &r,
+            ProgramType::PerfEvent,
+            &perf_event(Some("sched/sched_wakeup"), true)
+        ));
+        let r = rule("perf_event_all_processes = true");
+        assert!(rule_allows(
+            &r,
+            ProgramType::PerfEvent,
+            &perf_event(None, false)
+        ));
+
+        // Lists that aren't set don't limit anything.
+        let r = rule("");
+        assert!(rule_allows(&r, ProgramType::Xdp, &eth1));
+        assert!(rule_allows(&r, ProgramType::Tracing, &AttachPoint::None));
+    }
+
+    #[test]
+    fn test_owner_filter() {
+        let config: AuthorizationConfig = toml::from_str(
+            r#"
+            [[rules]]
+            users = ["1000"]
+
+            [[rules]]
+            users = ["1001"]
+            manage_all_programs = true
+            "#,
+        )
+        .unwrap();
+        let authorizer = Authorizer::new(Some(config));
+        let local = |uid| Caller::Local {
+            uid,
+            gid: uid,
+            pid: None,
+        };
+
+        assert_eq!(
+            authorizer.owner_filter(&local(1000)).unwrap(),
+            Some("uid:1000".to_string())
+        );
+        assert_eq!(authorizer.owner_filter(&local(1001)).unwrap(), None);
+        assert_eq!(authorizer.owner_filter(&local(0)).unwrap(), None);
+        assert!(authorizer.owner_filter(&Caller::Unknown).is_err());
+        assert!(authorizer.authorize_manage_all(&local(1000)).is_err());
+
+        // Without an authorization section nobody is limited.
+        let authorizer = Authorizer::default();
+        assert_eq!(authorizer.owner_filter(&local(1000)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_allowed_program_types() {
+        let config: AuthorizationConfig = toml::from_str(
+            r#"
+            [[rules]]
+            users = ["1000"]
+            program_types = ["Xdp"]
+
+            [[rules]]
+            users = ["1000"]
+            program_types = ["Tc"]
+
+            [[rules]]
+            users = ["1001"]
+            "#,
+        )
+        .unwrap();
+        let authorizer = Authorizer::new(Some(config));
+        let local = |uid| Caller::Local {
+            uid,
+            gid: uid,
+            pid: None,
+        };
+
+        assert_eq!(
+            authorizer.allowed_program_types(&local(1000)).unwrap(),
+            Some(vec![ProgramType::Xdp, ProgramType::Tc])
+        );
+        assert_eq!(
+            authorizer.allowed_program_types(&local(1001)).unwrap(),
+            None
+        );
+        assert!(authorizer.allowed_program_types(&local(1002)).is_err());
+        assert!(authorizer
+            .authorize_file(
+                &Caller::Remote {
+                    subject: "CN=operator".to_string()
+                },
+                "/etc/hostname"
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! Programs loaded with `unload_on_exit` are bound to the process that loaded
+//! them, and are unloaded by the manager task once that process exits.
+
+use std::{
+    fs, io,
+    os::fd::{FromRawFd, OwnedFd},
+};
+
+use log::{debug, warn};
+use nix::libc;
+use tokio::{io::unix::AsyncFd, sync::mpsc::Sender};
+
+use crate::command::Command;
+
+/// A process, identified by its pid and the time it started so that a later
+/// process which reuses the pid is not mistaken for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct BoundProcess {
+    pub(crate) pid: i32,
+    /// When the process started, in clock ticks after boot.
+    pub(crate) start_time: u64,
+}
+
+impl BoundProcess {
+    /// Opens a pidfd for the running process with this pid.
+    pub(crate) fn open(pid: i32) -> io::Result<(Self, OwnedFd)> {
+        let pidfd = pidfd_open(pid)?;
+        let start_time = start_time(pid)?;
+        Ok((BoundProcess { pid, start_time }, pidfd))
+    }
+
+    /// Opens a pidfd for the process again, such as after a restart. Returns
+    /// None if the process has exited.
+    pub(crate) fn reopen(&self) -> Option<OwnedFd> {
+        match BoundProcess::open(self.pid) {
+            Ok((process, pidfd)) if process == *self => Some(pidfd),
+            _ => None,
+        }
+    }
+}
+
+/// Waits for the process to exit and then asks the manager task to unload the
+/// programs bound to it. A process without a pidfd has already exited.
+pub(crate) async fn wait_for_exit(
+    process: BoundProcess,
+    pidfd: Option<OwnedFd>,
+    tx: Sender<Command>,
+) {
+    if let Some(pidfd) = pidfd {
+        // A pidfd becomes readable when its process exits.
+        let pidfd = match AsyncFd::new(pidfd) {
+            Ok(fd) => fd,
+            Err(e) => {
+                warn!("Unable to watch process {}: {e}", process.pid);
+                return;
+            }
+        };
+        if let Err(e) = pidfd.readable().await {
+            warn!("Unable to watch process {}: {e}", process.pid);
+            return;
+        }
+    }
+
+    debug!("Process {} exited", process.pid);
+    // The manager task has stopped if the send fails, and its programs will
+    // be checked again when it restarts.
+    let _ = tx.send(Command::ProcessExited(process)).await;
+}
+
+fn pidfd_open(pid: i32) -> io::Result<OwnedFd> {
+    // SAFETY: pidfd_open takes no pointers, and returns a new file descriptor
+    // which is owned by the caller.
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) })
+}
+
+// Reads the start time of the process, which is the 22nd field of
+// /proc/<pid>/stat. The fields are counted from after the command name, as
+// it is in parentheses and may itself hold spaces.
+fn start_time(pid: i32) -> io::Result<u64> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat"))?;
+    parse_start_time(&stat).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unable to parse /proc/{pid}/stat"),
+        )
+    })
+}
+
+fn parse_start_time(stat: &str) -> Option<u64> {
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_start_time() {
+        let stat = "4242 (my (odd) prog) S 1 4242 4242 0 -1 4194560 1510 0 0 0 \
+                    3 1 0 0 20 0 1 0 9876543 10485760 512 18446744073709551615";
+        assert_eq!(parse_start_time(stat), Some(9876543));
+        assert_eq!(parse_start_time("4242 (prog) S 1"), None);
+    }
+
+    #[test]
+    fn test_open_self() {
+        let pid = std::process::id() as i32;
+        let (process, _pidfd) = BoundProcess::open(pid).unwrap();
+        assert_eq!(process.pid, pid);
+        assert!(process.reopen().is_some());
+
+        let other = BoundProcess {
+            start_time: process.start_time + 1,
+            ..process
+        };
+        assert!(other.reopen().is_none());
+    }
+}
@@ -2,26 +2,46 @@
 // Copyright Authors of bpfman
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     convert::TryInto,
+    os::fd::AsFd,
     path::{Path, PathBuf},
+    process::Stdio,
+    time::SystemTime,
 };
 
 use anyhow::anyhow;
 use aya::{
     programs::{
-        kprobe::KProbeLink, links::FdLink, loaded_programs, trace_point::TracePointLink,
-        uprobe::UProbeLink, KProbe, TracePoint, UProbe,
+        fentry::FEntryLink,
+        fexit::FExitLink,
+        kprobe::KProbeLink,
+        links::FdLink,
+        loaded_programs,
+        lsm::LsmLink,
+        perf_event::{PerfEventScope, PerfTypeId, SamplePolicy},
+        raw_trace_point::RawTracePointLink,
+        tp_btf::BtfTracePointLink,
+        trace_point::TracePointLink,
+        uprobe::UProbeLink,
+        BtfTracePoint, CgroupSkb, CgroupSkbAttachType, CgroupSock, CgroupSockAddr,
+        CgroupSockAddrAttachType, CgroupSockAttachType, CgroupSockopt, CgroupSockoptAttachType,
+        CgroupSysctl, FEntry, FExit, KProbe, Lsm, PerfEvent, ProbeKind, Program as AyaProgram,
+        ProgramFd, RawTracePoint, TracePoint, UProbe,
     },
-    BpfLoader,
+    util::online_cpus,
+    BpfLoader, Btf, VerifierLogLevel,
 };
 use bpfman_api::{
     config::Config,
+    links::{attach_cgroup_before, cgroup_programs, update_link},
     util::directories::*,
+    ApplyAction, CgroupAttachType, ContainerAttachResult, ParseError, PerfEventType,
     ProbeType::{self, *},
-    ProgramType,
+    ProgramEventType, ProgramType, TcProceedOn, XdpProceedOn,
 };
-use log::{debug, info};
+use log::{debug, info, warn};
+use rand::Rng;
 use tokio::{
     fs::{create_dir_all, read_dir, remove_dir_all},
     select,
@@ -33,15 +53,24 @@ use tokio::{
 };
 
 use crate::{
+    audit::{AuditLog, AuditRecord, AuditRequest},
+    bound_process::BoundProcess,
     command::{
-        BpfMap, Command, Direction,
+        ApplyOutcome, AttachArgs, BpfMap, Command, DetachArgs, Direction,
         Direction::{Egress, Ingress},
-        Program, ProgramData, PullBytecodeArgs, UnloadArgs,
+        KprobeProgram, Location, MapOperation, Program, ProgramData, ProgramEvent,
+        PullBytecodeArgs, ReplaceArgs, UnloadArgs, UpdateArgs, UprobeProgram, VerifyArgs,
+        VerifyResult,
     },
+    elf::program_section,
     errors::BpfmanError,
+    maps::{map_info_by_id, PinnedMap},
     multiprog::{Dispatcher, DispatcherId, DispatcherInfo, TcDispatcher, XdpDispatcher},
     oci_utils::image_manager::Command as ImageManagerCommand,
-    utils::{bytes_to_string, get_ifindex, set_dir_permissions, should_map_be_pinned},
+    static_program::StaticPrograms,
+    utils::{
+        bytes_to_string, find_bpfman_ns, get_ifindex, set_dir_permissions, should_map_be_pinned,
+    },
     ROOT_DB,
 };
 
@@ -54,16 +83,21 @@ pub(crate) struct BpfManager {
     maps: HashMap<u32, BpfMap>,
     commands: Receiver<Command>,
     image_manager: Sender<ImageManagerCommand>,
+    events: broadcast::Sender<ProgramEvent>,
+    audit: AuditLog,
 }
 
 pub(crate) struct ProgramMap {
     programs: HashMap<u32, Program>,
+    // Additional attachments of loaded programs, indexed by link id.
+    links: HashMap<u32, Program>,
 }
 
 impl ProgramMap {
     fn new() -> Self {
         ProgramMap {
             programs: HashMap::new(),
+            links: HashMap::new(),
         }
     }
 
@@ -83,17 +117,43 @@ impl ProgramMap {
         self.programs.get(id)
     }
 
+    fn insert_link(&mut self, link_id: u32, link: Program) -> Option<Program> {
+        self.links.insert(link_id, link)
+    }
+
+    fn remove_link(&mut self, link_id: &u32) -> Option<Program> {
+        self.links.remove(link_id)
+    }
+
+    fn get_link(&self, link_id: &u32) -> Option<&Program> {
+        self.links.get(link_id)
+    }
+
+    // Returns a random link id which isn't used by any other link.
+    fn next_link_id(&self) -> u32 {
+        let mut rng = rand::thread_rng();
+        loop {
+            let link_id = rng.gen::<u32>();
+            if link_id != 0 && !self.links.contains_key(&link_id) {
+                return link_id;
+            }
+        }
+    }
+
     fn programs_mut<'a>(
         &'a mut self,
         program_type: &'a ProgramType,
         if_index: &'a Option<u32>,
         direction: &'a Option<Direction>,
     ) -> impl Iterator<Item = &'a mut Program> {
-        self.programs.values_mut().filter(|p| {
-            p.kind() == *program_type
-                && p.if_index().unwrap() == *if_index
-                && p.direction().unwrap() == *direction
-        })
+        self.programs
+            .values_mut()
+            .chain(self.links.values_mut())
+            .filter(|p| {
+                p.kind() == *program_type
+                    && p.if_index().unwrap() == *if_index
+                    && p.direction().unwrap() == *direction
+            })
     }
 
     // Adds a new program and sets the positions of programs that are to be attached via a dispatcher.
@@ -108,6 +168,7 @@ impl ProgramMap {
         let mut extensions = self
             .programs
             .values_mut()
+            .chain(self.links.values_mut())
             .filter(|p| {
                 p.kind() == program_type
                     && p.if_index().unwrap() == if_index
@@ -143,6 +204,7 @@ impl ProgramMap {
         let mut extensions = self
             .programs
             .values_mut()
+            .chain(self.links.values_mut())
             .filter(|p| {
                 p.kind() == program_type
                     && p.if_index().unwrap() == Some(if_index)
@@ -204,6 +266,8 @@ impl BpfManager {
         config: Config,
         commands: Receiver<Command>,
         image_manager: Sender<ImageManagerCommand>,
+        events: broadcast::Sender<ProgramEvent>,
+        audit: AuditLog,
     ) -> Self {
         Self {
             config,
@@ -212,6 +276,8 @@ impl BpfManager {
             maps: HashMap::new(),
             commands,
             image_manager,
+            events,
+            audit,
         }
     }
 
@@ -225,6 +291,26 @@ impl BpfManager {
                 .open_tree(name)
                 .expect("unable to open database tree");
 
+            if let Some(link_id) = name
+                .strip_prefix("link_")
+                .and_then(|id| id.parse::<u32>().ok())
+            {
+                debug!("rebuilding state for link {}", link_id);
+
+                // If there's an error here remove broken tree and continue
+                match Program::new_link_from_db(link_id) {
+                    Ok(link) => {
+                        self.programs.insert_link(link_id, link);
+                    }
+                    Err(_) => {
+                        ROOT_DB
+                            .drop_tree(name)
+                            .expect("unable to remove broken link tree");
+                    }
+                }
+                continue;
+            }
+
             let id = match name.parse::<u32>() {
                 Ok(id) => id,
                 Err(_) => {
@@ -312,10 +398,113 @@ impl BpfManager {
         Ok(())
     }
 
+    // Brings the loaded static programs in line with the static program
+    // files. Static programs loaded before a restart are restored by
+    // rebuild_state, and are matched to their entries by file and digest so
+    // they aren't loaded twice. Programs whose entry was edited or removed are
+    // unloaded, unless their file was skipped because it couldn't be read. A
+    // program that fails to load is skipped so that it doesn't keep the others
+    // from loading.
+    pub(crate) async fn reconcile_static_programs(&mut self, programs: StaticPrograms) {
+        let wanted: HashSet<(String, String)> = programs
+            .programs
+            .iter()
+            .map(|p| (p.file_name(), p.digest()))
+            .collect();
+
+        let loaded: Vec<(u32, (String, String))> = self
+            .programs
+            .get_programs_iter()
+            .filter_map(|(id, p)| {
+                p.get_data()
+                    .get_static_program()
+                    .ok()
+                    .flatten()
+                    .map(|key| (id, key))
+            })
+            .collect();
+
+        // Unload first, so that dispatcher slots are freed up for edited
+        // programs.
+        for (id, key) in &loaded {
+            if wanted.contains(key) || programs.skipped_files.contains(&key.0) {
+                continue;
+            }
+            let mut audit = AuditRecord::new_static(AuditRequest::Unload).with_id(*id);
+            match self.remove_program(*id).await {
+                Ok(()) => {
+                    info!(
+                        "Unloaded static program with program id {id}, its entry in {} was edited or removed",
+                        key.0
+                    );
+                    audit.succeeded();
+                }
+                Err(e) => {
+                    warn!("Failed to unload static program with program id {id}: {e}");
+                    audit.failed(&e.to_string());
+                }
+            }
+            self.audit.record(&audit);
+        }
+
+        let loaded: HashSet<(String, String)> = loaded.into_iter().map(|(_, key)| key).collect();
+        for static_program in programs.programs {
+            let key = (static_program.file_name(), static_program.digest());
+            if loaded.contains(&key) {
+                debug!(
+                    "static program {} from {} is already loaded",
+                    static_program.name(),
+                    key.0
+                );
+                continue;
+            }
+
+            let program = match static_program.to_program() {
+                Ok(p) => p,
+                Err(e) => {
+                    warn!("Invalid static program in {}: {e}", key.0);
+                    continue;
+                }
+            };
+
+            let mut audit = AuditRecord::new_static(AuditRequest::Load).with_program(&program);
+            match self.add_program(program).await {
+                Ok(p) => {
+                    info!(
+                        "Loaded static program {} from {} with program id {}",
+                        static_program.name(),
+                        key.0,
+                        p.get_data().get_id().unwrap_or_default()
+                    );
+                    send_event(&self.events, ProgramEventType::Loaded, &p);
+                    audit.loaded(&p);
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to load static program {} from {}: {e}",
+                        static_program.name(),
+                        key.0
+                    );
+                    audit.failed(&e.to_string());
+                }
+            }
+            self.audit.record(&audit);
+        }
+    }
+
     pub(crate) async fn add_program(
         &mut self,
         mut program: Program,
     ) -> Result<Program, BpfmanError> {
+        // A uuid identifies a single program.
+        if let Some(uuid) = program.get_data().get_uuid()? {
+            if self.program_id_by_uuid(&uuid).is_some() {
+                return Err(BpfmanError::Error(format!(
+                    "a program with uuid {uuid} is already loaded"
+                )));
+            }
+        }
+
         let map_owner_id = program.get_data().get_map_owner_id()?;
         // Set map_pin_path if we're using another program's maps
         if let Some(map_owner_id) = map_owner_id {
@@ -334,9 +523,16 @@ impl BpfManager {
 
                 self.add_multi_attach_program(&mut program).await
             }
-            Program::Tracepoint(_) | Program::Kprobe(_) | Program::Uprobe(_) => {
-                self.add_single_attach_program(&mut program).await
-            }
+            Program::Tracepoint(_)
+            | Program::RawTracepoint(_)
+            | Program::BtfTracepoint(_)
+            | Program::Kprobe(_)
+            | Program::Uprobe(_)
+            | Program::Fentry(_)
+            | Program::Fexit(_)
+            | Program::Lsm(_)
+            | Program::Cgroup(_)
+            | Program::PerfEvent(_) => self.add_single_attach_program(&mut program, None).await,
             Program::Unsupported(_) => panic!("Cannot add unsupported program"),
         };
 
@@ -362,7 +558,17 @@ impl BpfManager {
                 // Only add program to bpfManager if we've completed all mutations and it's successfully loaded.
                 self.programs.insert(id, program.to_owned());
 
-                Ok(program)
+                let Program::Cgroup(ref p) = program else {
+                    return Ok(program);
+                };
+                let cgroup_path = p.get_cgroup_path()?;
+                let attach_type = p.get_attach_type()?;
+                if let Err(e) = self.order_cgroup_programs(&cgroup_path, attach_type) {
+                    let _ = self.remove_program(id).await;
+                    return Err(e);
+                }
+                // Pick up the position the program was given.
+                self.get_program(id)
             }
             Err(e) => {
                 // Cleanup any directories associated with the map_pin_path.
@@ -376,25 +582,142 @@ impl BpfManager {
         }
     }
 
+    fn program_id_by_uuid(&self, uuid: &str) -> Option<u32> {
+        self.programs
+            .get_programs_iter()
+            .find(|(_, p)| p.get_data().get_uuid().ok().flatten().as_deref() == Some(uuid))
+            .map(|(id, _)| id)
+    }
+
+    // Brings the programs that were loaded with a uuid in line with the given
+    // programs: programs that are no longer wanted are unloaded, new programs
+    // are loaded, and changed programs are updated in place when possible or
+    // loaded again otherwise. Programs loaded without a uuid are left alone.
+    pub(crate) async fn apply_programs(
+        &mut self,
+        programs: Vec<Program>,
+    ) -> Result<Vec<ApplyOutcome>, BpfmanError> {
+        debug!("BpfManager::apply_programs()");
+
+        let wanted: HashSet<String> = programs
+            .iter()
+            .filter_map(|p| p.get_data().get_uuid().ok().flatten())
+            .collect();
+        let unwanted: Vec<(String, u32)> = self
+            .programs
+            .get_programs_iter()
+            .filter_map(|(id, p)| p.get_data().get_uuid().ok().flatten().map(|u| (u, id)))
+            .filter(|(uuid, _)| !wanted.contains(uuid))
+            .collect();
+
+        let mut outcomes = vec![];
+
+        // Each program is applied on its own, and one that fails is reported
+        // without stopping the rest, rather than undoing the programs that
+        // were already applied.
+
+        // Unload first, so that dispatcher slots are freed up for the programs
+        // being loaded.
+        for (uuid, id) in unwanted {
+            let outcome = match self.remove_program(id).await {
+                Ok(()) => ApplyOutcome {
+                    uuid,
+                    action: ApplyAction::Unloaded,
+                    id,
+                    error: None,
+                },
+                Err(e) => ApplyOutcome {
+                    uuid,
+                    action: ApplyAction::Failed,
+                    id,
+                    error: Some(e.to_string()),
+                },
+            };
+            outcomes.push(outcome);
+        }
+
+        for program in programs {
+            let uuid = program.get_data().get_uuid()?.unwrap_or_default();
+            let outcome = match self.apply_program(program).await {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    warn!("Unable to apply program {uuid}: {e}");
+                    ApplyOutcome {
+                        id: self.program_id_by_uuid(&uuid).unwrap_or(0),
+                        uuid,
+                        action: ApplyAction::Failed,
+                        error: Some(e.to_string()),
+                    }
+                }
+            };
+            outcomes.push(outcome);
+        }
+
+        Ok(outcomes)
+    }
+
+    async fn apply_program(&mut self, program: Program) -> Result<ApplyOutcome, BpfmanError> {
+        let uuid = program.get_data().get_uuid()?.unwrap_or_default();
+
+        let Some(id) = self.program_id_by_uuid(&uuid) else {
+            let program = self.add_program(program).await?;
+            send_event(&self.events, ProgramEventType::Loaded, &program);
+            return Ok(ApplyOutcome {
+                uuid,
+                action: ApplyAction::Loaded,
+                id: program.get_data().get_id()?,
+                error: None,
+            });
+        };
+
+        let existing = self
+            .programs
+            .get(&id)
+            .expect("program found by uuid should exist");
+
+        // A different digest means the bytecode or where the program is
+        // attached changed, which takes a new program.
+        if existing.get_data().get_apply_digest()? != program.get_data().get_apply_digest()? {
+            if let Err(e) = self.remove_program(id).await {
+                program.get_data().discard();
+                return Err(e);
+            }
+            let program = self.add_program(program).await?;
+            send_event(&self.events, ProgramEventType::Loaded, &program);
+            return Ok(ApplyOutcome {
+                uuid,
+                action: ApplyAction::Reloaded,
+                id: program.get_data().get_id()?,
+                error: None,
+            });
+        }
+
+        let changes = in_place_changes(existing, &program);
+        program.get_data().discard();
+        let (priority, proceed_on, metadata) = changes?;
+
+        let action = if priority.is_none() && proceed_on.is_none() && metadata.is_none() {
+            ApplyAction::Unchanged
+        } else {
+            self.update_program(id, priority, proceed_on, metadata)
+                .await?;
+            ApplyAction::Updated
+        };
+
+        Ok(ApplyOutcome {
+            uuid,
+            action,
+            id,
+            error: None,
+        })
+    }
+
     pub(crate) async fn add_multi_attach_program(
         &mut self,
         program: &mut Program,
     ) -> Result<u32, BpfmanError> {
         debug!("BpfManager::add_multi_attach_program()");
-        let name = &program.get_data().get_name()?;
-
-        // This load is just to verify the BPF Function Name is valid.
-        // The actual load is performed in the XDP or TC logic.
-        // don't pin maps here.
-        let mut ext_loader = BpfLoader::new()
-            .allow_unsupported_maps()
-            .extension(name)
-            .load(program.get_data().program_bytes())?;
-
-        match ext_loader.program_mut(name) {
-            Some(_) => Ok(()),
-            None => Err(BpfmanError::BpfFunctionNameNotValid(name.to_owned())),
-        }?;
+        verify_extension_name(program)?;
 
         let did = program
             .dispatcher_id()?
@@ -407,6 +730,38 @@ impl BpfManager {
 
         debug!("next_available_id={next_available_id}");
 
+        self.load_multi_attach_program(program).await
+    }
+
+    // Loads an XDP or TC program into a rebuilt dispatcher, which atomically
+    // replaces the dispatcher that is currently attached.
+    async fn load_multi_attach_program(
+        &mut self,
+        program: &mut Program,
+    ) -> Result<u32, BpfmanError> {
+        self.attach_to_dispatcher(program).await.or_else(|e| {
+            // If kernel ID was never set there's no pins to cleanup here so just continue
+            if program.get_data().get_id().is_ok() {
+                program
+                    .delete()
+                    .map_err(BpfmanError::BpfmanProgramDeleteError)?;
+            }
+            Err(e)
+        })?;
+
+        let id = program.get_data().get_id()?;
+        program.set_attached();
+
+        Ok(id)
+    }
+
+    // Rebuilds the dispatcher that the provided XDP or TC program or link is
+    // attached through, with the program or link added to it.
+    async fn attach_to_dispatcher(&mut self, program: &mut Program) -> Result<(), BpfmanError> {
+        let did = program
+            .dispatcher_id()?
+            .ok_or(BpfmanError::DispatcherNotRequired)?;
+
         let program_type = program.kind();
         let if_index = program.if_index()?;
         let if_name = program.if_name().unwrap().to_string();
@@ -441,27 +796,69 @@ impl BpfManager {
             old_dispatcher,
             self.image_manager.clone(),
         )
-        .await
-        .or_else(|e| {
-            // If kernel ID was never set there's no pins to cleanup here so just continue
-            if program.get_data().get_id().is_ok() {
-                program
-                    .delete()
-                    .map_err(BpfmanError::BpfmanProgramDeleteError)?;
-            }
-            Err(e)
-        })?;
+        .await?;
+        send_dispatcher_events(&self.events, &programs);
 
         self.dispatchers.insert(did, dispatcher);
-        let id = program.get_data().get_id()?;
-        program.set_attached();
 
-        Ok(id)
+        Ok(())
     }
 
+    // Attach a program inside the container with the given pid by handing the
+    // pinned program off to bpfman-ns, which reports the outcome on stdout.
+    fn attach_in_container(
+        &self,
+        program_type: &str,
+        container_pid: i32,
+        args: Vec<String>,
+    ) -> Result<u32, BpfmanError> {
+        let attach_error = |error: String| BpfmanError::ContainerAttachError {
+            program_type: program_type.to_string(),
+            container_pid,
+            error,
+        };
+
+        let bpfman_ns = find_bpfman_ns(
+            self.config
+                .bpfman_ns
+                .as_ref()
+                .and_then(|c| c.path.as_deref()),
+        )?;
+        debug!("using bpfman-ns at {}", bpfman_ns.display());
+
+        let output = std::process::Command::new(&bpfman_ns)
+            .args(args)
+            .stderr(Stdio::inherit())
+            .output()
+            .map_err(|e| attach_error(format!("failed to run {}: {e}", bpfman_ns.display())))?;
+
+        debug!("bpfman-ns status: {:?}", output.status);
+
+        let result: ContainerAttachResult =
+            serde_json::from_slice(&output.stdout).map_err(|e| {
+                attach_error(format!(
+                    "unable to parse bpfman-ns result ({}): {e}",
+                    output.status
+                ))
+            })?;
+
+        match (result.error, result.link_id) {
+            (Some(error), _) => Err(attach_error(error)),
+            (None, _) if !output.status.success() => Err(attach_error(format!(
+                "bpfman-ns exited with {}",
+                output.status
+            ))),
+            (None, Some(link_id)) => Ok(link_id),
+            (None, None) => Err(attach_error("bpfman-ns returned no link id".to_string())),
+        }
+    }
+
+    // `replaces` is the program a cgroup program is replacing. The new program
+    // takes over its link rather than being attached again.
     pub(crate) async fn add_single_attach_program(
         &mut self,
         p: &mut Program,
+        replaces: Option<u32>,
     ) -> Result<u32, BpfmanError> {
         debug!("BpfManager::add_single_attach_program()");
         let name = &p.get_data().get_name()?;
@@ -482,6 +879,10 @@ impl BpfManager {
             bpf.map_pin_path(map_pin_path);
         }
 
+        if let Some(level) = p.get_data().get_verifier_log_level()? {
+            bpf.verifier_log_level(VerifierLogLevel::from_bits_truncate(level));
+        }
+
         let mut loader = bpf
             .allow_unsupported_maps()
             .load(p.get_data().program_bytes())?;
@@ -490,17 +891,37 @@ impl BpfManager {
             .program_mut(name)
             .ok_or(BpfmanError::BpfFunctionNameNotValid(name.to_owned()))?;
 
+        // Load-only probe programs are created as kprobes, switch to a uprobe
+        // if that's what the bytecode contains.
+        let resolved = match (&*p, &*raw_program) {
+            (Program::Kprobe(program), aya::programs::Program::UProbe(_))
+                if program.get_data().get_load_only()? =>
+            {
+                Some(Program::Uprobe(UprobeProgram {
+                    data: program.get_data().clone(),
+                }))
+            }
+            _ => None,
+        };
+        if let Some(program) = resolved {
+            *p = program;
+        }
+
         let res = match p {
             Program::Tracepoint(ref mut program) => {
-                let tracepoint = program.get_tracepoint()?;
-                let parts: Vec<&str> = tracepoint.split('/').collect();
-                if parts.len() != 2 {
-                    return Err(BpfmanError::InvalidAttach(
-                        program.get_tracepoint()?.to_string(),
-                    ));
-                }
-                let category = parts[0].to_owned();
-                let name = parts[1].to_owned();
+                let load_only = program.get_data().get_load_only()?;
+                let attach_point = if load_only {
+                    None
+                } else {
+                    let tracepoint = program.get_tracepoint()?;
+                    let parts: Vec<&str> = tracepoint.split('/').collect();
+                    if parts.len() != 2 {
+                        return Err(BpfmanError::InvalidAttach(
+                            program.get_tracepoint()?.to_string(),
+                        ));
+                    }
+                    Some((parts[0].to_owned(), parts[1].to_owned()))
+                };
 
                 let tracepoint: &mut TracePoint = raw_program.try_into()?;
 
@@ -511,16 +932,18 @@ impl BpfManager {
 
                 let id = program.data.get_id()?;
 
-                let link_id = tracepoint.attach(&category, &name)?;
+                if let Some((category, name)) = attach_point {
+                    let link_id = tracepoint.attach(&category, &name)?;
 
-                let owned_link: TracePointLink = tracepoint.take_link(link_id)?;
-                let fd_link: FdLink = owned_link
-                    .try_into()
-                    .expect("unable to get owned tracepoint attach link");
+                    let owned_link: TracePointLink = tracepoint.take_link(link_id)?;
+                    let fd_link: FdLink = owned_link
+                        .try_into()
+                        .expect("unable to get owned tracepoint attach link");
 
-                fd_link
-                    .pin(format!("{RTDIR_FS}/prog_{}_link", id))
-                    .map_err(BpfmanError::UnableToPinLink)?;
+                    fd_link
+                        .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                        .map_err(BpfmanError::UnableToPinLink)?;
+                }
 
                 tracepoint
                     .pin(format!("{RTDIR_FS}/prog_{id}"))
@@ -529,7 +952,8 @@ impl BpfManager {
                 Ok(id)
             }
             Program::Kprobe(ref mut program) => {
-                let requested_probe_type = match program.get_retprobe()? {
+                let load_only = program.get_data().get_load_only()?;
+                let requested_probe_type = match !load_only && program.get_retprobe()? {
                     true => Kretprobe,
                     false => Kprobe,
                 };
@@ -543,10 +967,14 @@ impl BpfManager {
                 let kprobe: &mut KProbe = raw_program.try_into()?;
                 kprobe.load()?;
 
-                // verify that the program loaded was the same type as the
-                // user requested
                 let loaded_probe_type = ProbeType::from(kprobe.kind());
-                if requested_probe_type != loaded_probe_type {
+                if load_only {
+                    // there's no requested type for a load-only program, so
+                    // record the type that was loaded
+                    program.set_retprobe(loaded_probe_type == Kretprobe)?;
+                } else if requested_probe_type != loaded_probe_type {
+                    // verify that the program loaded was the same type as the
+                    // user requested
                     return Err(BpfmanError::Error(format!(
                         "expected {requested_probe_type}, loaded program is {loaded_probe_type}"
                     )));
@@ -556,25 +984,42 @@ impl BpfManager {
 
                 let id = program.data.get_id()?;
 
-                let link_id = kprobe.attach(program.get_fn_name()?, program.get_offset()?)?;
-
-                let owned_link: KProbeLink = kprobe.take_link(link_id)?;
-                let fd_link: FdLink = owned_link
-                    .try_into()
-                    .expect("unable to get owned kprobe attach link");
-
-                fd_link
-                    .pin(format!("{RTDIR_FS}/prog_{}_link", id))
-                    .map_err(BpfmanError::UnableToPinLink)?;
+                let program_pin_path = format!("{RTDIR_FS}/prog_{id}");
 
                 kprobe
-                    .pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .pin(program_pin_path.clone())
                     .map_err(BpfmanError::UnableToPinProgram)?;
 
+                match program.get_container_pid()? {
+                    // Load-only programs are attached later with the Attach command
+                    _ if load_only => (),
+                    None => {
+                        // Attach kprobe in same container as the bpfman process
+                        let link_id =
+                            kprobe.attach(program.get_fn_name()?, program.get_offset()?)?;
+
+                        let owned_link: KProbeLink = kprobe.take_link(link_id)?;
+                        let fd_link: FdLink = owned_link
+                            .try_into()
+                            .expect("unable to get owned kprobe attach link");
+
+                        fd_link
+                            .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                            .map_err(BpfmanError::UnableToPinLink)?;
+                    }
+                    Some(p) => {
+                        // Attach kprobe in different container from the bpfman process
+                        let prog_args = kprobe_container_args(program, program_pin_path, None, p)?;
+                        let link_id = self.attach_in_container("kprobe", p, prog_args)?;
+                        debug!("kprobe attached in container {p} with link id {link_id}");
+                    }
+                };
+
                 Ok(id)
             }
             Program::Uprobe(ref mut program) => {
-                let requested_probe_type = match program.get_retprobe()? {
+                let load_only = program.get_data().get_load_only()?;
+                let requested_probe_type = match !load_only && program.get_retprobe()? {
                     true => Uretprobe,
                     false => Uprobe,
                 };
@@ -582,10 +1027,14 @@ impl BpfManager {
                 let uprobe: &mut UProbe = raw_program.try_into()?;
                 uprobe.load()?;
 
-                // verify that the program loaded was the same type as the
-                // user requested
                 let loaded_probe_type = ProbeType::from(uprobe.kind());
-                if requested_probe_type != loaded_probe_type {
+                if load_only {
+                    // there's no requested type for a load-only program, so
+                    // record the type that was loaded
+                    program.set_retprobe(loaded_probe_type == Uretprobe)?;
+                } else if requested_probe_type != loaded_probe_type {
+                    // verify that the program loaded was the same type as the
+                    // user requested
                     return Err(BpfmanError::Error(format!(
                         "expected {requested_probe_type}, loaded program is {loaded_probe_type}"
                     )));
@@ -603,6 +1052,8 @@ impl BpfManager {
                     .map_err(BpfmanError::UnableToPinProgram)?;
 
                 match program.get_container_pid()? {
+                    // Load-only programs are attached later with the Attach command
+                    _ if load_only => (),
                     None => {
                         // Attach uprobe in same container as the bpfman process
                         let link_id = uprobe.attach(
@@ -649,86 +1100,1036 @@ impl BpfManager {
                             prog_args.extend(["--pid".to_string(), pid.to_string()])
                         }
 
-                        let status = std::process::Command::new("./target/debug/bpfman-ns")
-                            .args(prog_args)
-                            .status()
-                            .expect("bpfman-ns call failed to return status");
-
-                        debug!("bpfman-ns status: {:?}", status);
-
-                        if !status.success() {
-                            return Err(BpfmanError::ContainerAttachError {
-                                program_type: "uprobe".to_string(),
-                                container_pid: program.get_container_pid()?.unwrap(),
-                            });
-                        }
+                        let link_id = self.attach_in_container("uprobe", p, prog_args)?;
+                        debug!("uprobe attached in container {p} with link id {link_id}");
                     }
                 };
 
                 Ok(id)
             }
-            _ => panic!("not a supported single attach program"),
-        };
+            Program::RawTracepoint(ref mut program) => {
+                let tracepoint = program.get_tracepoint()?;
+                let raw_tp: &mut RawTracePoint = raw_program.try_into()?;
+                raw_tp.load()?;
+                program.get_data_mut().set_kernel_info(&raw_tp.info()?)?;
 
-        match res {
-            Ok(id) => {
-                // If this program is the map(s) owner pin all maps (except for .rodata and .bss) by name.
-                if p.get_data().get_map_pin_path()?.is_none() {
-                    let map_pin_path = calc_map_pin_path(id);
-                    p.get_data_mut().set_map_pin_path(&map_pin_path)?;
-                    create_map_pin_path(&map_pin_path).await?;
+                let id = program.data.get_id()?;
 
-                    for (name, map) in loader.maps_mut() {
-                        if !should_map_be_pinned(name) {
-                            continue;
-                        }
-                        debug!(
-                            "Pinning map: {name} to path: {}",
-                            map_pin_path.join(name).display()
-                        );
-                        map.pin(map_pin_path.join(name))
-                            .map_err(BpfmanError::UnableToPinMap)?;
-                    }
-                }
-            }
-            Err(_) => {
-                // If kernel ID was never set there's no pins to cleanup here so just continue
-                if p.get_data().get_id().is_ok() {
-                    p.delete().map_err(BpfmanError::BpfmanProgramDeleteError)?;
-                };
-            }
-        };
+                let link_id = raw_tp.attach(&tracepoint)?;
+                let owned_link: RawTracePointLink = raw_tp.take_link(link_id)?;
+                let fd_link: FdLink = owned_link.into();
+                fd_link
+                    .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                    .map_err(BpfmanError::UnableToPinLink)?;
 
-        res
-    }
+                raw_tp
+                    .pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .map_err(BpfmanError::UnableToPinProgram)?;
 
-    pub(crate) async fn remove_program(&mut self, id: u32) -> Result<(), BpfmanError> {
-        info!("Removing program with id: {id}");
-        let prog = match self.programs.remove(&id) {
-            Some(p) => p,
-            None => {
-                return Err(BpfmanError::Error(format!(
-                    "Program {0} does not exist or was not created by bpfman",
-                    id,
-                )));
+                Ok(id)
             }
-        };
+            Program::BtfTracepoint(ref mut program) => {
+                let tracepoint = program.get_tracepoint()?;
+                let btf = Btf::from_sys_fs()?;
+                let tp_btf: &mut BtfTracePoint = raw_program.try_into()?;
+                tp_btf.load(&tracepoint, &btf)?;
+                program.get_data_mut().set_kernel_info(&tp_btf.info()?)?;
 
-        let map_owner_id = prog.get_data().get_map_owner_id()?;
+                let id = program.data.get_id()?;
 
-        match prog {
-            Program::Xdp(_) | Program::Tc(_) => self.remove_multi_attach_program(&prog).await?,
-            Program::Tracepoint(_)
-            | Program::Kprobe(_)
-            | Program::Uprobe(_)
-            | Program::Unsupported(_) => (),
-        }
+                let link_id = tp_btf.attach()?;
+                let owned_link: BtfTracePointLink = tp_btf.take_link(link_id)?;
+                let fd_link: FdLink = owned_link.into();
+                fd_link
+                    .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                    .map_err(BpfmanError::UnableToPinLink)?;
+
+                tp_btf
+                    .pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .map_err(BpfmanError::UnableToPinProgram)?;
+
+                Ok(id)
+            }
+            Program::Fentry(ref mut program) => {
+                let fn_name = program.get_fn_name()?;
+                let btf = Btf::from_sys_fs()?;
+                let fentry: &mut FEntry = raw_program.try_into()?;
+                fentry.load(&fn_name, &btf)?;
+                program.get_data_mut().set_kernel_info(&fentry.info()?)?;
+
+                let id = program.data.get_id()?;
+
+                let link_id = fentry.attach()?;
+                let owned_link: FEntryLink = fentry.take_link(link_id)?;
+                let fd_link: FdLink = owned_link.into();
+                fd_link
+                    .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                    .map_err(BpfmanError::UnableToPinLink)?;
+
+                fentry
+                    .pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .map_err(BpfmanError::UnableToPinProgram)?;
+
+                Ok(id)
+            }
+            Program::Fexit(ref mut program) => {
+                let fn_name = program.get_fn_name()?;
+                let btf = Btf::from_sys_fs()?;
+                let fexit: &mut FExit = raw_program.try_into()?;
+                fexit.load(&fn_name, &btf)?;
+                program.get_data_mut().set_kernel_info(&fexit.info()?)?;
+
+                let id = program.data.get_id()?;
+
+                let link_id = fexit.attach()?;
+                let owned_link: FExitLink = fexit.take_link(link_id)?;
+                let fd_link: FdLink = owned_link.into();
+                fd_link
+                    .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                    .map_err(BpfmanError::UnableToPinLink)?;
+
+                fexit
+                    .pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .map_err(BpfmanError::UnableToPinProgram)?;
+
+                Ok(id)
+            }
+            Program::Lsm(ref mut program) => {
+                // The kernel accepts LSM programs without the bpf LSM, but
+                // they would never run, so fail before loading.
+                if !bpf_lsm_enabled()? {
+                    return Err(BpfmanError::BpfLsmNotEnabled);
+                }
+
+                let hook = program.get_hook()?;
+                let btf = Btf::from_sys_fs()?;
+                let lsm: &mut Lsm = raw_program.try_into()?;
+                lsm.load(&hook, &btf)?;
+                program.get_data_mut().set_kernel_info(&lsm.info()?)?;
+
+                let id = program.data.get_id()?;
+
+                let link_id = lsm.attach()?;
+                let owned_link: LsmLink = lsm.take_link(link_id)?;
+                let fd_link: FdLink = owned_link.into();
+                fd_link
+                    .pin(format!("{RTDIR_FS}/prog_{}_link", id))
+                    .map_err(BpfmanError::UnableToPinLink)?;
+
+                lsm.pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .map_err(BpfmanError::UnableToPinProgram)?;
+
+                Ok(id)
+            }
+            Program::Cgroup(ref mut program) => {
+                let attach_type = program.get_attach_type()?;
+                let cgroup_path = program.get_cgroup_path()?;
+                // Check the cgroup and the attach type before the program is
+                // loaded.
+                open_cgroup(&cgroup_path)?;
+                check_cgroup_section(program.get_data().program_bytes(), name, attach_type)?;
+
+                let id = match raw_program {
+                    AyaProgram::CgroupSkb(skb) => {
+                        check_cgroup_program_type(attach_type, ProgramType::CgroupSkb)?;
+                        skb.load()?;
+                        program.get_data_mut().set_kernel_info(&skb.info()?)?;
+                        let id = program.data.get_id()?;
+                        skb.pin(format!("{RTDIR_FS}/prog_{id}"))
+                            .map_err(BpfmanError::UnableToPinProgram)?;
+                        id
+                    }
+                    AyaProgram::CgroupSock(sock) => {
+                        check_cgroup_program_type(attach_type, ProgramType::CgroupSock)?;
+                        sock.load()?;
+                        program.get_data_mut().set_kernel_info(&sock.info()?)?;
+                        let id = program.data.get_id()?;
+                        sock.pin(format!("{RTDIR_FS}/prog_{id}"))
+                            .map_err(BpfmanError::UnableToPinProgram)?;
+                        id
+                    }
+                    AyaProgram::CgroupSockAddr(sock_addr) => {
+                        check_cgroup_program_type(attach_type, ProgramType::CgroupSockAddr)?;
+                        sock_addr.load()?;
+                        program.get_data_mut().set_kernel_info(&sock_addr.info()?)?;
+                        let id = program.data.get_id()?;
+                        sock_addr
+                            .pin(format!("{RTDIR_FS}/prog_{id}"))
+                            .map_err(BpfmanError::UnableToPinProgram)?;
+                        id
+                    }
+                    AyaProgram::CgroupSockopt(sockopt) => {
+                        check_cgroup_program_type(attach_type, ProgramType::CgroupSockopt)?;
+                        sockopt.load()?;
+                        program.get_data_mut().set_kernel_info(&sockopt.info()?)?;
+                        let id = program.data.get_id()?;
+                        sockopt
+                            .pin(format!("{RTDIR_FS}/prog_{id}"))
+                            .map_err(BpfmanError::UnableToPinProgram)?;
+                        id
+                    }
+                    AyaProgram::CgroupSysctl(sysctl) => {
+                        check_cgroup_program_type(attach_type, ProgramType::CgroupSysctl)?;
+                        sysctl.load()?;
+                        program.get_data_mut().set_kernel_info(&sysctl.info()?)?;
+                        let id = program.data.get_id()?;
+                        sysctl
+                            .pin(format!("{RTDIR_FS}/prog_{id}"))
+                            .map_err(BpfmanError::UnableToPinProgram)?;
+                        id
+                    }
+                    p => {
+                        return Err(BpfmanError::Error(format!(
+                            "{attach_type} needs a {} program, the bytecode has a {:?} program",
+                            attach_type.program_type(),
+                            p.prog_type()
+                        )))
+                    }
+                };
+
+                let attached = match replaces {
+                    // The link is switched to the new program, which keeps the
+                    // old one's place on the hook.
+                    Some(old_id) => take_over_cgroup_link(old_id, id, attach_type),
+                    None => self.attach_new_cgroup_program(
+                        id,
+                        &cgroup_path,
+                        attach_type,
+                        program.get_priority()?,
+                    ),
+                };
+
+                // A program that couldn't be attached is unpinned along with
+                // the rest of a failed load below.
+                attached.map(|()| id)
+            }
+            Program::PerfEvent(ref mut program) => {
+                let event_type = program.get_event_type()?;
+                let config = perf_event_config(event_type, &program.get_event()?)?;
+                let sample_policy = sample_policy(
+                    program.get_sample_period()?,
+                    program.get_sample_frequency()?,
+                )?;
+                let pid = program.get_pid()?;
+                // Without a pid, the program runs for every process, which
+                // takes a perf event on each CPU.
+                let cpus = match (program.get_cpu()?, pid) {
+                    (Some(cpu), _) => vec![Some(cpu)],
+                    (None, Some(_)) => vec![None],
+                    (None, None) => online_cpus()
+                        .map_err(|(path, e)| {
+                            BpfmanError::Error(format!("unable to read {path}: {e}"))
+                        })?
+                        .into_iter()
+                        .map(Some)
+                        .collect(),
+                };
+
+                let perf_event: &mut PerfEvent = raw_program.try_into()?;
+                perf_event.load()?;
+                program
+                    .get_data_mut()
+                    .set_kernel_info(&perf_event.info()?)?;
+
+                let id = program.data.get_id()?;
+
+                // The program is pinned first, so that the links already
+                // pinned are removed along with it if an attach fails.
+                perf_event
+                    .pin(format!("{RTDIR_FS}/prog_{id}"))
+                    .map_err(BpfmanError::UnableToPinProgram)?;
+
+                for cpu in cpus {
+                    let link_id = perf_event.attach(
+                        perf_type_id(event_type),
+                        config,
+                        perf_event_scope(cpu, pid),
+                        sample_policy.clone(),
+                    )?;
+                    let fd_link: FdLink =
+                        perf_event.take_link(link_id)?.try_into().map_err(|_| {
+                            BpfmanError::Error(
+                                "perf_event programs need a kernel with bpf link support for perf events (5.15 or later)"
+                                    .to_string(),
+                            )
+                        })?;
+                    let link_pin_path = match (cpu, pid) {
+                        (Some(cpu), None) => format!("{RTDIR_FS}/prog_{id}_link_cpu{cpu}"),
+                        _ => format!("{RTDIR_FS}/prog_{id}_link"),
+                    };
+                    fd_link
+                        .pin(link_pin_path)
+                        .map_err(BpfmanError::UnableToPinLink)?;
+                }
+
+                Ok(id)
+            }
+            _ => panic!("not a supported single attach program"),
+        };
+
+        match res {
+            Ok(id) => {
+                // If this program is the map(s) owner pin all maps (except for .rodata and .bss) by name.
+                if p.get_data().get_map_pin_path()?.is_none() {
+                    let map_pin_path = calc_map_pin_path(id);
+                    p.get_data_mut().set_map_pin_path(&map_pin_path)?;
+                    create_map_pin_path(&map_pin_path).await?;
+
+                    for (name, map) in loader.maps_mut() {
+                        if !should_map_be_pinned(name) {
+                            continue;
+                        }
+                        debug!(
+                            "Pinning map: {name} to path: {}",
+                            map_pin_path.join(name).display()
+                        );
+                        map.pin(map_pin_path.join(name))
+                            .map_err(BpfmanError::UnableToPinMap)?;
+                    }
+                }
+            }
+            Err(_) => {
+                // If kernel ID was never set there's no pins to cleanup here so just continue
+                if p.get_data().get_id().is_ok() {
+                    p.delete().map_err(BpfmanError::BpfmanProgramDeleteError)?;
+                };
+            }
+        };
+
+        res
+    }
+
+    pub(crate) async fn attach_program(
+        &mut self,
+        id: u32,
+        mut link: Program,
+    ) -> Result<u32, BpfmanError> {
+        info!("Attaching program with id: {id}");
+        let program = match self.programs.get(&id) {
+            Some(p) => p.to_owned(),
+            None => {
+                return Err(BpfmanError::Error(format!(
+                    "Program {0} does not exist or was not created by bpfman",
+                    id,
+                )));
+            }
+        };
+
+        match (&program, &link) {
+            (Program::Xdp(_), Program::Xdp(_))
+            | (Program::Tc(_), Program::Tc(_))
+            | (Program::Tracepoint(_), Program::Tracepoint(_))
+            | (Program::Kprobe(_), Program::Kprobe(_))
+            | (Program::Uprobe(_), Program::Uprobe(_)) => (),
+            (Program::RawTracepoint(_), _)
+            | (Program::BtfTracepoint(_), _)
+            | (Program::Fentry(_), _)
+            | (Program::Fexit(_), _)
+            | (Program::Lsm(_), _)
+            | (Program::Cgroup(_), _)
+            | (Program::PerfEvent(_), _)
+            | (Program::Unsupported(_), _) => {
+                return Err(BpfmanError::Error(format!(
+                    "{} programs can only be attached when they are loaded",
+                    program.kind()
+                )));
+            }
+            _ => {
+                return Err(BpfmanError::Error(format!(
+                    "attach info does not match the type of program {id}"
+                )));
+            }
+        }
+
+        let link_id = self.programs.next_link_id();
+        link.get_data_mut().set_link(program.get_data(), link_id)?;
+
+        let result = match link {
+            Program::Xdp(_) | Program::Tc(_) => self.attach_multi_attach_link(&mut link).await,
+            _ => self.attach_single_attach_link(&link),
+        };
+
+        if let Err(e) = result {
+            // Only the link's own pin and database tree are removed here,
+            // the program stays loaded.
+            link.delete()
+                .map_err(BpfmanError::BpfmanProgramDeleteError)?;
+            return Err(e);
+        }
+
+        if let Some(p) = self.programs.get_mut(&id) {
+            let mut link_ids = p.get_data().get_link_ids()?;
+            link_ids.push(link_id);
+            p.get_data_mut().set_link_ids(link_ids)?;
+        }
+        send_event(&self.events, ProgramEventType::Attached, &link);
+        self.programs.insert_link(link_id, link);
+
+        info!("Attached program with id: {id} with link id: {link_id}");
+
+        Ok(link_id)
+    }
+
+    async fn attach_multi_attach_link(&mut self, link: &mut Program) -> Result<(), BpfmanError> {
+        debug!("BpfManager::attach_multi_attach_link()");
+        link.set_if_index(get_ifindex(&link.if_name()?)?)?;
+
+        let id = link.get_data().get_id()?;
+        let program_type = link.kind();
+        let if_index = link.if_index()?;
+        let direction = link.direction()?;
+
+        // A dispatcher references its extensions by program, so a program can
+        // only be attached once to each dispatcher.
+        if self
+            .programs
+            .programs_mut(&program_type, &if_index, &direction)
+            .any(|p| p.get_data().get_id().ok() == Some(id))
+        {
+            return Err(BpfmanError::Error(format!(
+                "program {id} is already attached to {}",
+                link.if_name()?
+            )));
+        }
+
+        let did = link
+            .dispatcher_id()?
+            .ok_or(BpfmanError::DispatcherNotRequired)?;
+
+        let next_available_id = self.dispatchers.attached_programs(&did);
+        if next_available_id >= 10 {
+            return Err(BpfmanError::TooManyPrograms);
+        }
+
+        debug!("next_available_id={next_available_id}");
+
+        // The program is already loaded, so the dispatcher attaches it from
+        // its pin rather than loading it again.
+        link.set_attached();
+
+        self.attach_to_dispatcher(link).await
+    }
+
+    fn attach_single_attach_link(&self, link: &Program) -> Result<(), BpfmanError> {
+        debug!("BpfManager::attach_single_attach_link()");
+        let id = link.get_data().get_id()?;
+        let link_id = link
+            .get_data()
+            .get_link_id()?
+            .ok_or_else(|| BpfmanError::Error(format!("program {id} has no link id")))?;
+
+        let program_pin_path = format!("{RTDIR_FS}/prog_{id}");
+        let link_pin_path = format!("{RTDIR_FS}/prog_{id}_link_{link_id}");
+
+        match link {
+            Program::Tracepoint(link) => {
+                let tracepoint = link.get_tracepoint()?;
+                let parts: Vec<&str> = tracepoint.split('/').collect();
+                if parts.len() != 2 {
+                    return Err(BpfmanError::InvalidAttach(tracepoint));
+                }
+
+                let mut tracepoint = TracePoint::from_pin(&program_pin_path)?;
+                let link_id = tracepoint.attach(parts[0], parts[1])?;
+
+                let owned_link: TracePointLink = tracepoint.take_link(link_id)?;
+                let fd_link: FdLink = owned_link.try_into().map_err(|e| {
+                    BpfmanError::Error(format!("unable to get owned tracepoint attach link: {e}"))
+                })?;
+
+                fd_link
+                    .pin(link_pin_path)
+                    .map_err(BpfmanError::UnableToPinLink)?;
+            }
+            Program::Kprobe(link) => {
+                let probe_kind = match link.get_retprobe()? {
+                    true => ProbeKind::KRetProbe,
+                    false => ProbeKind::KProbe,
+                };
+
+                if link.get_retprobe()? && link.get_offset()? != 0 {
+                    return Err(BpfmanError::Error(format!(
+                        "offset not allowed for {Kretprobe}"
+                    )));
+                }
+
+                match link.get_container_pid()? {
+                    None => {
+                        let mut kprobe = KProbe::from_pin(&program_pin_path, probe_kind)?;
+                        let link_id = kprobe.attach(link.get_fn_name()?, link.get_offset()?)?;
+
+                        let owned_link: KProbeLink = kprobe.take_link(link_id)?;
+                        let fd_link: FdLink = owned_link.try_into().map_err(|e| {
+                            BpfmanError::Error(format!(
+                                "unable to get owned kprobe attach link: {e}"
+                            ))
+                        })?;
+
+                        fd_link
+                            .pin(link_pin_path)
+                            .map_err(BpfmanError::UnableToPinLink)?;
+                    }
+                    Some(p) => {
+                        let prog_args =
+                            kprobe_container_args(link, program_pin_path, Some(link_pin_path), p)?;
+                        let link_id = self.attach_in_container("kprobe", p, prog_args)?;
+                        debug!("kprobe attached in container {p} with link id {link_id}");
+                    }
+                }
+            }
+            Program::Uprobe(link) => {
+                let probe_kind = match link.get_retprobe()? {
+                    true => ProbeKind::URetProbe,
+                    false => ProbeKind::UProbe,
+                };
+                let fn_name = link.get_fn_name()?;
+
+                match link.get_container_pid()? {
+                    None => {
+                        let mut uprobe = UProbe::from_pin(&program_pin_path, probe_kind)?;
+                        let link_id = uprobe.attach(
+                            fn_name.as_deref(),
+                            link.get_offset()?,
+                            link.get_target()?,
+                            link.get_pid()?,
+                        )?;
+
+                        let owned_link: UProbeLink = uprobe.take_link(link_id)?;
+                        let fd_link: FdLink = owned_link.try_into().map_err(|e| {
+                            BpfmanError::Error(format!(
+                                "unable to get owned uprobe attach link: {e}"
+                            ))
+                        })?;
+
+                        fd_link
+                            .pin(link_pin_path)
+                            .map_err(BpfmanError::UnableToPinLink)?;
+                    }
+                    Some(p) => {
+                        let mut prog_args = vec![
+                            "uprobe".to_string(),
+                            "--program-pin-path".to_string(),
+                            program_pin_path,
+                            "--link-pin-path".to_string(),
+                            link_pin_path,
+                            "--offset".to_string(),
+                            link.get_offset()?.to_string(),
+                            "--target".to_string(),
+                            link.get_target()?,
+                            "--container-pid".to_string(),
+                            p.to_string(),
+                        ];
+
+                        if let Some(fn_name) = fn_name {
+                            prog_args.extend(["--fn-name".to_string(), fn_name])
+                        }
+
+                        if link.get_retprobe()? {
+                            prog_args.push("--retprobe".to_string());
+                        }
+
+                        if let Some(pid) = link.get_pid()? {
+                            prog_args.extend(["--pid".to_string(), pid.to_string()])
+                        }
+
+                        let link_id = self.attach_in_container("uprobe", p, prog_args)?;
+                        debug!("uprobe attached in container {p} with link id {link_id}");
+                    }
+                }
+            }
+            _ => {
+                return Err(BpfmanError::Error(format!(
+                    "{} programs can't be attached with a link",
+                    link.kind()
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn detach_link(&mut self, link_id: u32) -> Result<(), BpfmanError> {
+        info!("Detaching link with id: {link_id}");
+        let link = match self.programs.remove_link(&link_id) {
+            Some(l) => l,
+            None => {
+                return Err(BpfmanError::Error(format!(
+                    "Link {0} does not exist or was not created by bpfman",
+                    link_id,
+                )));
+            }
+        };
+
+        if matches!(link, Program::Xdp(_) | Program::Tc(_)) {
+            self.remove_multi_attach_program(&link).await?;
+        }
+
+        let id = link.get_data().get_id()?;
+        if let Some(p) = self.programs.get_mut(&id) {
+            let link_ids = p
+                .get_data()
+                .get_link_ids()?
+                .into_iter()
+                .filter(|l| *l != link_id)
+                .collect();
+            p.get_data_mut().set_link_ids(link_ids)?;
+        }
+
+        send_event(&self.events, ProgramEventType::Detached, &link);
+        link.delete()
+            .map_err(BpfmanError::BpfmanProgramDeleteError)?;
+
+        Ok(())
+    }
+
+    // Detaches the attachment a tracepoint or probe program was loaded with.
+    // The program stays loaded, the same as one loaded without attach info.
+    pub(crate) fn detach_program(&mut self, id: u32) -> Result<(), BpfmanError> {
+        info!("Detaching program with id: {id}");
+        let program = match self.programs.get_mut(&id) {
+            Some(p) => p,
+            None => {
+                return Err(BpfmanError::Error(format!(
+                    "Program {0} does not exist or was not created by bpfman",
+                    id,
+                )));
+            }
+        };
+
+        if !matches!(
+            program,
+            Program::Tracepoint(_) | Program::Kprobe(_) | Program::Uprobe(_)
+        ) {
+            return Err(BpfmanError::Error(format!(
+                "{} programs stay attached until they are unloaded",
+                program.kind()
+            )));
+        }
+        if program.get_data().get_load_only()? {
+            return Err(BpfmanError::Error(format!("Program {id} is not attached")));
+        }
+
+        // The pin holds the only reference to the link, removing it detaches
+        // the program.
+        std::fs::remove_file(format!("{RTDIR_FS}/prog_{id}_link"))?;
+        program.get_data_mut().set_load_only(true)?;
+
+        send_event(&self.events, ProgramEventType::Detached, program);
+
+        Ok(())
+    }
+
+    pub(crate) async fn update_program(
+        &mut self,
+        id: u32,
+        priority: Option<i32>,
+        proceed_on: Option<Vec<i32>>,
+        metadata: Option<HashMap<String, String>>,
+    ) -> Result<Program, BpfmanError> {
+        info!("Updating program with id: {id}");
+        if self.programs.get(&id).is_none() && self.programs.get_link(&id).is_some() {
+            return Err(BpfmanError::Error(format!(
+                "{id} is a link, links keep the priority and proceed_on they were attached with. Detach it and attach it again to change them"
+            )));
+        }
+        let program = match self.programs.get_mut(&id) {
+            Some(p) => p,
+            None => {
+                return Err(BpfmanError::Error(format!(
+                    "Program {0} does not exist or was not created by bpfman",
+                    id,
+                )));
+            }
+        };
+
+        // Cgroup programs are ordered by priority, but the kernel runs every
+        // program on a cgroup hook, so they have no proceed_on.
+        let cgroup_hook = match program {
+            Program::Cgroup(p) => {
+                if proceed_on.is_some() {
+                    return Err(BpfmanError::Error(
+                        "proceed_on cannot be set for cgroup programs".to_string(),
+                    ));
+                }
+                Some((p.get_cgroup_path()?, p.get_attach_type()?))
+            }
+            _ => None,
+        };
+
+        let rebuild_dispatcher =
+            cgroup_hook.is_none() && (priority.is_some() || proceed_on.is_some());
+        let dispatcher = if rebuild_dispatcher {
+            // The program keeps its kernel id and maps, only the dispatcher it
+            // is attached through is replaced.
+            let did = program.dispatcher_id()?.ok_or_else(|| {
+                BpfmanError::Error(format!(
+                    "priority and proceed_on can only be updated for {}, {} and cgroup programs",
+                    ProgramType::Xdp,
+                    ProgramType::Tc
+                ))
+            })?;
+            let if_index = program
+                .if_index()?
+                .expect("if_index should be known at this point");
+            Some((did, if_index, program.kind(), program.direction()?))
+        } else {
+            None
+        };
+
+        // The values being replaced are kept so they can be put back if the
+        // program can't be reattached with the new ones.
+        let old_order = set_order(program, priority, proceed_on)?;
+
+        match (dispatcher, &cgroup_hook) {
+            (Some((did, if_index, program_type, direction)), _) => {
+                let res = self
+                    .rebuild_multiattach_dispatcher(did, if_index, program_type, direction)
+                    .await;
+                if res.is_err() {
+                    self.restore_order(id, &old_order);
+                    self.programs
+                        .set_program_positions(program_type, if_index, direction);
+                }
+                res
+            }
+            (None, Some((cgroup_path, attach_type))) if priority.is_some() => {
+                let res = self.order_cgroup_programs(cgroup_path, *attach_type);
+                if res.is_err() {
+                    self.restore_order(id, &old_order);
+                }
+                res
+            }
+            _ => Ok(()),
+        }?;
+
+        // Metadata is only written once the program is attached with its new
+        // priority and proceed_on.
+        if let Some(metadata) = metadata {
+            if let Some(program) = self.programs.get_mut(&id) {
+                program.get_data_mut().set_metadata(metadata)?;
+            }
+        }
+
+        let program = self.get_program(id)?;
+        send_event(&self.events, ProgramEventType::Updated, &program);
+
+        Ok(program)
+    }
+
+    // Puts back the priority and proceed_on an update replaced. The update's
+    // own error is what's returned to the caller, so this one is only logged.
+    fn restore_order(&mut self, id: u32, old_order: &(Option<i32>, Option<Vec<i32>>)) {
+        if let Some(program) = self.programs.get_mut(&id) {
+            if let Err(e) = set_order(program, old_order.0, old_order.1.clone()) {
+                warn!("Unable to restore the priority of program {id}: {e}");
+            }
+        }
+    }
+
+    pub(crate) async fn replace_program(
+        &mut self,
+        id: u32,
+        location: Location,
+        name: Option<String>,
+        global_data: Option<HashMap<String, Vec<u8>>>,
+    ) -> Result<Program, BpfmanError> {
+        info!("Replacing program with id: {id}");
+        let old = match self.programs.get(&id) {
+            Some(p) => p,
+            None => {
+                return Err(BpfmanError::Error(format!(
+                    "Program {0} does not exist or was not created by bpfman",
+                    id,
+                )));
+            }
+        };
+
+        if !old.get_data().get_link_ids()?.is_empty() {
+            return Err(BpfmanError::Error(format!(
+                "Program {id} has links which must be detached before it is replaced"
+            )));
+        }
+
+        let map_owner_id = old.get_data().get_map_owner_id()?;
+        let mut program = replacement_program(id, old, location, name, global_data)?;
+
+        program
+            .get_data_mut()
+            .set_program_bytes(self.image_manager.clone())
+            .await?;
+
+        let old = self
+            .programs
+            .remove(&id)
+            .expect("program should exist at this point");
+
+        let result = match program {
+            // The new program takes the old one's place in the dispatcher.
+            Program::Xdp(_) | Program::Tc(_) => match verify_extension_name(&program) {
+                Ok(()) => self.load_multi_attach_program(&mut program).await,
+                Err(e) => Err(e),
+            },
+            // The cgroup link is updated to run the new program in place.
+            Program::Cgroup(_) => self.add_single_attach_program(&mut program, Some(id)).await,
+            // The kernel can't update the links of these programs, so the new
+            // program is attached before the old one's link is removed, and
+            // events may briefly be seen by both.
+            Program::Tracepoint(_)
+            | Program::RawTracepoint(_)
+            | Program::BtfTracepoint(_)
+            | Program::Kprobe(_)
+            | Program::Uprobe(_)
+            | Program::Fentry(_)
+            | Program::Fexit(_)
+            | Program::Lsm(_)
+            | Program::PerfEvent(_) => self.add_single_attach_program(&mut program, None).await,
+            Program::Unsupported(_) => Err(BpfmanError::Error(format!(
+                "Program {id} has an unsupported type and can't be replaced"
+            ))),
+        };
+
+        // Program bytes MUST be cleared after load.
+        program.get_data_mut().clear_program_bytes();
+
+        let new_id = match result {
+            Ok(new_id) => new_id,
+            Err(e) => {
+                self.programs.insert(id, old);
+                return Err(e);
+            }
+        };
+
+        info!("Replaced {} program {id} with id: {new_id}", program.kind());
+
+        program.get_data_mut().swap_tree(new_id)?;
+        self.replace_map_user(id, new_id, map_owner_id, &mut program)?;
+        self.programs.insert(new_id, program.to_owned());
+
+        match ProgramEvent::new(ProgramEventType::Replaced, &program) {
+            Ok(mut event) => {
+                event.replaced_id = Some(id);
+                let _ = self.events.send(event);
+            }
+            Err(e) => warn!("unable to create {} event: {e}", ProgramEventType::Replaced),
+        }
+
+        old.delete()
+            .map_err(BpfmanError::BpfmanProgramDeleteError)?;
+
+        Ok(program)
+    }
+
+    // Attaches a new cgroup program with a link pinned at prog_{id}_link. It
+    // runs before the first program on its hook with a higher priority, so
+    // ties run in the order they were added. Where the kernel can't attach a
+    // program before another, it's attached last here and moved to its
+    // position once it has been added.
+    fn attach_new_cgroup_program(
+        &self,
+        id: u32,
+        cgroup_path: &str,
+        attach_type: CgroupAttachType,
+        priority: i32,
+    ) -> Result<(), BpfmanError> {
+        let link_pin_path = format!("{RTDIR_FS}/prog_{id}_link");
+        let before = self
+            .cgroup_hook_order(cgroup_path, attach_type)?
+            .into_iter()
+            .find(|(_, p)| *p > priority)
+            .map(|(id, _)| id);
+        match before {
+            Some(before) if cgroup_ordering_supported(id, attach_type)? => {
+                attach_cgroup_program_before(id, cgroup_path, attach_type, before, &link_pin_path)
+            }
+            _ => attach_cgroup_program(id, cgroup_path, attach_type, &link_pin_path),
+        }
+    }
+
+    // Keeps the programs attached to a cgroup with the same attach type in
+    // priority order, with ties in the order they run now, then reads each
+    // program's position back from the kernel. A program that can't be moved
+    // stays where it was, and the positions still match the kernel.
+    fn order_cgroup_programs(
+        &mut self,
+        cgroup_path: &str,
+        attach_type: CgroupAttachType,
+    ) -> Result<(), BpfmanError> {
+        let res = self.move_cgroup_programs(cgroup_path, attach_type);
+
+        let positions = self
+            .cgroup_hook_order(cgroup_path, attach_type)
+            .and_then(|order| {
+                for (position, (id, _)) in order.iter().enumerate() {
+                    if let Some(p) = self.programs.get_mut(id) {
+                        p.set_position(position)?;
+                    }
+                }
+                Ok(())
+            });
+        res.and(positions)
+    }
+
+    // Attaches the programs that are out of place again one at a time, so
+    // only the program being moved ever runs twice.
+    fn move_cgroup_programs(
+        &self,
+        cgroup_path: &str,
+        attach_type: CgroupAttachType,
+    ) -> Result<(), BpfmanError> {
+        let current = self.cgroup_hook_order(cgroup_path, attach_type)?;
+        if current.windows(2).all(|w| w[0].1 <= w[1].1) {
+            return Ok(());
+        }
+        let ordering_supported = cgroup_ordering_supported(current[0].0, attach_type)?;
+        for (id, before) in cgroup_moves(&current, ordering_supported) {
+            debug!("Attaching cgroup program {id} again to keep its hook in priority order");
+            reattach_cgroup_program(id, cgroup_path, attach_type, before)?;
+        }
+        Ok(())
+    }
+
+    // Returns the id and priority of each program bpfman has attached to the
+    // cgroup with the attach type, in the order the kernel runs them.
+    fn cgroup_hook_order(
+        &self,
+        cgroup_path: &str,
+        attach_type: CgroupAttachType,
+    ) -> Result<Vec<(u32, i32)>, BpfmanError> {
+        let cgroup = open_cgroup(cgroup_path)?;
+        let ids = cgroup_programs(cgroup.as_fd(), attach_type).map_err(|e| {
+            BpfmanError::Error(format!(
+                "unable to list the {attach_type} programs of {cgroup_path}: {e}"
+            ))
+        })?;
+        let mut order = vec![];
+        for id in ids {
+            if let Some(Program::Cgroup(p)) = self.programs.get(&id) {
+                order.push((id, p.get_priority()?));
+            }
+        }
+        Ok(order)
+    }
+
+    // Returns when the next program loaded with a ttl expires.
+    fn next_expiry(&self) -> Option<SystemTime> {
+        self.programs
+            .get_programs_iter()
+            .filter_map(|(_, p)| p.get_data().get_expires_at().ok().flatten())
+            .min()
+    }
+
+    // Unloads the programs whose ttl has passed, including any that expired
+    // while bpfman was stopped.
+    pub(crate) async fn remove_expired_programs(&mut self) {
+        let now = SystemTime::now();
+        let ids: Vec<u32> = self
+            .programs
+            .get_programs_iter()
+            .filter(|(_, p)| {
+                p.get_data()
+                    .get_expires_at()
+                    .ok()
+                    .flatten()
+                    .map_or(false, |t| t <= now)
+            })
+            .map(|(id, _)| id)
+            .collect();
+
+        for id in ids {
+            info!("Unloading program {id} as its ttl has expired");
+            if let Err(e) = self.remove_program(id).await {
+                warn!("Unable to unload program {id}: {e}");
+            }
+        }
+    }
+
+    /// The processes that loaded programs are bound to.
+    pub(crate) fn bound_processes(&self) -> HashSet<BoundProcess> {
+        self.programs
+            .get_programs_iter()
+            .filter_map(|(_, p)| p.get_data().get_bound_process().ok().flatten())
+            .collect()
+    }
+
+    // Unloads the programs bound to a process that has exited.
+    pub(crate) async fn remove_bound_programs(&mut self, process: BoundProcess) {
+        let ids: Vec<u32> = self
+            .programs
+            .get_programs_iter()
+            .filter(|(_, p)| p.get_data().get_bound_process().ok().flatten() == Some(process))
+            .map(|(id, _)| id)
+            .collect();
+
+        for id in ids {
+            info!(
+                "Unloading program {id} as process {} has exited",
+                process.pid
+            );
+            if let Err(e) = self.remove_program(id).await {
+                warn!("Unable to unload program {id}: {e}");
+            }
+        }
+    }
+
+    pub(crate) async fn remove_program(&mut self, id: u32) -> Result<(), BpfmanError> {
+        info!("Removing program with id: {id}");
+
+        // A program's links are removed along with it.
+        let link_ids = match self.programs.get(&id) {
+            Some(p) => p.get_data().get_link_ids()?,
+            None => vec![],
+        };
+        for link_id in link_ids {
+            self.detach_link(link_id).await?;
+        }
+
+        let prog = match self.programs.remove(&id) {
+            Some(p) => p,
+            None => {
+                return Err(BpfmanError::Error(format!(
+                    "Program {0} does not exist or was not created by bpfman",
+                    id,
+                )));
+            }
+        };
+
+        let map_owner_id = prog.get_data().get_map_owner_id()?;
+
+        match prog {
+            Program::Xdp(_) | Program::Tc(_) => self.remove_multi_attach_program(&prog).await?,
+            Program::Tracepoint(_)
+            | Program::RawTracepoint(_)
+            | Program::BtfTracepoint(_)
+            | Program::Kprobe(_)
+            | Program::Uprobe(_)
+            | Program::Fentry(_)
+            | Program::Fexit(_)
+            | Program::Lsm(_)
+            | Program::Cgroup(_)
+            | Program::PerfEvent(_)
+            | Program::Unsupported(_) => (),
+        }
 
         self.delete_map(id, map_owner_id).await?;
 
+        let cgroup_hook = match &prog {
+            Program::Cgroup(p) => Some((p.get_cgroup_path()?, p.get_attach_type()?)),
+            _ => None,
+        };
+
+        send_event(&self.events, ProgramEventType::Unloaded, &prog);
         prog.delete()
             .map_err(BpfmanError::BpfmanProgramDeleteError)?;
 
+        // The programs after it on its cgroup hook move up a position. The
+        // program is already unloaded, so this can't fail the unload, for
+        // example when the cgroup has been removed along with its programs.
+        if let Some((cgroup_path, attach_type)) = cgroup_hook {
+            if let Err(e) = self.order_cgroup_programs(&cgroup_path, attach_type) {
+                warn!("Unable to update the positions of the programs on {cgroup_path}: {e}");
+            }
+        }
+
         Ok(())
     }
 
@@ -790,6 +2191,7 @@ impl BpfManager {
             self.image_manager.clone(),
         )
         .await?;
+        send_dispatcher_events(&self.events, &programs);
         self.dispatchers.insert(did, dispatcher);
         Ok(())
     }
@@ -844,6 +2246,7 @@ impl BpfManager {
                 self.image_manager.clone(),
             )
             .await?;
+            send_dispatcher_events(&self.events, &programs);
             self.dispatchers.insert(did, dispatcher);
         } else {
             debug!("No dispatcher found in rebuild_multiattach_dispatcher() for {did:?}");
@@ -851,6 +2254,222 @@ impl BpfManager {
         Ok(())
     }
 
+    pub(crate) fn map_operation(
+        &self,
+        id: u32,
+        map_name: &str,
+        operation: MapOperation,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, BpfmanError> {
+        debug!("BpfManager::map_operation() for map {map_name} of program {id}");
+
+        // The map name is used as a file name in the program's map pin path.
+        if map_name.is_empty() || map_name.contains('/') || map_name == "." || map_name == ".." {
+            return Err(BpfmanError::Error(format!(
+                "{map_name} is not a valid map name"
+            )));
+        }
+
+        let map_pin_path = match self.programs.get(&id) {
+            Some(p) => p.get_data().get_map_pin_path()?.ok_or_else(|| {
+                BpfmanError::Error(format!("map_pin_path should be set for {id}"))
+            })?,
+            None => {
+                return Err(BpfmanError::Error(format!(
+                    "Program {0} does not exist or was not created by bpfman",
+                    id,
+                )));
+            }
+        };
+
+        let map = PinnedMap::open(map_pin_path.join(map_name))?;
+
+        match operation {
+            MapOperation::Lookup { key } => {
+                let value = map.lookup(&key)?;
+                Ok(vec![(key, value)])
+            }
+            MapOperation::Update { key, value, flags } => {
+                map.update(&key, &value, flags)?;
+                Ok(vec![])
+            }
+            MapOperation::Delete { key } => {
+                map.delete(&key)?;
+                Ok(vec![])
+            }
+            MapOperation::Dump => map.dump(),
+        }
+    }
+
+    // Loads a program so that the kernel runs the verifier over it. Nothing is
+    // attached, pinned or saved to the database, and the program and its maps
+    // are unloaded again when the loader is dropped.
+    pub(crate) async fn verify_program(
+        &self,
+        args: &VerifyArgs,
+    ) -> Result<VerifyResult, BpfmanError> {
+        debug!("BpfManager::verify_program()");
+
+        let (bytes, image_name, _) = args
+            .location
+            .get_program_bytes(self.image_manager.clone())
+            .await?;
+
+        // As with a load, the program name defaults to the one in the image
+        // metadata and must match it when provided.
+        let name = match args.location {
+            Location::Image(_) if args.name.is_empty() => image_name,
+            Location::Image(_) if args.name != image_name => {
+                return Err(BpfmanError::BytecodeMetaDataMismatch {
+                    image_prog_name: image_name,
+                    provided_prog_name: args.name.clone(),
+                });
+            }
+            _ => args.name.clone(),
+        };
+
+        // Maps that are pinned by name would otherwise be pinned under
+        // /sys/fs/bpf, so they are pinned to a scratch directory that is removed
+        // once the program has been verified.
+        let pin_dir = tempfile::Builder::new()
+            .prefix("verify_")
+            .tempdir_in(RTDIR_FS)?;
+
+        let mut bpf = BpfLoader::new();
+        for (key, value) in &args.global_data {
+            bpf.set_global(key, value.as_slice(), true);
+        }
+        if let Some(level) = args.verifier_log_level {
+            bpf.verifier_log_level(VerifierLogLevel::from_bits_truncate(level));
+        }
+
+        let mut loader = bpf
+            .allow_unsupported_maps()
+            .map_pin_path(pin_dir.path())
+            .load(&bytes)?;
+
+        let raw_program = loader
+            .program_mut(&name)
+            .ok_or(BpfmanError::BpfFunctionNameNotValid(name.clone()))?;
+
+        // The caller's rules are checked before the program reaches the kernel.
+        if let Some(types) = &args.program_types {
+            let kind = match raw_program {
+                AyaProgram::Xdp(_) => Some(ProgramType::Xdp),
+                AyaProgram::SchedClassifier(_) => Some(ProgramType::Tc),
+                AyaProgram::TracePoint(_) => Some(ProgramType::Tracepoint),
+                AyaProgram::KProbe(_) | AyaProgram::UProbe(_) => Some(ProgramType::Probe),
+                AyaProgram::FEntry(_) | AyaProgram::FExit(_) | AyaProgram::BtfTracePoint(_) => {
+                    Some(ProgramType::Tracing)
+                }
+                AyaProgram::RawTracePoint(_) => Some(ProgramType::RawTracepoint),
+                AyaProgram::Lsm(_) => Some(ProgramType::Lsm),
+                AyaProgram::CgroupSkb(_) => Some(ProgramType::CgroupSkb),
+                AyaProgram::CgroupSock(_) => Some(ProgramType::CgroupSock),
+                AyaProgram::CgroupSockAddr(_) => Some(ProgramType::CgroupSockAddr),
+                AyaProgram::CgroupSockopt(_) => Some(ProgramType::CgroupSockopt),
+                AyaProgram::CgroupSysctl(_) => Some(ProgramType::CgroupSysctl),
+                AyaProgram::PerfEvent(_) => Some(ProgramType::PerfEvent),
+                _ => None,
+            };
+            if let Some(kind) = kind.filter(|k| !types.contains(k)) {
+                return Err(BpfmanError::ProgramTypeNotAllowed(kind.to_string()));
+            }
+        }
+
+        let info = match raw_program {
+            AyaProgram::Xdp(p) => {
+                p.load()?;
+                p.info()?
+            }
+            AyaProgram::SchedClassifier(p) => {
+                p.load()?;
+                p.info()?
+            }
+            AyaProgram::TracePoint(p) => {
+                p.load()?;
+                p.info()?
+            }
+            AyaProgram::KProbe(p) => {
+                p.load()?;
+                p.info()?
+            }
+            AyaProgram::UProbe(p) => {
+                p.load()?;
+                p.info()?
+            }
+            AyaProgram::FEntry(p) => {
+                p.load(verify_fn_name(args)?, &Btf::from_sys_fs()?)?;
+                p.info()?
+            }
+            AyaProgram::FExit(p) => {
+                p.load(verify_fn_name(args)?, &Btf::from_sys_fs()?)?;
+                p.info()?
+            }
+            AyaProgram::Lsm(p) => {
+                p.load(verify_fn_name(args)?, &Btf::from_sys_fs()?)?;
+                p.info()?
+            }
+            AyaProgram::RawTracePoint(p) => {
+                p.load()?;
+                p.info()?
+            }
+            AyaProgram::BtfTracePoint(p) => {
+                p.load(verify_fn_name(args)?, &Btf::from_sys_fs()?)?;
+                p.info()?
+            }
+            AyaProgram::CgroupSkb(p) => {
+                p.load()?;
+                p.info()?
+            }
+            AyaProgram::CgroupSock(p) => {
+                p.load()?;
+                p.info()?
+            }
+            AyaProgram::CgroupSockAddr(p) => {
+                p.load()?;
+                p.info()?
+            }
+            AyaProgram::CgroupSockopt(p) => {
+                p.load()?;
+                p.info()?
+            }
+            AyaProgram::CgroupSysctl(p) => {
+                p.load()?;
+                p.info()?
+            }
+            AyaProgram::PerfEvent(p) => {
+                p.load()?;
+                p.info()?
+            }
+            p => {
+                return Err(BpfmanError::Error(format!(
+                    "verifying {:?} programs is not supported",
+                    p.prog_type()
+                )))
+            }
+        };
+
+        let maps = info
+            .map_ids()?
+            .into_iter()
+            .map(map_info_by_id)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        info!(
+            "Verified program {name} with {} instructions",
+            info.verified_instruction_count()
+        );
+
+        Ok(VerifyResult {
+            name,
+            program_type: info.program_type(),
+            verified_insns: info.verified_instruction_count(),
+            bytes_xlated: info.size_translated(),
+            bytes_jited: info.size_jitted(),
+            maps,
+        })
+    }
+
     pub(crate) fn list_programs(&mut self) -> Result<Vec<Program>, BpfmanError> {
         debug!("BpfManager::list_programs()");
 
@@ -925,9 +2544,9 @@ impl BpfManager {
             })
             .await?;
         let res = match rx.await? {
-            Ok(_) => {
+            Ok(image) => {
                 info!("Successfully pulled bytecode");
-                Ok(())
+                Ok(image.digest)
             }
             Err(e) => Err(BpfmanError::BpfBytecodeError(e)),
         };
@@ -937,6 +2556,7 @@ impl BpfManager {
 
     pub(crate) async fn process_commands(&mut self, mut shutdown_channel: broadcast::Receiver<()>) {
         loop {
+            let next_expiry = self.next_expiry();
             // Start receiving messages
             select! {
                 biased;
@@ -945,10 +2565,22 @@ impl BpfManager {
                     ROOT_DB.flush().expect("Unable to flush database to disk before shutting down BpfManager");
                     break;
                 }
+                _ = sleep_until_expiry(next_expiry) => {
+                    self.remove_expired_programs().await;
+                }
                 Some(cmd) = self.commands.recv() => {
                     match cmd {
                         Command::Load(args) => {
-                            let prog = self.add_program(args.program).await;
+                            let prog = match self.check_map_owner(&args.program, &args.owner) {
+                                Ok(()) => self.add_program(args.program).await,
+                                Err(e) => {
+                                    args.program.get_data().discard();
+                                    Err(e)
+                                }
+                            };
+                            if let Ok(ref p) = prog {
+                                send_event(&self.events, ProgramEventType::Loaded, p);
+                            }
                             // Ignore errors as they'll be propagated to caller in the RPC status
                             let _ = args.responder.send(prog);
                         },
@@ -964,15 +2596,138 @@ impl BpfManager {
                             let _ = args.responder.send(prog);
                         },
                         Command::PullBytecode (args) => self.pull_bytecode(args).await.unwrap(),
+                        Command::Attach(args) => self.attach_command(args).await.unwrap(),
+                        Command::Detach(args) => self.detach_command(args).await.unwrap(),
+                        Command::Update(args) => self.update_command(args).await.unwrap(),
+                        Command::Replace(args) => self.replace_command(args).await.unwrap(),
+                        Command::Map(args) => {
+                            let res = self
+                                .check_owner(args.id, &args.owner)
+                                .and_then(|_| self.map_operation(args.id, &args.map_name, args.operation));
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        }
+                        Command::StaticPrograms(programs) => {
+                            self.reconcile_static_programs(programs).await;
+                        }
+                        Command::ProcessExited(process) => {
+                            self.remove_bound_programs(process).await;
+                        }
+                        Command::Apply(args) => {
+                            let res = self.apply_programs(args.programs).await;
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        }
+                        Command::Verify(args) => {
+                            let res = self.verify_program(&args).await;
+                            // Ignore errors as they'll be propagated to caller in the RPC status
+                            let _ = args.responder.send(res);
+                        }
                     }
                 }
             }
-        }
-        info!("Stopping processing commands");
+        }
+        info!("Stopping processing commands");
+    }
+
+    // Fails if the program was not loaded by the owner, when one is given.
+    // Programs that bpfman doesn't know about are left to the command to
+    // report.
+    fn check_owner(&self, id: u32, owner: &Option<String>) -> Result<(), BpfmanError> {
+        let (Some(owner), Some(program)) = (owner, self.programs.get(&id)) else {
+            return Ok(());
+        };
+        if program.get_data().get_owner()?.as_ref() != Some(owner) {
+            return Err(BpfmanError::NotOwner(id));
+        }
+        Ok(())
+    }
+
+    // Fails if the program shares the maps of a program that was not loaded by
+    // the owner, when one is given.
+    fn check_map_owner(
+        &self,
+        program: &Program,
+        owner: &Option<String>,
+    ) -> Result<(), BpfmanError> {
+        match program.get_data().get_map_owner_id()? {
+            Some(map_owner_id) => self.check_owner(map_owner_id, owner),
+            None => Ok(()),
+        }
+    }
+
+    async fn unload_command(&mut self, args: UnloadArgs) -> anyhow::Result<()> {
+        let res = match self.check_owner(args.id, &args.owner) {
+            Ok(()) => self.remove_program(args.id).await,
+            Err(e) => Err(e),
+        };
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn attach_command(&mut self, args: AttachArgs) -> anyhow::Result<()> {
+        let res = match self.check_owner(args.id, &args.owner) {
+            Ok(()) => self.attach_program(args.id, args.link).await,
+            Err(e) => {
+                args.link.get_data().discard();
+                Err(e)
+            }
+        };
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn detach_command(&mut self, args: DetachArgs) -> anyhow::Result<()> {
+        let res = match args.id {
+            Some(id) => match self.check_owner(id, &args.owner) {
+                Ok(()) => self.detach_program(id),
+                Err(e) => Err(e),
+            },
+            None => {
+                let id = self
+                    .programs
+                    .get_link(&args.link_id)
+                    .map(|l| l.get_data().get_id())
+                    .transpose();
+                let res = match id {
+                    Ok(Some(id)) => self.check_owner(id, &args.owner),
+                    Ok(None) => Ok(()),
+                    Err(e) => Err(e),
+                };
+                match res {
+                    Ok(()) => self.detach_link(args.link_id).await,
+                    Err(e) => Err(e),
+                }
+            }
+        };
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
     }
 
-    async fn unload_command(&mut self, args: UnloadArgs) -> anyhow::Result<()> {
-        let res = self.remove_program(args.id).await;
+    async fn replace_command(&mut self, args: ReplaceArgs) -> anyhow::Result<()> {
+        let res = match self.check_owner(args.id, &args.owner) {
+            Ok(()) => {
+                self.replace_program(args.id, args.location, args.name, args.global_data)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        // Ignore errors as they'll be propagated to caller in the RPC status
+        let _ = args.responder.send(res);
+        Ok(())
+    }
+
+    async fn update_command(&mut self, args: UpdateArgs) -> anyhow::Result<()> {
+        let res = match self.check_owner(args.id, &args.owner) {
+            Ok(()) => {
+                self.update_program(args.id, args.priority, args.proceed_on, args.metadata)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
         // Ignore errors as they'll be propagated to caller in the RPC status
         let _ = args.responder.send(res);
         Ok(())
@@ -980,11 +2735,9 @@ impl BpfManager {
 
     // This function checks to see if the user provided map_owner_id is valid.
     fn is_map_owner_id_valid(&mut self, map_owner_id: u32) -> Result<PathBuf, BpfmanError> {
-        let map_pin_path = calc_map_pin_path(map_owner_id);
-
-        if self.maps.contains_key(&map_owner_id) {
+        if let Some(map) = self.maps.get(&map_owner_id) {
             // Return the map_pin_path
-            return Ok(map_pin_path);
+            return Ok(map.map_pin_path.clone());
         }
         Err(BpfmanError::Error(
             "map_owner_id does not exists".to_string(),
@@ -1053,15 +2806,8 @@ impl BpfManager {
                 }
             }
             None => {
-                let map = BpfMap { used_by: vec![id] };
-
-                self.maps.insert(id, map);
-
-                // Update this program with the updated map_used_by
-                data.set_maps_used_by(vec![id])?;
-
                 // Set the permissions on the map_pin_path directory.
-                if let Some(map_pin_path) = data.get_map_pin_path()? {
+                let map_pin_path = if let Some(map_pin_path) = data.get_map_pin_path()? {
                     if let Some(path) = map_pin_path.to_str() {
                         debug!("bpf set dir permissions for {}", path);
                         set_dir_permissions(path, MAPS_MODE).await;
@@ -1072,12 +2818,23 @@ impl BpfManager {
                             id
                         )));
                     }
+                    map_pin_path
                 } else {
                     return Err(BpfmanError::Error(format!(
                         "map_pin_path should be set for {}",
                         id
                     )));
-                }
+                };
+
+                let map = BpfMap {
+                    map_pin_path,
+                    used_by: vec![id],
+                };
+
+                self.maps.insert(id, map);
+
+                // Update this program with the updated map_used_by
+                data.set_maps_used_by(vec![id])?;
             }
         }
 
@@ -1103,7 +2860,7 @@ impl BpfManager {
 
             if map.used_by.is_empty() {
                 // No more programs using this map, so remove the entry from the map list.
-                let path = calc_map_pin_path(index);
+                let path = map.map_pin_path.clone();
                 self.maps.remove(&index.clone());
                 remove_dir_all(path)
                     .await
@@ -1127,6 +2884,51 @@ impl BpfManager {
         Ok(())
     }
 
+    // This function hands the maps used by a replaced eBPF program over to the
+    // program that replaced it. If the replaced program was the map owner, the
+    // new program becomes the owner, and the programs sharing the maps are
+    // updated to reference it. The maps themselves stay where they are pinned.
+    fn replace_map_user(
+        &mut self,
+        old_id: u32,
+        new_id: u32,
+        map_owner_id: Option<u32>,
+        program: &mut Program,
+    ) -> Result<(), BpfmanError> {
+        let index = map_owner_id.unwrap_or(old_id);
+        let mut map = self
+            .maps
+            .remove(&index)
+            .ok_or_else(|| BpfmanError::Error("map_pin_path does not exists".to_string()))?;
+
+        for used_by_id in map.used_by.iter_mut() {
+            if *used_by_id == old_id {
+                *used_by_id = new_id;
+            }
+        }
+
+        program
+            .get_data_mut()
+            .set_maps_used_by(map.used_by.clone())?;
+
+        let index = match map_owner_id {
+            Some(i) => i,
+            None => new_id,
+        };
+        for used_by_id in map.used_by.iter() {
+            if let Some(p) = self.programs.get_mut(used_by_id) {
+                if map_owner_id.is_none() {
+                    p.get_data_mut().set_map_owner_id(new_id)?;
+                }
+                p.get_data_mut().set_maps_used_by(map.used_by.clone())?;
+            }
+        }
+
+        self.maps.insert(index, map);
+
+        Ok(())
+    }
+
     async fn rebuild_map_entry(&mut self, id: u32, program: &mut Program) {
         let map_owner_id = program.get_data().get_map_owner_id().unwrap();
         let index = match map_owner_id {
@@ -1154,7 +2956,14 @@ impl BpfManager {
                 }
             }
         } else {
-            let map = BpfMap { used_by: vec![id] };
+            let map = BpfMap {
+                map_pin_path: program
+                    .get_data()
+                    .get_map_pin_path()
+                    .unwrap()
+                    .unwrap_or_else(|| calc_map_pin_path(index)),
+                used_by: vec![id],
+            };
             self.maps.insert(index, map);
 
             program.get_data_mut().set_maps_used_by(vec![id]).unwrap();
@@ -1162,6 +2971,639 @@ impl BpfManager {
     }
 }
 
+// Sends a program lifecycle event to the Watch requesters. Sending only fails
+// when nobody is watching, so that error is ignored.
+fn send_event(
+    events: &broadcast::Sender<ProgramEvent>,
+    event_type: ProgramEventType,
+    program: &Program,
+) {
+    match ProgramEvent::new(event_type, program) {
+        Ok(event) => {
+            let _ = events.send(event);
+        }
+        Err(e) => warn!("unable to create {event_type} event: {e}"),
+    }
+}
+
+// Sends a dispatcher_rebuilt event for each of the programs in a dispatcher
+// that was just rebuilt.
+fn send_dispatcher_events(events: &broadcast::Sender<ProgramEvent>, programs: &[&mut Program]) {
+    for program in programs {
+        send_event(events, ProgramEventType::DispatcherRebuilt, program);
+    }
+}
+
+// Returns the priority, proceed-on and metadata of the desired program where
+// they differ from the loaded program, which are the changes Update can make.
+#[allow(clippy::type_complexity)]
+fn in_place_changes(
+    current: &Program,
+    desired: &Program,
+) -> Result<
+    (
+        Option<i32>,
+        Option<Vec<i32>>,
+        Option<HashMap<String, String>>,
+    ),
+    BpfmanError,
+> {
+    let (priority, proceed_on) = match (current, desired) {
+        (Program::Xdp(c), Program::Xdp(d)) => (
+            changed(c.get_priority()?, d.get_priority()?),
+            changed(
+                c.get_proceed_on()?.as_action_vec(),
+                d.get_proceed_on()?.as_action_vec(),
+            ),
+        ),
+        (Program::Tc(c), Program::Tc(d)) => (
+            changed(c.get_priority()?, d.get_priority()?),
+            changed(
+                c.get_proceed_on()?.as_action_vec(),
+                d.get_proceed_on()?.as_action_vec(),
+            ),
+        ),
+        (Program::Cgroup(c), Program::Cgroup(d)) => {
+            (changed(c.get_priority()?, d.get_priority()?), None)
+        }
+        _ => (None, None),
+    };
+    let metadata = changed(
+        current.get_data().get_metadata()?,
+        desired.get_data().get_metadata()?,
+    );
+
+    Ok((priority, proceed_on, metadata))
+}
+
+fn changed<T: PartialEq>(current: T, desired: T) -> Option<T> {
+    (current != desired).then_some(desired)
+}
+
+// Checks that bpf is in the list of active LSMs, which is set by the lsm=
+// kernel parameter or CONFIG_LSM.
+// Sets the priority and proceed_on of an XDP, TC or cgroup program, and
+// returns the values they replaced. proceed_on is parsed before anything is
+// written, so nothing changes if it isn't valid.
+fn set_order(
+    program: &mut Program,
+    priority: Option<i32>,
+    proceed_on: Option<Vec<i32>>,
+) -> Result<(Option<i32>, Option<Vec<i32>>), BpfmanError> {
+    let parse_error =
+        |e: ParseError| BpfmanError::Error(format!("failed to parse proceed_on: {e}"));
+    match program {
+        Program::Xdp(p) => {
+            let proceed_on = proceed_on
+                .map(XdpProceedOn::from_int32s)
+                .transpose()
+                .map_err(parse_error)?;
+            let old_priority = priority.map(|_| p.get_priority()).transpose()?;
+            let old_proceed_on = match proceed_on {
+                Some(proceed_on) => {
+                    let old = p.get_proceed_on()?.as_action_vec();
+                    p.set_proceed_on(proceed_on)?;
+                    Some(old)
+                }
+                None => None,
+            };
+            if let Some(priority) = priority {
+                p.set_priority(priority)?;
+            }
+            Ok((old_priority, old_proceed_on))
+        }
+        Program::Tc(p) => {
+            let proceed_on = proceed_on
+                .map(TcProceedOn::from_int32s)
+                .transpose()
+                .map_err(parse_error)?;
+            let old_priority = priority.map(|_| p.get_priority()).transpose()?;
+            let old_proceed_on = match proceed_on {
+                Some(proceed_on) => {
+                    let old = p.get_proceed_on()?.as_action_vec();
+                    p.set_proceed_on(proceed_on)?;
+                    Some(old)
+                }
+                None => None,
+            };
+            if let Some(priority) = priority {
+                p.set_priority(priority)?;
+            }
+            Ok((old_priority, old_proceed_on))
+        }
+        Program::Cgroup(p) => {
+            let old_priority = priority.map(|_| p.get_priority()).transpose()?;
+            if let Some(priority) = priority {
+                p.set_priority(priority)?;
+            }
+            Ok((old_priority, None))
+        }
+        _ => Ok((None, None)),
+    }
+}
+
+// Builds the program that replaces program `id` with new bytecode. The name
+// and global data default to the old program's.
+fn replacement_program(
+    id: u32,
+    old: &Program,
+    location: Location,
+    name: Option<String>,
+    global_data: Option<HashMap<String, Vec<u8>>>,
+) -> Result<Program, BpfmanError> {
+    let old_data = old.get_data();
+    let map_pin_path = old_data
+        .get_map_pin_path()?
+        .ok_or_else(|| BpfmanError::Error(format!("map_pin_path should be set for {id}")))?;
+
+    let data = ProgramData::new_pre_load(
+        location,
+        match name {
+            Some(n) => n,
+            None => old_data.get_name()?,
+        },
+        old_data.get_metadata()?,
+        match global_data {
+            Some(g) => g,
+            None => old_data.get_global_data()?,
+        },
+        old_data.get_map_owner_id()?,
+    )?;
+    let mut program = old.new_replacement(data)?;
+
+    // The new program is loaded with the maps pinned by the program it
+    // replaces, rather than creating its own.
+    program.get_data_mut().set_map_pin_path(&map_pin_path)?;
+    Ok(program)
+}
+
+fn bpf_lsm_enabled() -> Result<bool, BpfmanError> {
+    let lsms = std::fs::read_to_string("/sys/kernel/security/lsm")
+        .map_err(|e| BpfmanError::Error(format!("unable to read the list of active LSMs: {e}")))?;
+    Ok(has_bpf_lsm(&lsms))
+}
+
+// The file lists the active LSMs separated by commas.
+fn has_bpf_lsm(lsms: &str) -> bool {
+    lsms.trim().split(',').any(|lsm| lsm == "bpf")
+}
+
+// Opens the cgroup v2 directory a cgroup program is attached to.
+fn open_cgroup(cgroup_path: &str) -> Result<std::fs::File, BpfmanError> {
+    let cgroup = std::fs::File::open(cgroup_path)
+        .map_err(|e| BpfmanError::Error(format!("unable to open cgroup {cgroup_path}: {e}")))?;
+    if !cgroup.metadata()?.is_dir() {
+        return Err(BpfmanError::InvalidAttach(cgroup_path.to_string()));
+    }
+    Ok(cgroup)
+}
+
+// Returns the perf config of the event, which for a tracepoint is its id.
+fn perf_event_config(event_type: PerfEventType, event: &str) -> Result<u64, BpfmanError> {
+    if let Some(config) = event_type
+        .event_config(event)
+        .map_err(|e| BpfmanError::Error(e.to_string()))?
+    {
+        return Ok(config);
+    }
+
+    let mut last_error = None;
+    for tracefs in ["/sys/kernel/tracing", "/sys/kernel/debug/tracing"] {
+        let path = format!("{tracefs}/events/{event}/id");
+        match std::fs::read_to_string(&path) {
+            Ok(id) => {
+                return id.trim().parse().map_err(|e| {
+                    BpfmanError::Error(format!("unable to parse tracepoint id in {path}: {e}"))
+                })
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(BpfmanError::Error(format!(
+        "unable to read the id of tracepoint {event}: {}",
+        last_error.expect("at least one tracefs path is tried")
+    )))
+}
+
+fn perf_type_id(event_type: PerfEventType) -> PerfTypeId {
+    match event_type {
+        PerfEventType::Software => PerfTypeId::Software,
+        PerfEventType::Hardware => PerfTypeId::Hardware,
+        PerfEventType::Tracepoint => PerfTypeId::TracePoint,
+    }
+}
+
+// A perf event is opened either for one process or for one CPU. Negative
+// pids are rejected when the program is created.
+fn perf_event_scope(cpu: Option<u32>, pid: Option<i32>) -> PerfEventScope {
+    match (cpu, pid) {
+        (Some(cpu), None) => PerfEventScope::AllProcessesOneCpu { cpu },
+        (Some(cpu), Some(pid)) => PerfEventScope::OneProcessOneCpu {
+            cpu,
+            pid: pid as u32,
+        },
+        (None, Some(pid)) => PerfEventScope::OneProcessAnyCpu { pid: pid as u32 },
+        (None, None) => unreachable!("perf events without a pid are opened on each CPU"),
+    }
+}
+
+fn sample_policy(
+    sample_period: Option<u64>,
+    sample_frequency: Option<u64>,
+) -> Result<SamplePolicy, BpfmanError> {
+    match (sample_period, sample_frequency) {
+        (Some(period), None) => Ok(SamplePolicy::Period(period)),
+        (None, Some(frequency)) => Ok(SamplePolicy::Frequency(frequency)),
+        _ => Err(BpfmanError::Error(
+            "exactly one of sample_period and sample_frequency must be set".to_string(),
+        )),
+    }
+}
+
+// Moves the link of the cgroup program `old_id` to the program `id`, which
+// has been loaded and pinned to replace it.
+fn take_over_cgroup_link(
+    old_id: u32,
+    id: u32,
+    attach_type: CgroupAttachType,
+) -> Result<(), BpfmanError> {
+    let old_link_pin_path = format!("{RTDIR_FS}/prog_{old_id}_link");
+    let link_pin_path = format!("{RTDIR_FS}/prog_{id}_link");
+    let update = |id: u32| {
+        let program = cgroup_program_fd(id, attach_type)?;
+        update_link(&old_link_pin_path, program.as_fd()).map_err(|e| {
+            BpfmanError::Error(format!(
+                "unable to update {old_link_pin_path} to program {id}: {e}"
+            ))
+        })
+    };
+    update(id)?;
+    if let Err(e) = std::fs::rename(&old_link_pin_path, &link_pin_path) {
+        update(old_id)?;
+        return Err(BpfmanError::Error(format!(
+            "unable to move {old_link_pin_path} to {link_pin_path}: {e}"
+        )));
+    }
+    Ok(())
+}
+
+// sock, sock_addr and sockopt programs are loaded for the attach type their
+// section names, such as cgroup/connect4, and the kernel won't attach them
+// with another, so a different requested attach type is rejected up front.
+// Sections that don't name an attach type are left for the kernel to check.
+fn check_cgroup_section(
+    bytes: &[u8],
+    name: &str,
+    attach_type: CgroupAttachType,
+) -> Result<(), BpfmanError> {
+    match attach_type.program_type() {
+        ProgramType::CgroupSock | ProgramType::CgroupSockAddr | ProgramType::CgroupSockopt => (),
+        _ => return Ok(()),
+    }
+    let Some(section) = program_section(bytes, name) else {
+        return Ok(());
+    };
+    let section_type = section
+        .strip_prefix("cgroup/")
+        .and_then(|t| CgroupAttachType::try_from(t.to_string()).ok());
+    match section_type {
+        Some(section_type) if section_type != attach_type => Err(BpfmanError::InvalidAttach(
+            format!("{attach_type} ({name} is a {section} program)"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Returns the moves that bring the programs on a cgroup hook into priority
+// order, with ties in the order they run now. `current` is the id and
+// priority of each program in the order they run. Each move is a program to
+// attach again, just before another program or, without one, last.
+//
+// Where the kernel can attach a program before another, the most programs
+// that already run in order relative to each other stay put, and the others
+// are moved, from the last one wanted, just before the program that should
+// follow them, which is in place by then. Otherwise, programs can only be attached last,
+// so the programs from the first one out of place onwards are attached again
+// in order.
+fn cgroup_moves(current: &[(u32, i32)], ordering_supported: bool) -> Vec<(u32, Option<u32>)> {
+    // The index in current of each program, in the order they're wanted.
+    let mut wanted: Vec<usize> = (0..current.len()).collect();
+    wanted.sort_by_key(|i| current[*i].1);
+
+    if !ordering_supported {
+        let first_moved = wanted
+            .iter()
+            .enumerate()
+            .position(|(position, i)| position != *i)
+            .unwrap_or(wanted.len());
+        return wanted[first_moved..]
+            .iter()
+            .map(|i| (current[*i].0, None))
+            .collect();
+    }
+
+    // The longest increasing subsequence of wanted, which are the programs
+    // already in order relative to each other.
+    let mut length = vec![1; wanted.len()];
+    let mut previous = vec![None; wanted.len()];
+    for j in 0..wanted.len() {
+        for k in 0..j {
+            if wanted[k] < wanted[j] && length[k] + 1 > length[j] {
+                length[j] = length[k] + 1;
+                previous[j] = Some(k);
+            }
+        }
+    }
+    let mut kept = vec![false; wanted.len()];
+    let mut next = (0..wanted.len()).max_by_key(|j| (length[*j], std::cmp::Reverse(*j)));
+    while let Some(j) = next {
+        kept[j] = true;
+        next = previous[j];
+    }
+
+    (0..wanted.len())
+        .rev()
+        .filter(|j| !kept[*j])
+        .map(|j| {
+            let before = wanted.get(j + 1).map(|i| current[*i].0);
+            (current[wanted[j]].0, before)
+        })
+        .collect()
+}
+
+// Opens the loaded cgroup program `id` from its pin. The kernel checks that
+// the attach type of a link matches the one the program was loaded with, so
+// sock, sock_addr and sockopt programs, whose attach type aya takes from their
+// section, are opened with the requested type.
+fn cgroup_program_fd(id: u32, attach_type: CgroupAttachType) -> Result<ProgramFd, BpfmanError> {
+    let program_pin_path = format!("{RTDIR_FS}/prog_{id}");
+    let fd = match attach_type.program_type() {
+        ProgramType::CgroupSkb => {
+            CgroupSkb::from_pin(&program_pin_path, skb_attach_type(attach_type)?)?
+                .fd()?
+                .try_clone()?
+        }
+        ProgramType::CgroupSock => {
+            CgroupSock::from_pin(&program_pin_path, sock_attach_type(attach_type)?)?
+                .fd()?
+                .try_clone()?
+        }
+        ProgramType::CgroupSockAddr => {
+            CgroupSockAddr::from_pin(&program_pin_path, sock_addr_attach_type(attach_type)?)?
+                .fd()?
+                .try_clone()?
+        }
+        ProgramType::CgroupSockopt => {
+            CgroupSockopt::from_pin(&program_pin_path, sockopt_attach_type(attach_type)?)?
+                .fd()?
+                .try_clone()?
+        }
+        _ => CgroupSysctl::from_pin(&program_pin_path)?
+            .fd()?
+            .try_clone()?,
+    };
+    Ok(fd)
+}
+
+// Attaches a loaded cgroup program from its pin, after any programs already
+// on the same cgroup and attach type, and pins the link at `link_pin_path`.
+fn attach_cgroup_program(
+    id: u32,
+    cgroup_path: &str,
+    attach_type: CgroupAttachType,
+    link_pin_path: &str,
+) -> Result<(), BpfmanError> {
+    let program_pin_path = format!("{RTDIR_FS}/prog_{id}");
+    let cgroup = open_cgroup(cgroup_path)?;
+    let fd_link = match attach_type.program_type() {
+        ProgramType::CgroupSkb => {
+            let mut skb = CgroupSkb::from_pin(&program_pin_path, skb_attach_type(attach_type)?)?;
+            let link_id = skb.attach(cgroup, skb_attach_type(attach_type)?)?;
+            cgroup_fd_link(skb.take_link(link_id)?.try_into())
+        }
+        ProgramType::CgroupSock => {
+            let mut sock = CgroupSock::from_pin(&program_pin_path, sock_attach_type(attach_type)?)?;
+            let link_id = sock.attach(cgroup)?;
+            cgroup_fd_link(sock.take_link(link_id)?.try_into())
+        }
+        ProgramType::CgroupSockAddr => {
+            let mut sock_addr =
+                CgroupSockAddr::from_pin(&program_pin_path, sock_addr_attach_type(attach_type)?)?;
+            let link_id = sock_addr.attach(cgroup)?;
+            cgroup_fd_link(sock_addr.take_link(link_id)?.try_into())
+        }
+        ProgramType::CgroupSockopt => {
+            let mut sockopt =
+                CgroupSockopt::from_pin(&program_pin_path, sockopt_attach_type(attach_type)?)?;
+            let link_id = sockopt.attach(cgroup)?;
+            cgroup_fd_link(sockopt.take_link(link_id)?.try_into())
+        }
+        _ => {
+            let mut sysctl = CgroupSysctl::from_pin(&program_pin_path)?;
+            let link_id = sysctl.attach(cgroup)?;
+            cgroup_fd_link(sysctl.take_link(link_id)?.try_into())
+        }
+    }?;
+    fd_link
+        .pin(link_pin_path)
+        .map_err(BpfmanError::UnableToPinLink)?;
+    Ok(())
+}
+
+// Attaches a loaded cgroup program from its pin just before the program
+// `before`, and pins the link at `link_pin_path`. Only kernels where
+// cgroup_ordering_supported can do this.
+fn attach_cgroup_program_before(
+    id: u32,
+    cgroup_path: &str,
+    attach_type: CgroupAttachType,
+    before: u32,
+    link_pin_path: &str,
+) -> Result<(), BpfmanError> {
+    let cgroup = open_cgroup(cgroup_path)?;
+    let program = cgroup_program_fd(id, attach_type)?;
+    attach_cgroup_before(
+        program.as_fd(),
+        cgroup.as_fd(),
+        attach_type,
+        before,
+        link_pin_path,
+    )
+    .map_err(|e| {
+        BpfmanError::Error(format!(
+            "unable to attach program {id} to {cgroup_path} before program {before}: {e}"
+        ))
+    })
+}
+
+// Whether the kernel can attach a cgroup program before another, asked once
+// with the loaded cgroup program `id`.
+fn cgroup_ordering_supported(id: u32, attach_type: CgroupAttachType) -> Result<bool, BpfmanError> {
+    let program = cgroup_program_fd(id, attach_type)?;
+    Ok(bpfman_api::links::cgroup_ordering_supported(
+        program.as_fd(),
+        attach_type,
+    ))
+}
+
+// Attaches the program `id` again, just before the program `before` or last,
+// and moves the new link over its pinned one, which detaches the old link.
+// The new link is pinned next to the old one first, so the program always
+// has exactly one pinned link: it briefly runs twice rather than not at all,
+// and is left as it was if it can't be moved.
+fn reattach_cgroup_program(
+    id: u32,
+    cgroup_path: &str,
+    attach_type: CgroupAttachType,
+    before: Option<u32>,
+) -> Result<(), BpfmanError> {
+    let link_pin_path = format!("{RTDIR_FS}/prog_{id}_link");
+    let new_link_pin_path = format!("{link_pin_path}_new");
+    // A pin left behind by an earlier attempt would keep its link attached.
+    let _ = std::fs::remove_file(&new_link_pin_path);
+
+    match before {
+        Some(before) => {
+            attach_cgroup_program_before(id, cgroup_path, attach_type, before, &new_link_pin_path)
+        }
+        None => attach_cgroup_program(id, cgroup_path, attach_type, &new_link_pin_path),
+    }?;
+    if let Err(e) = std::fs::rename(&new_link_pin_path, &link_pin_path) {
+        let _ = std::fs::remove_file(&new_link_pin_path);
+        return Err(BpfmanError::Error(format!(
+            "unable to move {new_link_pin_path} to {link_pin_path}: {e}"
+        )));
+    }
+    Ok(())
+}
+
+// Cgroup programs are attached with a bpf link where the kernel supports it,
+// and only a link can be pinned to outlive bpfman.
+fn cgroup_fd_link<E>(link: Result<FdLink, E>) -> Result<FdLink, BpfmanError> {
+    link.map_err(|_| {
+        BpfmanError::Error(
+            "cgroup programs need a kernel with bpf link support for cgroups (5.7 or later)"
+                .to_string(),
+        )
+    })
+}
+
+fn skb_attach_type(attach_type: CgroupAttachType) -> Result<CgroupSkbAttachType, BpfmanError> {
+    match attach_type {
+        CgroupAttachType::Ingress => Ok(CgroupSkbAttachType::Ingress),
+        CgroupAttachType::Egress => Ok(CgroupSkbAttachType::Egress),
+        a => Err(BpfmanError::InvalidAttach(a.to_string())),
+    }
+}
+
+fn sock_attach_type(attach_type: CgroupAttachType) -> Result<CgroupSockAttachType, BpfmanError> {
+    match attach_type {
+        CgroupAttachType::SockCreate => Ok(CgroupSockAttachType::SockCreate),
+        CgroupAttachType::SockRelease => Ok(CgroupSockAttachType::SockRelease),
+        CgroupAttachType::PostBind4 => Ok(CgroupSockAttachType::PostBind4),
+        CgroupAttachType::PostBind6 => Ok(CgroupSockAttachType::PostBind6),
+        a => Err(BpfmanError::InvalidAttach(a.to_string())),
+    }
+}
+
+fn sock_addr_attach_type(
+    attach_type: CgroupAttachType,
+) -> Result<CgroupSockAddrAttachType, BpfmanError> {
+    match attach_type {
+        CgroupAttachType::Bind4 => Ok(CgroupSockAddrAttachType::Bind4),
+        CgroupAttachType::Bind6 => Ok(CgroupSockAddrAttachType::Bind6),
+        CgroupAttachType::Connect4 => Ok(CgroupSockAddrAttachType::Connect4),
+        CgroupAttachType::Connect6 => Ok(CgroupSockAddrAttachType::Connect6),
+        CgroupAttachType::GetPeername4 => Ok(CgroupSockAddrAttachType::GetPeerName4),
+        CgroupAttachType::GetPeername6 => Ok(CgroupSockAddrAttachType::GetPeerName6),
+        CgroupAttachType::GetSockname4 => Ok(CgroupSockAddrAttachType::GetSockName4),
+        CgroupAttachType::GetSockname6 => Ok(CgroupSockAddrAttachType::GetSockName6),
+        CgroupAttachType::Sendmsg4 => Ok(CgroupSockAddrAttachType::UDPSendMsg4),
+        CgroupAttachType::Sendmsg6 => Ok(CgroupSockAddrAttachType::UDPSendMsg6),
+        CgroupAttachType::Recvmsg4 => Ok(CgroupSockAddrAttachType::UDPRecvMsg4),
+        CgroupAttachType::Recvmsg6 => Ok(CgroupSockAddrAttachType::UDPRecvMsg6),
+        a => Err(BpfmanError::InvalidAttach(a.to_string())),
+    }
+}
+
+fn sockopt_attach_type(
+    attach_type: CgroupAttachType,
+) -> Result<CgroupSockoptAttachType, BpfmanError> {
+    match attach_type {
+        CgroupAttachType::Getsockopt => Ok(CgroupSockoptAttachType::Get),
+        CgroupAttachType::Setsockopt => Ok(CgroupSockoptAttachType::Set),
+        a => Err(BpfmanError::InvalidAttach(a.to_string())),
+    }
+}
+
+// The attach type decides which type of cgroup program can be attached.
+fn check_cgroup_program_type(
+    attach_type: CgroupAttachType,
+    program_type: ProgramType,
+) -> Result<(), BpfmanError> {
+    if attach_type.program_type() != program_type {
+        return Err(BpfmanError::InvalidAttach(attach_type.to_string()));
+    }
+    Ok(())
+}
+
+// The bpfman-ns arguments that attach a kprobe in a container. Without a link
+// pin path, bpfman-ns pins the link next to the program.
+fn kprobe_container_args(
+    program: &KprobeProgram,
+    program_pin_path: String,
+    link_pin_path: Option<String>,
+    container_pid: i32,
+) -> Result<Vec<String>, BpfmanError> {
+    let mut args = vec![
+        "kprobe".to_string(),
+        "--program-pin-path".to_string(),
+        program_pin_path,
+    ];
+    if let Some(path) = link_pin_path {
+        args.extend(["--link-pin-path".to_string(), path]);
+    }
+    args.extend([
+        "--fn-name".to_string(),
+        program.get_fn_name()?,
+        "--offset".to_string(),
+        program.get_offset()?.to_string(),
+        "--container-pid".to_string(),
+        container_pid.to_string(),
+    ]);
+
+    if program.get_retprobe()? {
+        args.push("--retprobe".to_string());
+    }
+
+    Ok(args)
+}
+
+fn verify_fn_name(args: &VerifyArgs) -> Result<&str, BpfmanError> {
+    args.fn_name.as_deref().ok_or_else(|| {
+        BpfmanError::Error(
+            "fn_name is required to verify fentry, fexit, LSM and tp_btf programs".to_string(),
+        )
+    })
+}
+
+// This load is just to verify the BPF Function Name is valid.
+// The actual load is performed in the XDP or TC logic.
+// don't pin maps here.
+fn verify_extension_name(program: &Program) -> Result<(), BpfmanError> {
+    let name = &program.get_data().get_name()?;
+
+    let mut ext_loader = BpfLoader::new()
+        .allow_unsupported_maps()
+        .extension(name)
+        .load(program.get_data().program_bytes())?;
+
+    match ext_loader.program_mut(name) {
+        Some(_) => Ok(()),
+        None => Err(BpfmanError::BpfFunctionNameNotValid(name.to_owned())),
+    }
+}
+
 // map_pin_path is a the directory the maps are located. Currently, it
 // is a fixed bpfman location containing the map_index, which is a ID.
 // The ID is either the programs ID, or the ID of another program
@@ -1176,3 +3618,424 @@ pub async fn create_map_pin_path(p: &Path) -> Result<(), BpfmanError> {
         .await
         .map_err(|e| BpfmanError::Error(format!("can't create map dir: {e}")))
 }
+
+// Waits until the expiry time, or forever if there is none.
+async fn sleep_until_expiry(expiry: Option<SystemTime>) {
+    match expiry {
+        Some(t) => {
+            let wait = t.duration_since(SystemTime::now()).unwrap_or_default();
+            tokio::time::sleep(wait).await
+        }
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::command::{CgroupProgram, LsmProgram, TcProgram, TracepointProgram, XdpProgram};
+
+    fn test_manager() -> BpfManager {
+        BpfManager::new(
+            Config::default(),
+            mpsc::channel(1).1,
+            mpsc::channel(1).0,
+            broadcast::channel(1).0,
+            AuditLog::default(),
+        )
+    }
+
+    fn test_data() -> ProgramData {
+        ProgramData::new_pre_load(
+            Location::File("/tmp/prog.o".to_string()),
+            "prog".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_update_program_rejected() {
+        let mut manager = test_manager();
+        let link = TracepointProgram::new(test_data(), "sched/sched_switch".to_string()).unwrap();
+        manager.programs.insert_link(1, Program::Tracepoint(link));
+        let err = manager
+            .update_program(1, Some(10), None, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("is a link"));
+
+        let cgroup = CgroupProgram::new(
+            test_data(),
+            "/sys/fs/cgroup/bpfman-test".to_string(),
+            CgroupAttachType::Ingress,
+            50,
+        )
+        .unwrap();
+        manager.programs.insert(2, Program::Cgroup(cgroup));
+        let err = manager
+            .update_program(2, None, Some(vec![2]), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("proceed_on cannot be set"));
+
+        let tracepoint =
+            TracepointProgram::new(test_data(), "sched/sched_switch".to_string()).unwrap();
+        manager.programs.insert(3, Program::Tracepoint(tracepoint));
+        let err = manager
+            .update_program(3, Some(10), None, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("can only be updated for"));
+    }
+
+    #[tokio::test]
+    async fn test_update_program_restores_order() {
+        let mut manager = test_manager();
+
+        // The cgroup doesn't exist, so the programs on it can't be reordered
+        // and the program keeps its old priority.
+        let cgroup = CgroupProgram::new(
+            test_data(),
+            "/nonexistent/bpfman-test".to_string(),
+            CgroupAttachType::Ingress,
+            50,
+        )
+        .unwrap();
+        manager.programs.insert(1, Program::Cgroup(cgroup));
+        let metadata = HashMap::from([("key".to_string(), "value".to_string())]);
+        assert!(manager
+            .update_program(1, Some(10), None, Some(metadata))
+            .await
+            .is_err());
+        let program = manager.programs.get(&1).unwrap();
+        assert_eq!(program.priority().unwrap(), 50);
+        assert!(program.get_data().get_metadata().unwrap().is_empty());
+
+        let xdp =
+            XdpProgram::new(test_data(), 50, "eth0".to_string(), XdpProceedOn::default()).unwrap();
+        manager.programs.insert(2, Program::Xdp(xdp));
+        let program = manager.programs.get_mut(&2).unwrap();
+        let old_order = set_order(program, Some(10), Some(vec![2])).unwrap();
+        assert_eq!(program.priority().unwrap(), 10);
+        manager.restore_order(2, &old_order);
+        let Some(Program::Xdp(xdp)) = manager.programs.get(&2) else {
+            panic!("expected an xdp program");
+        };
+        assert_eq!(xdp.get_priority().unwrap(), 50);
+        assert_eq!(
+            xdp.get_proceed_on().unwrap().as_action_vec(),
+            XdpProceedOn::default().as_action_vec()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replace_program_with_links() {
+        let mut manager = test_manager();
+        let mut tracepoint =
+            TracepointProgram::new(test_data(), "sched/sched_switch".to_string()).unwrap();
+        tracepoint.get_data_mut().set_link_ids(vec![7]).unwrap();
+        manager.programs.insert(1, Program::Tracepoint(tracepoint));
+
+        let err = manager
+            .replace_program(1, Location::File("/tmp/prog_v2.o".to_string()), None, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("has links"));
+        assert!(manager.programs.get(&1).is_some());
+    }
+
+    #[test]
+    fn test_replacement_program() {
+        let mut data = ProgramData::new_pre_load(
+            Location::File("/tmp/prog.o".to_string()),
+            "prog".to_string(),
+            HashMap::from([("key".to_string(), "value".to_string())]),
+            HashMap::from([("GLOBAL_u8".to_string(), vec![1])]),
+            Some(3),
+        )
+        .unwrap();
+        data.set_map_pin_path(Path::new("/run/bpfman/fs/maps/3"))
+            .unwrap();
+        let old = Program::Xdp(
+            XdpProgram::new(data, 50, "eth0".to_string(), XdpProceedOn::default()).unwrap(),
+        );
+
+        // The replacement shares the old program's maps and keeps its attach
+        // info, name and metadata.
+        let new = replacement_program(
+            1,
+            &old,
+            Location::File("/tmp/prog_v2.o".to_string()),
+            None,
+            Some(HashMap::from([("GLOBAL_u8".to_string(), vec![2])])),
+        )
+        .unwrap();
+        let data = new.get_data();
+        assert_eq!(
+            data.get_map_pin_path().unwrap(),
+            Some(PathBuf::from("/run/bpfman/fs/maps/3"))
+        );
+        assert_eq!(data.get_map_owner_id().unwrap(), Some(3));
+        assert_eq!(data.get_name().unwrap(), "prog");
+        assert_eq!(data.get_metadata().unwrap()["key"], "value");
+        assert_eq!(data.get_global_data().unwrap()["GLOBAL_u8"], vec![2]);
+        assert_eq!(new.priority().unwrap(), 50);
+        assert_eq!(new.if_name().unwrap(), "eth0");
+
+        // A program without a map pin path was never loaded.
+        let old = Program::Xdp(
+            XdpProgram::new(test_data(), 50, "eth0".to_string(), XdpProceedOn::default()).unwrap(),
+        );
+        assert!(replacement_program(
+            1,
+            &old,
+            Location::File("/tmp/prog_v2.o".to_string()),
+            None,
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_map_operation_map_name() {
+        let manager = test_manager();
+        // Names that would reach outside the program's map pin path are
+        // rejected before the program is looked up.
+        for map_name in ["", ".", "..", "../1234/counts", "counts/other"] {
+            let err = manager
+                .map_operation(1, map_name, MapOperation::Dump)
+                .unwrap_err();
+            assert!(err.to_string().contains("is not a valid map name"), "{err}");
+        }
+        let err = manager
+            .map_operation(1, "counts", MapOperation::Dump)
+            .unwrap_err();
+        assert!(err.to_string().contains("does not exist"), "{err}");
+    }
+
+    #[test]
+    fn test_has_bpf_lsm() {
+        assert!(has_bpf_lsm("lockdown,yama,bpf"));
+        assert!(has_bpf_lsm("lockdown,capability,yama,bpf\n"));
+        assert!(has_bpf_lsm("bpf"));
+        assert!(!has_bpf_lsm("lockdown,capability,yama,apparmor\n"));
+        assert!(!has_bpf_lsm("lockdown,bpf_lsm"));
+        assert!(!has_bpf_lsm(""));
+    }
+
+    #[test]
+    fn test_kprobe_container_args() {
+        let kprobe = KprobeProgram::new(
+            test_data(),
+            "try_to_wake_up".to_string(),
+            16,
+            false,
+            Some(4321),
+        )
+        .unwrap();
+        assert_eq!(
+            kprobe_container_args(&kprobe, "/run/bpfman/fs/prog_7".to_string(), None, 4321)
+                .unwrap(),
+            [
+                "kprobe",
+                "--program-pin-path",
+                "/run/bpfman/fs/prog_7",
+                "--fn-name",
+                "try_to_wake_up",
+                "--offset",
+                "16",
+                "--container-pid",
+                "4321",
+            ]
+        );
+
+        let kretprobe = KprobeProgram::new(
+            test_data(),
+            "try_to_wake_up".to_string(),
+            0,
+            true,
+            Some(4321),
+        )
+        .unwrap();
+        assert_eq!(
+            kprobe_container_args(
+                &kretprobe,
+                "/run/bpfman/fs/prog_7".to_string(),
+                Some("/run/bpfman/fs/prog_7_link_9".to_string()),
+                4321
+            )
+            .unwrap(),
+            [
+                "kprobe",
+                "--program-pin-path",
+                "/run/bpfman/fs/prog_7",
+                "--link-pin-path",
+                "/run/bpfman/fs/prog_7_link_9",
+                "--fn-name",
+                "try_to_wake_up",
+                "--offset",
+                "0",
+                "--container-pid",
+                "4321",
+                "--retprobe",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_attach_program_mismatch() {
+        let mut manager = test_manager();
+        let tracepoint =
+            TracepointProgram::new(test_data(), "sched/sched_switch".to_string()).unwrap();
+        manager.programs.insert(1, Program::Tracepoint(tracepoint));
+        let kprobe = |fn_name: &str| {
+            Program::Kprobe(
+                KprobeProgram::new(test_data(), fn_name.to_string(), 0, false, None).unwrap(),
+            )
+        };
+
+        let err = manager
+            .attach_program(1, kprobe("try_to_wake_up"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("does not match"), "{err}");
+        let program = manager.programs.get(&1).unwrap();
+        assert!(program.get_data().get_link_ids().unwrap().is_empty());
+
+        let lsm = LsmProgram::new(test_data(), "file_open".to_string()).unwrap();
+        manager.programs.insert(2, Program::Lsm(lsm));
+        let link = LsmProgram::new(test_data(), "file_open".to_string()).unwrap();
+        let err = manager
+            .attach_program(2, Program::Lsm(link))
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("can only be attached when they are loaded"));
+
+        let err = manager
+            .attach_program(3, kprobe("try_to_wake_up"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("does not exist"), "{err}");
+    }
+
+    #[test]
+    fn test_detach_program_state() {
+        let mut manager = test_manager();
+
+        // A program loaded without attach info has nothing to detach.
+        let mut data = test_data();
+        data.set_load_only(true).unwrap();
+        let tracepoint = TracepointProgram::new(data, "sched/sched_switch".to_string()).unwrap();
+        manager.programs.insert(1, Program::Tracepoint(tracepoint));
+        let err = manager.detach_program(1).unwrap_err();
+        assert!(err.to_string().contains("is not attached"), "{err}");
+        let program = manager.programs.get(&1).unwrap();
+        assert!(program.get_data().get_load_only().unwrap());
+
+        // A program whose link can't be unpinned stays attached.
+        let id = u32::MAX;
+        let tracepoint =
+            TracepointProgram::new(test_data(), "sched/sched_switch".to_string()).unwrap();
+        manager.programs.insert(id, Program::Tracepoint(tracepoint));
+        assert!(manager.detach_program(id).is_err());
+        let program = manager.programs.get(&id).unwrap();
+        assert!(!program.get_data().get_load_only().unwrap());
+    }
+
+    #[test]
+    fn test_detach_program_dispatcher_types() {
+        // XDP and TC programs are attached through their dispatcher, so only
+        // the links created by Attach can be detached from them.
+        let mut manager = test_manager();
+        let xdp =
+            XdpProgram::new(test_data(), 50, "eth0".to_string(), XdpProceedOn::default()).unwrap();
+        manager.programs.insert(1, Program::Xdp(xdp));
+        let tc = TcProgram::new(
+            test_data(),
+            50,
+            "eth0".to_string(),
+            TcProceedOn::default(),
+            Ingress,
+        )
+        .unwrap();
+        manager.programs.insert(2, Program::Tc(tc));
+
+        for id in [1, 2] {
+            let err = manager.detach_program(id).unwrap_err();
+            assert!(err
+                .to_string()
+                .contains("stay attached until they are unloaded"));
+            let program = manager.programs.get(&id).unwrap();
+            assert!(!program.get_data().get_load_only().unwrap());
+        }
+        assert!(manager.detach_program(3).is_err());
+    }
+
+    #[test]
+    fn test_cgroup_moves_in_order() {
+        let current = [(1, 0), (2, 0), (3, 5), (4, 10)];
+        assert!(cgroup_moves(&current, true).is_empty());
+        assert!(cgroup_moves(&current, false).is_empty());
+    }
+
+    #[test]
+    fn test_cgroup_moves_one_program() {
+        // Program 3 has just been attached last, or had its priority lowered.
+        let current = [(1, 1), (2, 2), (3, 0)];
+        assert_eq!(cgroup_moves(&current, true), vec![(3, Some(1))]);
+        assert_eq!(
+            cgroup_moves(&current, false),
+            vec![(3, None), (1, None), (2, None)]
+        );
+
+        // Program 1 had its priority raised, so it moves last.
+        let current = [(1, 5), (2, 1), (3, 2)];
+        assert_eq!(cgroup_moves(&current, true), vec![(1, None)]);
+        assert_eq!(
+            cgroup_moves(&current, false),
+            vec![(2, None), (3, None), (1, None)]
+        );
+
+        // Program 4 moves between programs 1 and 2.
+        let current = [(1, 1), (2, 3), (3, 4), (4, 2)];
+        assert_eq!(cgroup_moves(&current, true), vec![(4, Some(2))]);
+        assert_eq!(
+            cgroup_moves(&current, false),
+            vec![(4, None), (2, None), (3, None)]
+        );
+    }
+
+    #[test]
+    fn test_cgroup_moves_ties() {
+        // Ties keep the order they run in now. Moving either program 1 or 2
+        // is enough, and programs 2 and 3 are the ones kept.
+        let current = [(1, 1), (2, 0), (3, 1)];
+        assert_eq!(cgroup_moves(&current, true), vec![(1, Some(3))]);
+        assert_eq!(
+            cgroup_moves(&current, false),
+            vec![(2, None), (1, None), (3, None)]
+        );
+    }
+
+    #[test]
+    fn test_cgroup_moves_several() {
+        // Each program is moved just before one that is already in place,
+        // starting from the end.
+        let current = [(1, 4), (2, 3), (3, 2), (4, 1)];
+        assert_eq!(
+            cgroup_moves(&current, true),
+            vec![(1, None), (2, Some(1)), (3, Some(2))]
+        );
+        assert_eq!(
+            cgroup_moves(&current, false),
+            vec![(4, None), (3, None), (2, None), (1, None)]
+        );
+    }
+}
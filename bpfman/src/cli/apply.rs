@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context};
+use bpfman_api::{
+    v1::{
+        bpfman_client::BpfmanClient, bytecode_location::Location, ApplyRequest, BytecodeImage,
+        BytecodeLocation, LoadRequest,
+    },
+    ApplyAction,
+};
+use comfy_table::Table;
+use hex::FromHex;
+use serde::Deserialize;
+use tonic::transport::Channel;
+
+use crate::cli::{
+    args::{ApplyArgs, LoadCommands, PullBytecodeArgs},
+    load::print_verifier_log,
+};
+
+/// The programs in a `bpfman apply` file:
+///
+/// ```yaml
+/// programs:
+///   - uuid: 2a1d0e02-6d0a-4ff4-a5b6-8d1c6f1e5a3c
+///     name: pass
+///     image:
+///       image_url: quay.io/bpfman-bytecode/xdp_pass:latest
+///     metadata:
+///       owner: acme
+///     xdp:
+///       iface: eth0
+///       priority: 50
+/// ```
+#[derive(Debug, Deserialize)]
+struct ApplyFile {
+    #[serde(default)]
+    programs: Vec<ApplyProgram>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApplyProgram {
+    uuid: String,
+    name: String,
+    path: Option<String>,
+    image: Option<PullBytecodeArgs>,
+    // Values are hex strings, as they are for `bpfman load --global`.
+    #[serde(default)]
+    global_data: HashMap<String, String>,
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+    verifier_log_level: Option<u32>,
+    #[serde(flatten)]
+    attach: LoadCommands,
+}
+
+impl TryFrom<ApplyProgram> for LoadRequest {
+    type Error = anyhow::Error;
+
+    fn try_from(p: ApplyProgram) -> Result<Self, Self::Error> {
+        let location = match (&p.path, &p.image) {
+            (Some(path), None) => Location::File(path.clone()),
+            (None, Some(image)) => Location::Image(BytecodeImage::try_from(image)?),
+            _ => bail!("program {} must set one of path or image", p.uuid),
+        };
+
+        let mut global_data = HashMap::new();
+        for (name, value) in p.global_data {
+            let value = Vec::<u8>::from_hex(&value)
+                .with_context(|| format!("invalid global data {name} in program {}", p.uuid))?;
+            global_data.insert(name, value);
+        }
+
+        Ok(LoadRequest {
+            bytecode: Some(BytecodeLocation {
+                location: Some(location),
+            }),
+            name: p.name,
+            program_type: p.attach.get_prog_type() as u32,
+            attach: p.attach.get_attach_type()?,
+            metadata: p.metadata,
+            global_data,
+            uuid: Some(p.uuid),
+            map_owner_id: None,
+            verifier_log_level: p.verifier_log_level,
+            unload_on_exit: false,
+            ttl_seconds: None,
+        })
+    }
+}
+
+pub(crate) async fn execute_apply(args: &ApplyArgs, channel: Channel) -> anyhow::Result<()> {
+    let contents = std::fs::read_to_string(&args.file)
+        .with_context(|| format!("failed to read {}", args.file))?;
+    let file: ApplyFile = serde_yaml::from_str(&contents)
+        .with_context(|| format!("failed to parse {}", args.file))?;
+
+    let programs = file
+        .programs
+        .into_iter()
+        .map(LoadRequest::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut client = BpfmanClient::new(channel);
+
+    let response = client
+        .apply(tonic::Request::new(ApplyRequest { programs }))
+        .await
+        .map_err(print_verifier_log)?
+        .into_inner();
+
+    let mut table = Table::new();
+    table.load_preset(comfy_table::presets::NOTHING);
+    table.set_header(vec!["UUID", "Action", "Program ID"]);
+    let mut errors = vec![];
+    for r in response.results {
+        let action = ApplyAction::try_from(r.action)?;
+        let id = match action {
+            ApplyAction::Unloaded => "".to_string(),
+            ApplyAction::Failed if r.id == 0 => "".to_string(),
+            _ => r.id.to_string(),
+        };
+        if let Some(error) = r.error {
+            errors.push(format!("{}: {error}", r.uuid));
+        }
+        table.add_row(vec![r.uuid, action.to_string(), id]);
+    }
+    println!("{table}");
+
+    if !errors.is_empty() {
+        bail!(
+            "{} programs could not be applied:\n{}",
+            errors.len(),
+            errors.join("\n")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use bpfman_api::{
+        v1::{attach_info::Info, RawTracepointAttachInfo, XdpAttachInfo},
+        ProgramType,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_parse_apply_file() {
+        let input = r#"
+programs:
+  - uuid: a
+    name: pass
+    path: /opt/xdp_pass.o
+    global_data:
+      GLOBAL_u8: "01"
+    xdp:
+      iface: eth0
+      priority: 50
+  - uuid: b
+    name: counter
+    image:
+      image_url: quay.io/bpfman-bytecode/kprobe:latest
+    kprobe:
+      fn_name: try_to_wake_up
+"#;
+        let file: ApplyFile = serde_yaml::from_str(input).unwrap();
+        let mut requests = file
+            .programs
+            .into_iter()
+            .map(LoadRequest::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(requests.len(), 2);
+
+        let kprobe = requests.pop().unwrap();
+        assert_eq!(kprobe.uuid.as_deref(), Some("b"));
+        assert!(matches!(
+            kprobe.bytecode.unwrap().location,
+            Some(Location::Image(_))
+        ));
+
+        let xdp = requests.pop().unwrap();
+        assert_eq!(xdp.global_data["GLOBAL_u8"], vec![1]);
+        match xdp.attach.unwrap().info.unwrap() {
+            Info::XdpAttachInfo(XdpAttachInfo {
+                iface, priority, ..
+            }) => {
+                assert_eq!(iface, "eth0");
+                assert_eq!(priority, 50);
+            }
+            info => panic!("unexpected attach info {info:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_apply_raw_tracepoint() {
+        let input = r#"
+programs:
+  - uuid: a
+    name: sched_switch
+    path: /opt/raw_tp.o
+    raw_tracepoint:
+      tracepoint: sched_switch
+"#;
+        let file: ApplyFile = serde_yaml::from_str(input).unwrap();
+        let request = LoadRequest::try_from(file.programs.into_iter().next().unwrap()).unwrap();
+        assert_eq!(request.program_type, u32::from(ProgramType::RawTracepoint));
+        match request.attach.unwrap().info.unwrap() {
+            Info::RawTracepointAttachInfo(RawTracepointAttachInfo { tracepoint }) => {
+                assert_eq!(tracepoint, "sched_switch");
+            }
+            info => panic!("unexpected attach info {info:?}"),
+        }
+    }
+}
@@ -4,10 +4,22 @@
 use bpfman_api::ProgramType;
 use clap::{Args, Parser, Subcommand};
 use hex::FromHex;
+use serde::Deserialize;
+
+use crate::cli::output::OutputFormat;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub(crate) struct Cli {
+    /// Optional: bpfman endpoint to connect to, either a unix socket or a
+    /// remote bpfman reached with mutual TLS.
+    /// Format: unix://<PATH> or https://<HOST>:<PORT>
+    /// Example: --endpoint https://node1.example.com:50051
+    ///
+    /// [default: unix:///run/bpfman/sock/bpfman.sock]
+    #[clap(long, global = true, verbatim_doc_comment, env = "BPFMAN_ENDPOINT")]
+    pub(crate) endpoint: Option<String>,
+
     #[command(subcommand)]
     pub(crate) command: Commands,
 }
@@ -19,13 +31,31 @@ pub(crate) enum Commands {
     Load(LoadSubcommand),
     /// Unload an eBPF program using the program id.
     Unload(UnloadArgs),
+    /// Attach a loaded eBPF program to an additional hook point.
+    Attach(AttachArgs),
+    /// Detach a link created with `bpfman attach` using the link id.
+    Detach(DetachArgs),
+    /// Update a loaded eBPF program in place using the program id.
+    Update(UpdateArgs),
+    /// Replace the bytecode of a loaded eBPF program, keeping its maps.
+    Replace(ReplaceArgs),
+    /// Run the kernel verifier over an eBPF program without attaching it.
+    #[command(subcommand)]
+    Verify(VerifySubcommand),
     /// List all eBPF programs loaded via bpfman.
     List(ListArgs),
     /// Get an eBPF program using the program id.
     Get(GetArgs),
+    /// Watch for eBPF programs being loaded, unloaded, attached or changed by bpfman.
+    Watch(WatchArgs),
+    /// Load, update and unload eBPF programs to match the programs in a YAML file.
+    Apply(ApplyArgs),
     /// eBPF Bytecode Image related commands.
     #[command(subcommand)]
     Image(ImageSubCommand),
+    /// Read and write the elements of a map used by an eBPF program.
+    #[command(subcommand)]
+    Map(MapSubcommand),
     /// Run bpfman as a service.
     #[command(subcommand)]
     System(SystemSubcommand),
@@ -75,8 +105,32 @@ pub(crate) struct LoadFileArgs {
     #[clap(long, verbatim_doc_comment)]
     pub(crate) map_owner_id: Option<u32>,
 
+    /// Optional: Kernel verifier log level to load the program with. The log is
+    /// printed if the program fails to load.
+    /// Values can be combined: 1 (basic), 2 (verbose), 4 (statistics).
+    /// Example: --verifier-log-level 3
+    #[clap(long, verbatim_doc_comment, value_parser = clap::value_parser!(u32).range(0..8))]
+    pub(crate) verifier_log_level: Option<u32>,
+
+    /// Optional: Unload the program once this long has passed, given in
+    /// seconds or with a suffix of s, m, h or d.
+    /// Example: --ttl 30m
+    #[clap(long, verbatim_doc_comment, value_parser=parse_ttl)]
+    pub(crate) ttl: Option<u64>,
+
+    /// Optional: Load the program without attaching it, so that it can be
+    /// attached later with `bpfman attach`. Used instead of an attach type.
+    /// Example: --program-type probe
+    ///
+    /// [possible values: tracepoint, probe]
+    #[clap(long, verbatim_doc_comment, hide_possible_values = true)]
+    pub(crate) program_type: Option<ProgramType>,
+
+    #[command(flatten)]
+    pub(crate) output: OutputArgs,
+
     #[clap(subcommand)]
-    pub(crate) command: LoadCommands,
+    pub(crate) command: Option<LoadCommands>,
 }
 
 #[derive(Args, Debug)]
@@ -115,8 +169,32 @@ pub(crate) struct LoadImageArgs {
     #[clap(long, verbatim_doc_comment)]
     pub(crate) map_owner_id: Option<u32>,
 
+    /// Optional: Kernel verifier log level to load the program with. The log is
+    /// printed if the program fails to load.
+    /// Values can be combined: 1 (basic), 2 (verbose), 4 (statistics).
+    /// Example: --verifier-log-level 3
+    #[clap(long, verbatim_doc_comment, value_parser = clap::value_parser!(u32).range(0..8))]
+    pub(crate) verifier_log_level: Option<u32>,
+
+    /// Optional: Unload the program once this long has passed, given in
+    /// seconds or with a suffix of s, m, h or d.
+    /// Example: --ttl 30m
+    #[clap(long, verbatim_doc_comment, value_parser=parse_ttl)]
+    pub(crate) ttl: Option<u64>,
+
+    /// Optional: Load the program without attaching it, so that it can be
+    /// attached later with `bpfman attach`. Used instead of an attach type.
+    /// Example: --program-type probe
+    ///
+    /// [possible values: tracepoint, probe]
+    #[clap(long, verbatim_doc_comment, hide_possible_values = true)]
+    pub(crate) program_type: Option<ProgramType>,
+
+    #[command(flatten)]
+    pub(crate) output: OutputArgs,
+
     #[clap(subcommand)]
-    pub(crate) command: LoadCommands,
+    pub(crate) command: Option<LoadCommands>,
 }
 
 #[derive(Clone, Debug)]
@@ -125,7 +203,10 @@ pub(crate) struct GlobalArg {
     pub(crate) value: Vec<u8>,
 }
 
-#[derive(Subcommand, Debug)]
+// LoadCommands is also read from the programs in a `bpfman apply` file, where
+// each attach type is a key holding the same fields as its command line flags.
+#[derive(Subcommand, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub(crate) enum LoadCommands {
     /// Install an eBPF program on the XDP hook point for a given interface.
     Xdp {
@@ -145,6 +226,7 @@ pub(crate) enum LoadCommands {
         ///
         /// [default: pass, dispatcher_return]
         #[clap(long, verbatim_doc_comment, num_args(1..))]
+        #[serde(default)]
         proceed_on: Vec<String>,
     },
     /// Install an eBPF program on the TC hook point for a given interface.
@@ -172,6 +254,7 @@ pub(crate) enum LoadCommands {
         ///
         /// [default: ok, pipe, dispatcher_return]
         #[clap(long, verbatim_doc_comment, num_args(1..))]
+        #[serde(default)]
         proceed_on: Vec<String>,
     },
     /// Install an eBPF program on a Tracepoint.
@@ -181,6 +264,22 @@ pub(crate) enum LoadCommands {
         #[clap(short, long, verbatim_doc_comment)]
         tracepoint: String,
     },
+    /// Install an eBPF program on a raw Tracepoint.
+    #[serde(rename = "raw_tracepoint")]
+    RawTracepoint {
+        /// Required: The raw tracepoint to attach to, without its category.
+        /// Example: --tracepoint "sched_switch"
+        #[clap(short, long, verbatim_doc_comment)]
+        tracepoint: String,
+    },
+    /// Install an eBPF tp_btf program on a BTF-enabled Tracepoint.
+    #[serde(rename = "tp_btf")]
+    TpBtf {
+        /// Required: The tracepoint to attach to, without its category.
+        /// Example: --tracepoint "sched_switch"
+        #[clap(short, long, verbatim_doc_comment)]
+        tracepoint: String,
+    },
     /// Install an eBPF kprobe or kretprobe
     Kprobe {
         /// Required: Function to attach the kprobe to.
@@ -196,10 +295,10 @@ pub(crate) enum LoadCommands {
         ///
         /// [default: false]
         #[clap(short, long, verbatim_doc_comment)]
+        #[serde(default)]
         retprobe: bool,
 
         /// Optional: Host PID of container to attach the kprobe in.
-        /// (NOT CURRENTLY SUPPORTED)
         #[clap(short, long)]
         container_pid: Option<i32>,
     },
@@ -225,6 +324,7 @@ pub(crate) enum LoadCommands {
         ///
         /// [default: false]
         #[clap(short, long, verbatim_doc_comment)]
+        #[serde(default)]
         retprobe: bool,
 
         /// Optional: Only execute uprobe for given process identification number (PID).
@@ -237,6 +337,166 @@ pub(crate) enum LoadCommands {
         #[clap(short, long)]
         container_pid: Option<i32>,
     },
+    /// Install an eBPF fentry program
+    Fentry {
+        /// Required: Kernel function to attach the fentry program to.
+        /// Example: --fn-name "do_unlinkat"
+        #[clap(short, long, verbatim_doc_comment)]
+        fn_name: String,
+    },
+    /// Install an eBPF fexit program
+    Fexit {
+        /// Required: Kernel function to attach the fexit program to.
+        /// Example: --fn-name "do_unlinkat"
+        #[clap(short, long, verbatim_doc_comment)]
+        fn_name: String,
+    },
+    /// Install an eBPF LSM program
+    Lsm {
+        /// Required: Security hook to attach the LSM program to, without the
+        /// bpf_lsm_ prefix. The bpf LSM must be enabled in the kernel.
+        /// Example: --hook "file_open"
+        #[clap(long, verbatim_doc_comment)]
+        hook: String,
+    },
+    /// Install an eBPF cgroup_skb program on a cgroup
+    #[serde(rename = "cgroup_skb")]
+    CgroupSkb {
+        /// Required: Path of the cgroup v2 directory to attach the program to.
+        /// Example: --cgroup-path /sys/fs/cgroup/system.slice
+        #[clap(short, long, verbatim_doc_comment)]
+        cgroup_path: String,
+
+        /// Required: Direction of the packets the program is run on.
+        ///
+        /// [possible values: ingress, egress]
+        #[clap(short, long, verbatim_doc_comment)]
+        direction: String,
+
+        /// Optional: Priority to run program in among the programs on the
+        /// same cgroup and attach type. Lower value runs first.
+        #[clap(short, long, verbatim_doc_comment, default_value_t = 0)]
+        #[serde(default)]
+        priority: i32,
+    },
+    /// Install an eBPF cgroup_sock program on a cgroup
+    #[serde(rename = "cgroup_sock")]
+    CgroupSock {
+        /// Required: Path of the cgroup v2 directory to attach the program to.
+        /// Example: --cgroup-path /sys/fs/cgroup/system.slice
+        #[clap(short, long, verbatim_doc_comment)]
+        cgroup_path: String,
+
+        /// Required: The socket operation the program is run on. Must match
+        /// the program's section, such as cgroup/sock_create.
+        ///
+        /// [possible values: sock_create, sock_release, post_bind4, post_bind6]
+        #[clap(short, long, verbatim_doc_comment)]
+        attach_type: String,
+
+        /// Optional: Priority to run program in among the programs on the
+        /// same cgroup and attach type. Lower value runs first.
+        #[clap(short, long, verbatim_doc_comment, default_value_t = 0)]
+        #[serde(default)]
+        priority: i32,
+    },
+    /// Install an eBPF cgroup_sock_addr program on a cgroup
+    #[serde(rename = "cgroup_sock_addr")]
+    CgroupSockAddr {
+        /// Required: Path of the cgroup v2 directory to attach the program to.
+        /// Example: --cgroup-path /sys/fs/cgroup/system.slice
+        #[clap(short, long, verbatim_doc_comment)]
+        cgroup_path: String,
+
+        /// Required: The socket call the program is run on. Must match the
+        /// program's section, such as cgroup/connect4.
+        ///
+        /// [possible values: bind4, bind6, connect4, connect6, getpeername4,
+        ///                   getpeername6, getsockname4, getsockname6, sendmsg4,
+        ///                   sendmsg6, recvmsg4, recvmsg6]
+        #[clap(short, long, verbatim_doc_comment)]
+        attach_type: String,
+
+        /// Optional: Priority to run program in among the programs on the
+        /// same cgroup and attach type. Lower value runs first.
+        #[clap(short, long, verbatim_doc_comment, default_value_t = 0)]
+        #[serde(default)]
+        priority: i32,
+    },
+    /// Install an eBPF cgroup_sockopt program on a cgroup
+    #[serde(rename = "cgroup_sockopt")]
+    CgroupSockopt {
+        /// Required: Path of the cgroup v2 directory to attach the program to.
+        /// Example: --cgroup-path /sys/fs/cgroup/system.slice
+        #[clap(short, long, verbatim_doc_comment)]
+        cgroup_path: String,
+
+        /// Required: The socket call the program is run on. Must match the
+        /// program's section, such as cgroup/getsockopt.
+        ///
+        /// [possible values: getsockopt, setsockopt]
+        #[clap(short, long, verbatim_doc_comment)]
+        attach_type: String,
+
+        /// Optional: Priority to run program in among the programs on the
+        /// same cgroup and attach type. Lower value runs first.
+        #[clap(short, long, verbatim_doc_comment, default_value_t = 0)]
+        #[serde(default)]
+        priority: i32,
+    },
+    /// Install an eBPF cgroup_sysctl program on a cgroup
+    #[serde(rename = "cgroup_sysctl")]
+    CgroupSysctl {
+        /// Required: Path of the cgroup v2 directory to attach the program to.
+        /// Example: --cgroup-path /sys/fs/cgroup/system.slice
+        #[clap(short, long, verbatim_doc_comment)]
+        cgroup_path: String,
+
+        /// Optional: Priority to run program in among the programs on the
+        /// same cgroup and attach type. Lower value runs first.
+        #[clap(short, long, verbatim_doc_comment, default_value_t = 0)]
+        #[serde(default)]
+        priority: i32,
+    },
+    /// Install an eBPF perf_event program on a perf event
+    #[serde(rename = "perf_event")]
+    PerfEvent {
+        /// Required: The type of perf event.
+        ///
+        /// [possible values: software, hardware, tracepoint]
+        #[clap(short = 't', long, verbatim_doc_comment)]
+        event_type: String,
+
+        /// Required: The perf event. Software and hardware events are named
+        /// after their PERF_COUNT_SW_ and PERF_COUNT_HW_ constants in lower
+        /// case, and tracepoints as category/name.
+        /// Example: --event cpu_clock
+        #[clap(short, long, verbatim_doc_comment)]
+        event: String,
+
+        /// Optional: Run the program once every this many events.
+        /// Exactly one of --sample-period and --sample-frequency is required.
+        #[clap(
+            long,
+            verbatim_doc_comment,
+            conflicts_with = "sample_frequency",
+            required_unless_present = "sample_frequency"
+        )]
+        sample_period: Option<u64>,
+
+        /// Optional: Run the program this many times a second.
+        #[clap(long, verbatim_doc_comment)]
+        sample_frequency: Option<u64>,
+
+        /// Optional: Only run the program on this CPU. By default, the program
+        /// runs on every online CPU, with a perf event on each.
+        #[clap(short, long, verbatim_doc_comment)]
+        cpu: Option<u32>,
+
+        /// Optional: Only run the program for this process.
+        #[clap(short, long)]
+        pid: Option<i32>,
+    },
 }
 
 #[derive(Args, Debug)]
@@ -245,6 +505,170 @@ pub(crate) struct UnloadArgs {
     pub(crate) id: u32,
 }
 
+#[derive(Args, Debug)]
+pub(crate) struct AttachArgs {
+    /// Required: Program id to be attached.
+    pub(crate) id: u32,
+
+    #[clap(subcommand)]
+    pub(crate) command: LoadCommands,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct DetachArgs {
+    /// Required unless --program is set: Link id to be detached.
+    #[clap(required_unless_present = "program")]
+    pub(crate) link_id: Option<u32>,
+
+    /// Optional: Detach the attachment a tracepoint or probe program was
+    /// loaded with, using the program id. The program stays loaded and can be
+    /// attached again with `bpfman attach`. XDP and TC programs stay attached
+    /// until they are unloaded.
+    /// Example: --program 1234
+    #[clap(long, conflicts_with = "link_id", verbatim_doc_comment)]
+    pub(crate) program: Option<u32>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct UpdateArgs {
+    /// Required: Program id to be updated.
+    pub(crate) id: u32,
+
+    /// Optional: Priority to run program in chain. Lower value runs first.
+    /// Only supported for XDP, TC and cgroup programs.
+    #[clap(short, long, verbatim_doc_comment)]
+    pub(crate) priority: Option<i32>,
+
+    /// Optional: Proceed to call other programs in chain on this exit code.
+    /// Multiple values supported by repeating the parameter.
+    /// Only supported for XDP and TC programs, and replaces the existing values.
+    /// Example: --proceed-on "pass" --proceed-on "drop"
+    #[clap(long, verbatim_doc_comment, num_args(1..))]
+    pub(crate) proceed_on: Vec<String>,
+
+    /// Optional: Key/Value metadata which replaces the program's existing
+    /// metadata.
+    /// Format: <KEY>=<VALUE>
+    /// Example: --metadata owner=acme
+    #[clap(short, long, verbatim_doc_comment, value_parser=parse_key_val, value_delimiter = ',')]
+    pub(crate) metadata: Option<Vec<(String, String)>>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ReplaceArgs {
+    /// Required: Program id to be replaced.
+    pub(crate) id: u32,
+
+    #[clap(subcommand)]
+    pub(crate) command: ReplaceSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum ReplaceSubcommand {
+    /// Replace the program with an eBPF program from a local .o file.
+    File(ReplaceFileArgs),
+    /// Replace the program with an eBPF program packaged in a OCI container image.
+    Image(ReplaceImageArgs),
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ReplaceFileArgs {
+    /// Required: Location of local bytecode file
+    /// Example: --path /run/bpfman/examples/go-xdp-counter/bpf_bpfel.o
+    #[clap(short, long, verbatim_doc_comment)]
+    pub(crate) path: String,
+
+    /// Optional: The name of the function that is the entry point for the BPF program.
+    /// If not provided, the name of the program being replaced will be used.
+    #[clap(short, long, verbatim_doc_comment)]
+    pub(crate) name: Option<String>,
+
+    /// Optional: Global variables to be set when program is loaded.
+    /// If not provided, the global variables of the program being replaced will be used.
+    /// Format: <NAME>=<Hex Value>
+    #[clap(short, long, verbatim_doc_comment, num_args(1..), value_parser=parse_global_arg)]
+    pub(crate) global: Option<Vec<GlobalArg>>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ReplaceImageArgs {
+    /// Specify how the bytecode image should be pulled.
+    #[command(flatten)]
+    pub(crate) pull_args: PullBytecodeArgs,
+
+    /// Optional: The name of the function that is the entry point for the BPF program.
+    /// If not provided, the name of the program being replaced will be used.
+    #[clap(short, long, verbatim_doc_comment)]
+    pub(crate) name: Option<String>,
+
+    /// Optional: Global variables to be set when program is loaded.
+    /// If not provided, the global variables of the program being replaced will be used.
+    /// Format: <NAME>=<Hex Value>
+    #[clap(short, long, verbatim_doc_comment, num_args(1..), value_parser=parse_global_arg)]
+    pub(crate) global: Option<Vec<GlobalArg>>,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum VerifySubcommand {
+    /// Verify an eBPF program from a local .o file.
+    File(VerifyFileArgs),
+    /// Verify an eBPF program packaged in a OCI container image.
+    Image(VerifyImageArgs),
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct VerifyFileArgs {
+    /// Required: Location of local bytecode file
+    /// Example: --path /run/bpfman/examples/go-xdp-counter/bpf_bpfel.o
+    #[clap(short, long, verbatim_doc_comment)]
+    pub(crate) path: String,
+
+    /// Required: The name of the function that is the entry point for the BPF program.
+    #[clap(short, long)]
+    pub(crate) name: String,
+
+    #[command(flatten)]
+    pub(crate) verify: VerifyOptions,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct VerifyImageArgs {
+    /// Specify how the bytecode image should be pulled.
+    #[command(flatten)]
+    pub(crate) pull_args: PullBytecodeArgs,
+
+    /// Optional: The name of the function that is the entry point for the BPF program.
+    /// If not provided, the program name defined as part of the bytecode image will be used.
+    #[clap(short, long, verbatim_doc_comment, default_value = "")]
+    pub(crate) name: String,
+
+    #[command(flatten)]
+    pub(crate) verify: VerifyOptions,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct VerifyOptions {
+    /// Optional: Global variables to be set when program is loaded.
+    /// Format: <NAME>=<Hex Value>
+    #[clap(short, long, verbatim_doc_comment, num_args(1..), value_parser=parse_global_arg)]
+    pub(crate) global: Option<Vec<GlobalArg>>,
+
+    /// Optional: The kernel function to verify an fentry or fexit program against,
+    /// the hook to verify an LSM program against, or the tracepoint to verify a
+    /// tp_btf program against.
+    /// Required for fentry, fexit, LSM and tp_btf programs.
+    /// Example: --fn-name do_unlinkat
+    #[clap(short, long, verbatim_doc_comment)]
+    pub(crate) fn_name: Option<String>,
+
+    /// Optional: Kernel verifier log level to load the program with. The log is
+    /// printed if the program fails verification.
+    /// Values can be combined: 1 (basic), 2 (verbose), 4 (statistics).
+    /// Example: --verifier-log-level 3
+    #[clap(long, verbatim_doc_comment, value_parser = clap::value_parser!(u32).range(0..8))]
+    pub(crate) verifier_log_level: Option<u32>,
+}
+
 #[derive(Args, Debug)]
 pub(crate) struct ListArgs {
     /// Optional: List a specific program type
@@ -272,21 +696,117 @@ pub(crate) struct ListArgs {
     /// Optional: List all programs.
     #[clap(short, long, verbatim_doc_comment)]
     pub(crate) all: bool,
+
+    #[command(flatten)]
+    pub(crate) output: OutputArgs,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct WatchArgs {
+    /// Optional: Only show events for a specific program type
+    /// Example: --program-type xdp
+    #[clap(short, long, verbatim_doc_comment, hide_possible_values = true)]
+    pub(crate) program_type: Option<ProgramType>,
+
+    /// Optional: Only show events for programs which contain a specific set of
+    /// metadata labels.
+    /// Format: <KEY>=<VALUE>
+    ///
+    /// Example: --metadata-selector owner=acme
+    #[clap(short, long, verbatim_doc_comment, value_parser=parse_key_val, value_delimiter = ',')]
+    pub(crate) metadata_selector: Option<Vec<(String, String)>>,
 }
 
 #[derive(Args, Debug)]
 pub(crate) struct GetArgs {
     /// Required: Program id to get.
     pub(crate) id: u32,
+
+    #[command(flatten)]
+    pub(crate) output: OutputArgs,
 }
 
 #[derive(Subcommand, Debug)]
 pub(crate) enum ImageSubCommand {
     /// Pull an eBPF bytecode image from a remote registry.
-    Pull(PullBytecodeArgs),
+    Pull(ImagePullArgs),
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ImagePullArgs {
+    #[command(flatten)]
+    pub(crate) pull_args: PullBytecodeArgs,
+
+    #[command(flatten)]
+    pub(crate) output: OutputArgs,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct OutputArgs {
+    /// Optional: Output format. `wide` adds columns to the `bpfman list` table,
+    /// `json` and `yaml` print the programs in a stable schema for scripts.
+    /// Example: --output json
+    #[clap(short, long, verbatim_doc_comment, value_enum, default_value_t = OutputFormat::Table)]
+    pub(crate) output: OutputFormat,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum MapSubcommand {
+    /// Look up the value of a key in a map.
+    Lookup(MapKeyArgs),
+    /// Create or update the value of a key in a map.
+    Update(MapUpdateArgs),
+    /// Delete a key from a map.
+    Delete(MapKeyArgs),
+    /// Print all of the keys and values in a map.
+    Dump(MapArgs),
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct MapArgs {
+    /// Required: Program id of an eBPF program using the map.
+    pub(crate) id: u32,
+
+    /// Required: Name of the map.
+    pub(crate) map_name: String,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct MapKeyArgs {
+    #[command(flatten)]
+    pub(crate) map: MapArgs,
+
+    /// Required: Key in hex, in the byte order used by the map.
+    /// Example: --key 01000000
+    #[clap(short, long, verbatim_doc_comment, value_parser=parse_hex)]
+    pub(crate) key: Vec<u8>,
 }
 
 #[derive(Args, Debug)]
+pub(crate) struct MapUpdateArgs {
+    #[command(flatten)]
+    pub(crate) map: MapArgs,
+
+    /// Required: Key in hex, in the byte order used by the map.
+    /// Example: --key 01000000
+    #[clap(short, long, verbatim_doc_comment, value_parser=parse_hex)]
+    pub(crate) key: Vec<u8>,
+
+    /// Required: Value in hex, in the byte order used by the map.
+    /// Example: --value 0a00000000000000
+    #[clap(short, long, verbatim_doc_comment, value_parser=parse_hex)]
+    pub(crate) value: Vec<u8>,
+
+    /// Optional: Only create a new element, fail if the key exists.
+    #[clap(long, conflicts_with = "exist")]
+    pub(crate) noexist: bool,
+
+    /// Optional: Only update an existing element, fail if the key does not exist.
+    #[clap(long)]
+    pub(crate) exist: bool,
+}
+
+#[derive(Args, Debug, Deserialize)]
 pub(crate) struct PullBytecodeArgs {
     /// Required: Container Image URL.
     /// Example: --image-url quay.io/bpfman-bytecode/xdp_pass:latest
@@ -304,9 +824,23 @@ pub(crate) struct PullBytecodeArgs {
     ///
     /// [possible values: Always, IfNotPresent, Never]
     #[clap(short, long, verbatim_doc_comment, default_value = "IfNotPresent")]
+    #[serde(default = "default_pull_policy")]
     pub(crate) pull_policy: String,
 }
 
+fn default_pull_policy() -> String {
+    "IfNotPresent".to_string()
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ApplyArgs {
+    /// Required: YAML file listing every program that should be loaded.
+    /// Programs loaded by an earlier apply that aren't in the file are unloaded.
+    /// Example: --file programs.yaml
+    #[clap(short, long, verbatim_doc_comment)]
+    pub(crate) file: String,
+}
+
 #[derive(Args, Debug)]
 pub(crate) struct ServiceArgs {
     /// Enable CSI support.
@@ -329,6 +863,14 @@ pub(crate) fn parse_key_val(s: &str) -> Result<(String, String), std::io::Error>
     Ok((s[..pos].to_string(), s[pos + 1..].to_string()))
 }
 
+pub(crate) fn parse_hex(hex: &str) -> Result<Vec<u8>, std::io::Error> {
+    let value = Vec::<u8>::from_hex(hex).map_err(|_e| std::io::ErrorKind::InvalidInput)?;
+    if value.is_empty() {
+        return Err(std::io::ErrorKind::InvalidInput.into());
+    }
+    Ok(value)
+}
+
 pub(crate) fn parse_global_arg(global_arg: &str) -> Result<GlobalArg, std::io::Error> {
     let mut parts = global_arg.split('=');
 
@@ -345,3 +887,25 @@ pub(crate) fn parse_global_arg(global_arg: &str) -> Result<GlobalArg, std::io::E
         value,
     })
 }
+
+/// Parse a ttl into seconds, such as 90, 90s, 30m, 12h or 7d.
+pub(crate) fn parse_ttl(ttl: &str) -> Result<u64, std::io::Error> {
+    let (value, unit) = match ttl.find(|c: char| !c.is_ascii_digit()) {
+        Some(pos) => ttl.split_at(pos),
+        None => (ttl, "s"),
+    };
+    let value: u64 = value
+        .parse()
+        .map_err(|_e| std::io::ErrorKind::InvalidInput)?;
+    let secs = match unit {
+        "s" => Some(value),
+        "m" => value.checked_mul(60),
+        "h" => value.checked_mul(60 * 60),
+        "d" => value.checked_mul(24 * 60 * 60),
+        _ => None,
+    };
+    match secs {
+        Some(secs) if secs > 0 => Ok(secs),
+        _ => Err(std::io::ErrorKind::InvalidInput.into()),
+    }
+}
@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman_api::v1::{bpfman_client::BpfmanClient, AttachRequest};
+use tonic::transport::Channel;
+
+use crate::cli::args::AttachArgs;
+
+pub(crate) async fn execute_attach(
+    args: &AttachArgs,
+    channel: Channel,
+) -> Result<(), anyhow::Error> {
+    let mut client = BpfmanClient::new(channel);
+    let request = tonic::Request::new(AttachRequest {
+        id: args.id,
+        attach: args.command.get_attach_type()?,
+    });
+    let response = client.attach(request).await?.into_inner();
+
+    println!("Link ID: {}", response.link_id);
+    Ok(())
+}
@@ -0,0 +1,20 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman_api::v1::{bpfman_client::BpfmanClient, DetachRequest};
+use tonic::transport::Channel;
+
+use crate::cli::args::DetachArgs;
+
+pub(crate) async fn execute_detach(
+    args: &DetachArgs,
+    channel: Channel,
+) -> Result<(), anyhow::Error> {
+    let mut client = BpfmanClient::new(channel);
+    let request = tonic::Request::new(DetachRequest {
+        link_id: args.link_id.unwrap_or_default(),
+        id: args.program,
+    });
+    let _response = client.detach(request).await?.into_inner();
+    Ok(())
+}
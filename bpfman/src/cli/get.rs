@@ -2,15 +2,25 @@
 // Copyright Authors of bpfman
 
 use bpfman_api::v1::{bpfman_client::BpfmanClient, GetRequest};
+use tonic::transport::Channel;
 
-use crate::cli::{args::GetArgs, select_channel, table::ProgTable};
+use crate::cli::{
+    args::GetArgs,
+    output::{print_structured, ProgramOutput},
+    table::ProgTable,
+};
 
-pub(crate) async fn execute_get(args: &GetArgs) -> Result<(), anyhow::Error> {
-    let channel = select_channel().expect("failed to select channel");
+pub(crate) async fn execute_get(args: &GetArgs, channel: Channel) -> Result<(), anyhow::Error> {
     let mut client = BpfmanClient::new(channel);
     let request = tonic::Request::new(GetRequest { id: args.id });
     let response = client.get(request).await?.into_inner();
 
+    let format = args.output.output;
+    if !format.is_table() {
+        let output = ProgramOutput::new(response.info, response.kernel_info)?;
+        return print_structured(format, &output);
+    }
+
     ProgTable::new_get_bpfman(&response.info)?.print();
     ProgTable::new_get_unsupported(&response.kernel_info)?.print();
     Ok(())
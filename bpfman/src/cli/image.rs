@@ -6,16 +6,17 @@ use bpfman_api::{
     v1::{bpfman_client::BpfmanClient, BytecodeImage, PullBytecodeRequest},
     ImagePullPolicy,
 };
+use tonic::transport::Channel;
 
 use crate::cli::{
-    args::{ImageSubCommand, PullBytecodeArgs},
-    select_channel,
+    args::{ImagePullArgs, ImageSubCommand, PullBytecodeArgs},
+    output::{print_structured, ImageOutput},
 };
 
 impl ImageSubCommand {
-    pub(crate) async fn execute(&self) -> anyhow::Result<()> {
+    pub(crate) async fn execute(&self, channel: Channel) -> anyhow::Result<()> {
         match self {
-            ImageSubCommand::Pull(args) => execute_pull(args).await,
+            ImageSubCommand::Pull(args) => execute_pull(args, channel).await,
         }
     }
 }
@@ -44,11 +45,23 @@ impl TryFrom<&PullBytecodeArgs> for BytecodeImage {
     }
 }
 
-pub(crate) async fn execute_pull(args: &PullBytecodeArgs) -> anyhow::Result<()> {
-    let channel = select_channel().expect("failed to select channel");
+pub(crate) async fn execute_pull(args: &ImagePullArgs, channel: Channel) -> anyhow::Result<()> {
     let mut client = BpfmanClient::new(channel);
-    let image: BytecodeImage = args.try_into()?;
-    let request = tonic::Request::new(PullBytecodeRequest { image: Some(image) });
+    let image: BytecodeImage = (&args.pull_args).try_into()?;
+    let request = tonic::Request::new(PullBytecodeRequest {
+        image: Some(image.clone()),
+    });
     let _response = client.pull_bytecode(request).await?;
+
+    let format = args.output.output;
+    if !format.is_table() {
+        print_structured(
+            format,
+            &ImageOutput {
+                url: image.url,
+                pull_policy: ImagePullPolicy::try_from(image.image_pull_policy)?.to_string(),
+            },
+        )?;
+    }
     Ok(())
 }
@@ -3,11 +3,15 @@
 
 use anyhow::bail;
 use bpfman_api::v1::{bpfman_client::BpfmanClient, ListRequest};
+use tonic::transport::Channel;
 
-use crate::cli::{args::ListArgs, select_channel, table::ProgTable};
+use crate::cli::{
+    args::ListArgs,
+    output::{print_structured, OutputFormat, ProgramOutput},
+    table::ProgTable,
+};
 
-pub(crate) async fn execute_list(args: &ListArgs) -> anyhow::Result<()> {
-    let channel = select_channel().unwrap();
+pub(crate) async fn execute_list(args: &ListArgs, channel: Channel) -> anyhow::Result<()> {
     let mut client = BpfmanClient::new(channel);
     let prog_type_filter = args.program_type.map(|p| p as u32);
 
@@ -24,10 +28,31 @@ pub(crate) async fn execute_list(args: &ListArgs) -> anyhow::Result<()> {
         bpfman_programs_only: Some(!args.all),
     });
     let response = client.list(request).await?.into_inner();
-    let mut table = ProgTable::new_list();
+
+    let format = args.output.output;
+    if !format.is_table() {
+        let output = response
+            .results
+            .into_iter()
+            .map(|r| ProgramOutput::new(r.info, r.kernel_info))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        return print_structured(format, &output);
+    }
+
+    let wide = format == OutputFormat::Wide;
+    let mut table = if wide {
+        ProgTable::new_list_wide()
+    } else {
+        ProgTable::new_list()
+    };
 
     for r in response.results {
-        if let Err(e) = table.add_response_prog(r) {
+        let res = if wide {
+            table.add_response_prog_wide(r)
+        } else {
+            table.add_response_prog(r)
+        };
+        if let Err(e) = res {
             bail!(e)
         }
     }
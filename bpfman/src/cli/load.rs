@@ -7,41 +7,43 @@ use anyhow::bail;
 use bpfman_api::{
     v1::{
         attach_info::Info, bpfman_client::BpfmanClient, bytecode_location::Location, AttachInfo,
-        BytecodeImage, BytecodeLocation, KprobeAttachInfo, LoadRequest, TcAttachInfo,
-        TracepointAttachInfo, UprobeAttachInfo, XdpAttachInfo,
+        BtfTracepointAttachInfo, BytecodeImage, BytecodeLocation, CgroupAttachInfo,
+        FentryAttachInfo, FexitAttachInfo, KprobeAttachInfo, LoadRequest, LoadResponse,
+        LsmAttachInfo, PerfEventAttachInfo, RawTracepointAttachInfo, TcAttachInfo,
+        TracepointAttachInfo, UprobeAttachInfo, VerifierLog, XdpAttachInfo,
     },
-    ProgramType, TcProceedOn, XdpProceedOn,
+    CgroupAttachType, PerfEventType, ProgramType, TcProceedOn, XdpProceedOn,
 };
+use tonic::transport::Channel;
 
 use crate::cli::{
     args::{GlobalArg, LoadCommands, LoadFileArgs, LoadImageArgs, LoadSubcommand},
-    select_channel,
+    output::{print_structured, OutputFormat, ProgramOutput},
     table::ProgTable,
 };
 
 impl LoadSubcommand {
-    pub(crate) async fn execute(&self) -> anyhow::Result<()> {
+    pub(crate) async fn execute(&self, channel: Channel) -> anyhow::Result<()> {
         match self {
-            LoadSubcommand::File(l) => execute_load_file(l).await,
-            LoadSubcommand::Image(l) => execute_load_image(l).await,
+            LoadSubcommand::File(l) => execute_load_file(l, channel).await,
+            LoadSubcommand::Image(l) => execute_load_image(l, channel).await,
         }
     }
 }
 
-pub(crate) async fn execute_load_file(args: &LoadFileArgs) -> anyhow::Result<()> {
-    let channel = select_channel().expect("failed to select channel");
+pub(crate) async fn execute_load_file(args: &LoadFileArgs, channel: Channel) -> anyhow::Result<()> {
     let mut client = BpfmanClient::new(channel);
 
     let bytecode = Some(BytecodeLocation {
         location: Some(Location::File(args.path.clone())),
     });
 
-    let attach = args.command.get_attach_type()?;
+    let (program_type, attach) = get_load_info(&args.command, &args.program_type)?;
 
     let request = tonic::Request::new(LoadRequest {
         bytecode,
         name: args.name.to_string(),
-        program_type: args.command.get_prog_type() as u32,
+        program_type,
         attach,
         metadata: args
             .metadata
@@ -53,28 +55,35 @@ pub(crate) async fn execute_load_file(args: &LoadFileArgs) -> anyhow::Result<()>
         global_data: parse_global(&args.global),
         uuid: None,
         map_owner_id: args.map_owner_id,
+        verifier_log_level: args.verifier_log_level,
+        unload_on_exit: false,
+        ttl_seconds: args.ttl,
     });
-    let response = client.load(request).await?.into_inner();
+    let response = client
+        .load(request)
+        .await
+        .map_err(print_verifier_log)?
+        .into_inner();
 
-    ProgTable::new_get_bpfman(&response.info)?.print();
-    ProgTable::new_get_unsupported(&response.kernel_info)?.print();
-    Ok(())
+    print_load_response(args.output.output, response)
 }
 
-pub(crate) async fn execute_load_image(args: &LoadImageArgs) -> anyhow::Result<()> {
-    let channel = select_channel().expect("failed to select channel");
+pub(crate) async fn execute_load_image(
+    args: &LoadImageArgs,
+    channel: Channel,
+) -> anyhow::Result<()> {
     let mut client = BpfmanClient::new(channel);
 
     let bytecode = Some(BytecodeLocation {
         location: Some(Location::Image(BytecodeImage::try_from(&args.pull_args)?)),
     });
 
-    let attach = args.command.get_attach_type()?;
+    let (program_type, attach) = get_load_info(&args.command, &args.program_type)?;
 
     let request = tonic::Request::new(LoadRequest {
         bytecode,
         name: args.name.to_string(),
-        program_type: args.command.get_prog_type() as u32,
+        program_type,
         attach,
         metadata: args
             .metadata
@@ -86,12 +95,17 @@ pub(crate) async fn execute_load_image(args: &LoadImageArgs) -> anyhow::Result<(
         global_data: parse_global(&args.global),
         uuid: None,
         map_owner_id: args.map_owner_id,
+        verifier_log_level: args.verifier_log_level,
+        unload_on_exit: false,
+        ttl_seconds: args.ttl,
     });
-    let response = client.load(request).await?.into_inner();
+    let response = client
+        .load(request)
+        .await
+        .map_err(print_verifier_log)?
+        .into_inner();
 
-    ProgTable::new_get_bpfman(&response.info)?.print();
-    ProgTable::new_get_unsupported(&response.kernel_info)?.print();
-    Ok(())
+    print_load_response(args.output.output, response)
 }
 
 impl LoadCommands {
@@ -100,8 +114,19 @@ impl LoadCommands {
             LoadCommands::Xdp { .. } => ProgramType::Xdp,
             LoadCommands::Tc { .. } => ProgramType::Tc,
             LoadCommands::Tracepoint { .. } => ProgramType::Tracepoint,
+            LoadCommands::RawTracepoint { .. } => ProgramType::RawTracepoint,
+            LoadCommands::TpBtf { .. } => ProgramType::Tracing,
             LoadCommands::Kprobe { .. } => ProgramType::Probe,
             LoadCommands::Uprobe { .. } => ProgramType::Probe,
+            LoadCommands::Fentry { .. } => ProgramType::Tracing,
+            LoadCommands::Fexit { .. } => ProgramType::Tracing,
+            LoadCommands::Lsm { .. } => ProgramType::Lsm,
+            LoadCommands::CgroupSkb { .. } => ProgramType::CgroupSkb,
+            LoadCommands::CgroupSock { .. } => ProgramType::CgroupSock,
+            LoadCommands::CgroupSockAddr { .. } => ProgramType::CgroupSockAddr,
+            LoadCommands::CgroupSockopt { .. } => ProgramType::CgroupSockopt,
+            LoadCommands::CgroupSysctl { .. } => ProgramType::CgroupSysctl,
+            LoadCommands::PerfEvent { .. } => ProgramType::PerfEvent,
         }
     }
 
@@ -154,15 +179,22 @@ impl LoadCommands {
                     tracepoint: tracepoint.to_string(),
                 })),
             })),
+            LoadCommands::RawTracepoint { tracepoint } => Ok(Some(AttachInfo {
+                info: Some(Info::RawTracepointAttachInfo(RawTracepointAttachInfo {
+                    tracepoint: tracepoint.to_string(),
+                })),
+            })),
+            LoadCommands::TpBtf { tracepoint } => Ok(Some(AttachInfo {
+                info: Some(Info::BtfTracepointAttachInfo(BtfTracepointAttachInfo {
+                    tracepoint: tracepoint.to_string(),
+                })),
+            })),
             LoadCommands::Kprobe {
                 fn_name,
                 offset,
                 retprobe,
                 container_pid,
             } => {
-                if container_pid.is_some() {
-                    bail!("kprobe container option not supported yet");
-                }
                 let offset = offset.unwrap_or(0);
                 Ok(Some(AttachInfo {
                     info: Some(Info::KprobeAttachInfo(KprobeAttachInfo {
@@ -193,11 +225,135 @@ impl LoadCommands {
                     })),
                 }))
             }
+            LoadCommands::Fentry { fn_name } => Ok(Some(AttachInfo {
+                info: Some(Info::FentryAttachInfo(FentryAttachInfo {
+                    fn_name: fn_name.to_string(),
+                })),
+            })),
+            LoadCommands::Fexit { fn_name } => Ok(Some(AttachInfo {
+                info: Some(Info::FexitAttachInfo(FexitAttachInfo {
+                    fn_name: fn_name.to_string(),
+                })),
+            })),
+            LoadCommands::Lsm { hook } => Ok(Some(AttachInfo {
+                info: Some(Info::LsmAttachInfo(LsmAttachInfo {
+                    hook: hook.to_string(),
+                })),
+            })),
+            LoadCommands::CgroupSkb {
+                cgroup_path,
+                direction: attach_type,
+                priority,
+            }
+            | LoadCommands::CgroupSock {
+                cgroup_path,
+                attach_type,
+                priority,
+            }
+            | LoadCommands::CgroupSockAddr {
+                cgroup_path,
+                attach_type,
+                priority,
+            }
+            | LoadCommands::CgroupSockopt {
+                cgroup_path,
+                attach_type,
+                priority,
+            } => self.cgroup_attach_info(cgroup_path, attach_type, *priority),
+            LoadCommands::CgroupSysctl {
+                cgroup_path,
+                priority,
+            } => self.cgroup_attach_info(cgroup_path, "sysctl", *priority),
+            LoadCommands::PerfEvent {
+                event_type,
+                event,
+                sample_period,
+                sample_frequency,
+                cpu,
+                pid,
+            } => {
+                PerfEventType::try_from(event_type.to_string())?.event_config(event)?;
+                if sample_period.is_some() == sample_frequency.is_some() {
+                    bail!("exactly one of sample_period and sample_frequency must be set");
+                }
+                Ok(Some(AttachInfo {
+                    info: Some(Info::PerfEventAttachInfo(PerfEventAttachInfo {
+                        event_type: event_type.to_string(),
+                        event: event.to_string(),
+                        sample_period: *sample_period,
+                        sample_frequency: *sample_frequency,
+                        cpu: *cpu,
+                        pid: *pid,
+                    })),
+                }))
+            }
         }
     }
+
+    // Checks that the attach type is one of the program type's, as they
+    // share a field in the attach info.
+    fn cgroup_attach_info(
+        &self,
+        cgroup_path: &str,
+        attach_type: &str,
+        priority: i32,
+    ) -> Result<Option<AttachInfo>, anyhow::Error> {
+        let program_type = self.get_prog_type();
+        match CgroupAttachType::try_from(attach_type.to_string()) {
+            Ok(a) if a.program_type() == program_type => (),
+            _ => bail!("{attach_type} is not a valid attach type for {program_type} programs"),
+        };
+        Ok(Some(AttachInfo {
+            info: Some(Info::CgroupAttachInfo(CgroupAttachInfo {
+                cgroup_path: cgroup_path.to_string(),
+                attach_type: attach_type.to_string(),
+                priority,
+                position: 0,
+            })),
+        }))
+    }
 }
 
-fn parse_global(global: &Option<Vec<GlobalArg>>) -> HashMap<String, Vec<u8>> {
+fn print_load_response(format: OutputFormat, response: LoadResponse) -> anyhow::Result<()> {
+    if !format.is_table() {
+        let output = ProgramOutput::new(response.info, response.kernel_info)?;
+        return print_structured(format, &output);
+    }
+
+    ProgTable::new_get_bpfman(&response.info)?.print();
+    ProgTable::new_get_unsupported(&response.kernel_info)?.print();
+    Ok(())
+}
+
+// A program is either attached when it is loaded, or loaded with only its
+// program type and attached later with `bpfman attach`.
+fn get_load_info(
+    command: &Option<LoadCommands>,
+    program_type: &Option<ProgramType>,
+) -> anyhow::Result<(u32, Option<AttachInfo>)> {
+    match (command, program_type) {
+        (Some(command), None) => Ok((command.get_prog_type() as u32, command.get_attach_type()?)),
+        (None, Some(program_type @ (ProgramType::Tracepoint | ProgramType::Probe))) => {
+            Ok((*program_type as u32, None))
+        }
+        (None, Some(program_type)) => bail!(
+            "{program_type} programs can't be loaded without attaching them, use an attach type instead of --program-type"
+        ),
+        (Some(_), Some(_)) => bail!("--program-type cannot be used with an attach type"),
+        (None, None) => bail!("an attach type or --program-type is required"),
+    }
+}
+
+// A failed load carries the kernel verifier log in the status details, which
+// is printed ahead of the error itself.
+pub(crate) fn print_verifier_log(status: tonic::Status) -> tonic::Status {
+    if let Some(VerifierLog { log }) = VerifierLog::from_status(&status) {
+        eprintln!("Verifier log:\n{log}");
+    }
+    status
+}
+
+pub(crate) fn parse_global(global: &Option<Vec<GlobalArg>>) -> HashMap<String, Vec<u8>> {
     let mut global_data: HashMap<String, Vec<u8>> = HashMap::new();
 
     if let Some(global) = global {
@@ -207,3 +363,26 @@ fn parse_global(global: &Option<Vec<GlobalArg>>) -> HashMap<String, Vec<u8>> {
     }
     global_data
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_load_info_program_type() {
+        let (program_type, attach) = get_load_info(&None, &Some(ProgramType::Probe)).unwrap();
+        assert_eq!(program_type, ProgramType::Probe as u32);
+        assert!(attach.is_none());
+        assert!(get_load_info(&None, &Some(ProgramType::Tracepoint)).is_ok());
+
+        // XDP and TC programs are attached through a dispatcher, which is
+        // only built when they are loaded with an interface.
+        for program_type in [ProgramType::Xdp, ProgramType::Tc] {
+            let err = get_load_info(&None, &Some(program_type)).unwrap_err();
+            assert!(err
+                .to_string()
+                .contains("can't be loaded without attaching them"));
+        }
+        assert!(get_load_info(&None, &None).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman_api::v1::{
+    bpfman_client::BpfmanClient, MapDeleteRequest, MapDumpRequest, MapLookupRequest,
+    MapUpdateRequest,
+};
+use comfy_table::Table;
+use hex::encode_upper;
+use tonic::transport::Channel;
+
+use crate::cli::args::{MapArgs, MapKeyArgs, MapSubcommand, MapUpdateArgs};
+
+// Flags for the bpf map update command.
+const BPF_ANY: u64 = 0;
+const BPF_NOEXIST: u64 = 1;
+const BPF_EXIST: u64 = 2;
+
+impl MapSubcommand {
+    pub(crate) async fn execute(&self, channel: Channel) -> anyhow::Result<()> {
+        match self {
+            MapSubcommand::Lookup(args) => execute_lookup(args, channel).await,
+            MapSubcommand::Update(args) => execute_update(args, channel).await,
+            MapSubcommand::Delete(args) => execute_delete(args, channel).await,
+            MapSubcommand::Dump(args) => execute_dump(args, channel).await,
+        }
+    }
+}
+
+async fn execute_lookup(args: &MapKeyArgs, channel: Channel) -> anyhow::Result<()> {
+    let mut client = BpfmanClient::new(channel);
+    let request = tonic::Request::new(MapLookupRequest {
+        id: args.map.id,
+        map_name: args.map.map_name.clone(),
+        key: args.key.clone(),
+    });
+    let response = client.map_lookup(request).await?.into_inner();
+
+    println!("{}", encode_upper(response.value));
+    Ok(())
+}
+
+async fn execute_update(args: &MapUpdateArgs, channel: Channel) -> anyhow::Result<()> {
+    let mut client = BpfmanClient::new(channel);
+    let flags = if args.noexist {
+        BPF_NOEXIST
+    } else if args.exist {
+        BPF_EXIST
+    } else {
+        BPF_ANY
+    };
+    let request = tonic::Request::new(MapUpdateRequest {
+        id: args.map.id,
+        map_name: args.map.map_name.clone(),
+        key: args.key.clone(),
+        value: args.value.clone(),
+        flags,
+    });
+    let _response = client.map_update(request).await?.into_inner();
+    Ok(())
+}
+
+async fn execute_delete(args: &MapKeyArgs, channel: Channel) -> anyhow::Result<()> {
+    let mut client = BpfmanClient::new(channel);
+    let request = tonic::Request::new(MapDeleteRequest {
+        id: args.map.id,
+        map_name: args.map.map_name.clone(),
+        key: args.key.clone(),
+    });
+    let _response = client.map_delete(request).await?.into_inner();
+    Ok(())
+}
+
+async fn execute_dump(args: &MapArgs, channel: Channel) -> anyhow::Result<()> {
+    let mut client = BpfmanClient::new(channel);
+    let request = tonic::Request::new(MapDumpRequest {
+        id: args.id,
+        map_name: args.map_name.clone(),
+    });
+    let response = client.map_dump(request).await?.into_inner();
+
+    let mut table = Table::new();
+    table.load_preset(comfy_table::presets::NOTHING);
+    table.set_header(vec!["Key", "Value"]);
+    for entry in response.entries {
+        table.add_row(vec![encode_upper(entry.key), encode_upper(entry.value)]);
+    }
+    println!("{table}\n");
+    Ok(())
+}
@@ -1,31 +1,52 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
+mod apply;
 pub(crate) mod args;
+mod attach;
+mod detach;
 mod get;
 mod image;
 mod list;
 mod load;
+mod map;
+mod output;
+mod replace;
 mod system;
 mod table;
 mod unload;
+mod update;
+mod verify;
+mod watch;
 use std::fs;
 
+use anyhow::{bail, Context};
+
+use apply::execute_apply;
 use args::Commands;
+use attach::execute_attach;
 use bpfman_api::{
     config::Config,
-    util::directories::{CFGPATH_BPFMAN_CONFIG, RTPATH_BPFMAN_SOCKET},
+    util::directories::{
+        CFGPATH_BPFMAN_CLIENT_CERTS_KEY, CFGPATH_BPFMAN_CLIENT_CERTS_PEM, CFGPATH_BPFMAN_CONFIG,
+        CFGPATH_CA_CERTS_PEM, RTPATH_BPFMAN_SOCKET,
+    },
 };
+use detach::execute_detach;
 use get::execute_get;
 use list::execute_list;
 use log::warn;
+use replace::execute_replace;
 use tokio::net::UnixStream;
-use tonic::transport::{Channel, Endpoint, Uri};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity, Uri};
 use tower::service_fn;
 use unload::execute_unload;
+use update::execute_update;
+use verify::execute_verify;
+use watch::execute_watch;
 
 impl Commands {
-    pub(crate) async fn execute(&self) -> Result<(), anyhow::Error> {
+    pub(crate) async fn execute(&self, endpoint: Option<&str>) -> Result<(), anyhow::Error> {
         let config = if let Ok(c) = fs::read_to_string(CFGPATH_BPFMAN_CONFIG) {
             c.parse().unwrap_or_else(|_| {
                 warn!("Unable to parse config file, using defaults");
@@ -36,27 +57,124 @@ impl Commands {
             Config::default()
         };
 
+        // Only the commands that talk to bpfman need a channel.
+        let channel = || select_channel(endpoint);
+
         match self {
-            Commands::Load(l) => l.execute().await,
-            Commands::Unload(args) => execute_unload(args).await,
-            Commands::List(args) => execute_list(args).await,
-            Commands::Get(args) => execute_get(args).await,
-            Commands::Image(i) => i.execute().await,
+            Commands::Load(l) => l.execute(channel()?).await,
+            Commands::Unload(args) => execute_unload(args, channel()?).await,
+            Commands::Attach(args) => execute_attach(args, channel()?).await,
+            Commands::Detach(args) => execute_detach(args, channel()?).await,
+            Commands::Update(args) => execute_update(args, channel()?).await,
+            Commands::Replace(args) => execute_replace(args, channel()?).await,
+            Commands::Verify(args) => execute_verify(args, channel()?).await,
+            Commands::List(args) => execute_list(args, channel()?).await,
+            Commands::Get(args) => execute_get(args, channel()?).await,
+            Commands::Watch(args) => execute_watch(args, channel()?).await,
+            Commands::Apply(args) => execute_apply(args, channel()?).await,
+            Commands::Image(i) => i.execute(channel()?).await,
+            Commands::Map(m) => m.execute(channel()?).await,
             Commands::System(s) => s.execute(&config).await,
         }
     }
 }
 
-fn select_channel() -> Option<Channel> {
-    let path = RTPATH_BPFMAN_SOCKET.to_string();
-
-    let address = Endpoint::try_from(format!("unix:/{path}"));
-    if let Err(e) = address {
-        warn!("Failed to parse unix endpoint: {e:?}");
-        return None;
+// Connects to the unix socket bpfman listens on by default, or to the given
+// endpoint. Remote endpoints use mutual TLS with the bpfman client certificate.
+fn select_channel(endpoint: Option<&str>) -> anyhow::Result<Channel> {
+    let endpoint = match endpoint {
+        Some(e) => e,
+        None => return Ok(unix_channel(RTPATH_BPFMAN_SOCKET.to_string())?),
     };
-    let address = address.unwrap();
-    let channel = address
-        .connect_with_connector_lazy(service_fn(move |_: Uri| UnixStream::connect(path.clone())));
-    Some(channel)
+
+    if let Some(path) = endpoint.strip_prefix("unix://") {
+        return Ok(unix_channel(path.to_string())?);
+    }
+    if !endpoint.starts_with("https://") {
+        bail!("invalid endpoint {endpoint}, expected unix://<PATH> or https://<HOST>:<PORT>");
+    }
+
+    let ca = fs::read(CFGPATH_CA_CERTS_PEM)
+        .with_context(|| format!("unable to read CA certificate {CFGPATH_CA_CERTS_PEM}"))?;
+    let cert = fs::read(CFGPATH_BPFMAN_CLIENT_CERTS_PEM).with_context(|| {
+        format!("unable to read client certificate {CFGPATH_BPFMAN_CLIENT_CERTS_PEM}")
+    })?;
+    let key = fs::read(CFGPATH_BPFMAN_CLIENT_CERTS_KEY)
+        .with_context(|| format!("unable to read client key {CFGPATH_BPFMAN_CLIENT_CERTS_KEY}"))?;
+    let tls = ClientTlsConfig::new()
+        .ca_certificate(Certificate::from_pem(ca))
+        .identity(Identity::from_pem(cert, key));
+
+    let channel = Endpoint::from_shared(endpoint.to_string())
+        .with_context(|| format!("invalid endpoint {endpoint}"))?
+        .tls_config(tls)?
+        .connect_lazy();
+    Ok(channel)
+}
+
+fn unix_channel(path: String) -> Result<Channel, tonic::transport::Error> {
+    // The URI is ignored by the connector, but must be valid.
+    let address = Endpoint::try_from("http://[::]:50051")?;
+    Ok(address
+        .connect_with_connector_lazy(service_fn(move |_: Uri| UnixStream::connect(path.clone()))))
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::args::Cli;
+
+    #[tokio::test]
+    async fn test_select_channel_unix() {
+        // Unix sockets are connected to lazily, so the socket needn't exist.
+        assert!(select_channel(None).is_ok());
+        assert!(select_channel(Some("unix:///tmp/bpfman-test.sock")).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_select_channel_https() {
+        // The client certificates are only read for https endpoints, and may
+        // not be installed where the test runs.
+        if let Err(e) = select_channel(Some("https://node1.example.com:50051")) {
+            assert!(!e.to_string().starts_with("invalid endpoint"), "{e}");
+        }
+    }
+
+    #[test]
+    fn test_select_channel_invalid() {
+        for endpoint in [
+            "http://node1.example.com:50051",
+            "tcp://node1.example.com:50051",
+            "/run/bpfman/sock/bpfman.sock",
+        ] {
+            let err = select_channel(Some(endpoint)).unwrap_err();
+            assert!(err.to_string().starts_with("invalid endpoint"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_endpoint_env() {
+        std::env::set_var("BPFMAN_ENDPOINT", "https://node1.example.com:50051");
+        let cli = Cli::try_parse_from(["bpfman", "list"]).unwrap();
+        assert_eq!(
+            cli.endpoint.as_deref(),
+            Some("https://node1.example.com:50051")
+        );
+
+        // The flag takes precedence over the environment.
+        let cli = Cli::try_parse_from([
+            "bpfman",
+            "list",
+            "--endpoint",
+            "unix:///tmp/bpfman-test.sock",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.endpoint.as_deref(),
+            Some("unix:///tmp/bpfman-test.sock")
+        );
+        std::env::remove_var("BPFMAN_ENDPOINT");
+    }
 }
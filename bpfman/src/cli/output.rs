@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! The JSON and YAML output of the CLI. Scripts depend on these types, so
+//! fields may be added but existing fields must keep their names and meaning.
+
+use std::collections::BTreeMap;
+
+use bpfman_api::{
+    v1::{bytecode_location::Location, KernelProgramInfo, ProgramInfo},
+    AttachOutput, ImagePullPolicy, ProgramType,
+};
+use clap::ValueEnum;
+use hex::encode_upper;
+use serde::Serialize;
+
+#[derive(ValueEnum, Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) enum OutputFormat {
+    #[default]
+    Table,
+    Wide,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Whether the output is a table rather than JSON or YAML.
+    pub(crate) fn is_table(&self) -> bool {
+        matches!(self, OutputFormat::Table | OutputFormat::Wide)
+    }
+}
+
+/// Prints the value as JSON or YAML.
+pub(crate) fn print_structured<T: Serialize>(
+    format: OutputFormat,
+    value: &T,
+) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+        OutputFormat::Table | OutputFormat::Wide => {
+            unreachable!("tables are printed by ProgTable")
+        }
+    }
+    Ok(())
+}
+
+/// A program, with the state kept by bpfman if bpfman loaded it.
+#[derive(Serialize, Debug)]
+pub(crate) struct ProgramOutput {
+    pub(crate) bpfman: Option<BpfmanProgramOutput>,
+    pub(crate) kernel: Option<KernelProgramOutput>,
+}
+
+impl ProgramOutput {
+    pub(crate) fn new(
+        info: Option<ProgramInfo>,
+        kernel_info: Option<KernelProgramInfo>,
+    ) -> anyhow::Result<Self> {
+        Ok(ProgramOutput {
+            bpfman: info.map(TryInto::try_into).transpose()?,
+            kernel: kernel_info.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct BpfmanProgramOutput {
+    name: String,
+    bytecode: Option<BytecodeOutput>,
+    attach: Option<AttachOutput>,
+    links: Vec<LinkOutput>,
+    /// Global data values in hex.
+    global_data: BTreeMap<String, String>,
+    metadata: BTreeMap<String, String>,
+    map_owner_id: Option<u32>,
+    map_pin_path: String,
+    map_used_by: Vec<String>,
+    uuid: Option<String>,
+    expires_at: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub(crate) enum BytecodeOutput {
+    File { path: String },
+    Image { url: String, pull_policy: String },
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct LinkOutput {
+    link_id: u32,
+    attach: Option<AttachOutput>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct KernelProgramOutput {
+    id: u32,
+    name: String,
+    program_type: String,
+    loaded_at: String,
+    tag: String,
+    gpl_compatible: bool,
+    map_ids: Vec<u32>,
+    btf_id: u32,
+    bytes_xlated: u32,
+    jited: bool,
+    bytes_jited: u32,
+    bytes_memlock: u32,
+    verified_insns: u32,
+}
+
+/// An image pulled with `bpfman image pull`.
+#[derive(Serialize, Debug)]
+pub(crate) struct ImageOutput {
+    pub(crate) url: String,
+    pub(crate) pull_policy: String,
+}
+
+impl TryFrom<ProgramInfo> for BpfmanProgramOutput {
+    type Error = anyhow::Error;
+
+    fn try_from(info: ProgramInfo) -> Result<Self, Self::Error> {
+        let bytecode = match info.bytecode.and_then(|b| b.location) {
+            Some(Location::File(path)) => Some(BytecodeOutput::File { path }),
+            Some(Location::Image(i)) => Some(BytecodeOutput::Image {
+                url: i.url,
+                pull_policy: ImagePullPolicy::try_from(i.image_pull_policy)?.to_string(),
+            }),
+            None => None,
+        };
+
+        Ok(BpfmanProgramOutput {
+            name: info.name,
+            bytecode,
+            attach: info
+                .attach
+                .and_then(|a| a.info)
+                .map(TryInto::try_into)
+                .transpose()?,
+            links: info
+                .links
+                .into_iter()
+                .map(|l| {
+                    Ok(LinkOutput {
+                        link_id: l.link_id,
+                        attach: l
+                            .attach
+                            .and_then(|a| a.info)
+                            .map(TryInto::try_into)
+                            .transpose()?,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+            global_data: info
+                .global_data
+                .into_iter()
+                .map(|(k, v)| (k, encode_upper(v)))
+                .collect(),
+            metadata: info.metadata.into_iter().collect(),
+            map_owner_id: info.map_owner_id,
+            map_pin_path: info.map_pin_path,
+            map_used_by: info.map_used_by,
+            uuid: info.uuid,
+            expires_at: info.expires_at,
+        })
+    }
+}
+
+impl TryFrom<KernelProgramInfo> for KernelProgramOutput {
+    type Error = anyhow::Error;
+
+    fn try_from(info: KernelProgramInfo) -> Result<Self, Self::Error> {
+        Ok(KernelProgramOutput {
+            id: info.id,
+            name: info.name,
+            program_type: ProgramType::try_from(info.program_type)?.to_string(),
+            loaded_at: info.loaded_at,
+            tag: info.tag,
+            gpl_compatible: info.gpl_compatible,
+            map_ids: info.map_ids,
+            btf_id: info.btf_id,
+            bytes_xlated: info.bytes_xlated,
+            jited: info.jited,
+            bytes_jited: info.bytes_jited,
+            bytes_memlock: info.bytes_memlock,
+            verified_insns: info.verified_insns,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use bpfman_api::v1::{attach_info::Info, AttachInfo, BytecodeLocation, XdpAttachInfo};
+
+    use super::*;
+
+    #[test]
+    fn test_program_output_schema() {
+        let info = ProgramInfo {
+            name: "pass".to_string(),
+            bytecode: Some(BytecodeLocation {
+                location: Some(Location::File("/opt/pass.o".to_string())),
+            }),
+            attach: Some(AttachInfo {
+                info: Some(Info::XdpAttachInfo(XdpAttachInfo {
+                    priority: 50,
+                    iface: "eth0".to_string(),
+                    position: 0,
+                    proceed_on: vec![2, 31],
+                })),
+            }),
+            global_data: HashMap::from([("GLOBAL_u8".to_string(), vec![1])]),
+            ..Default::default()
+        };
+        let kernel_info = KernelProgramInfo {
+            id: 6213,
+            name: "pass".to_string(),
+            program_type: ProgramType::Xdp as u32,
+            ..Default::default()
+        };
+
+        let output = ProgramOutput::new(Some(info), Some(kernel_info)).unwrap();
+        let json = serde_json::to_value(output).unwrap();
+        assert_eq!(json["bpfman"]["bytecode"]["type"], "file");
+        assert_eq!(json["bpfman"]["bytecode"]["path"], "/opt/pass.o");
+        assert_eq!(json["bpfman"]["attach"]["type"], "xdp");
+        assert_eq!(
+            json["bpfman"]["attach"]["proceed_on"],
+            serde_json::json!(["pass", "dispatcher_return"])
+        );
+        assert_eq!(json["bpfman"]["global_data"]["GLOBAL_u8"], "01");
+        assert_eq!(json["kernel"]["id"], 6213);
+        assert_eq!(json["kernel"]["program_type"], "xdp");
+    }
+}
@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman_api::v1::{
+    bpfman_client::BpfmanClient, bytecode_location::Location, BytecodeImage, BytecodeLocation,
+    ReplaceRequest,
+};
+use tonic::transport::Channel;
+
+use crate::cli::{
+    args::{ReplaceArgs, ReplaceSubcommand},
+    load::parse_global,
+    table::ProgTable,
+};
+
+pub(crate) async fn execute_replace(args: &ReplaceArgs, channel: Channel) -> anyhow::Result<()> {
+    let mut client = BpfmanClient::new(channel);
+
+    let (location, name, global) = match &args.command {
+        ReplaceSubcommand::File(f) => (Location::File(f.path.clone()), &f.name, &f.global),
+        ReplaceSubcommand::Image(i) => (
+            Location::Image(BytecodeImage::try_from(&i.pull_args)?),
+            &i.name,
+            &i.global,
+        ),
+    };
+
+    let request = tonic::Request::new(ReplaceRequest {
+        id: args.id,
+        bytecode: Some(BytecodeLocation {
+            location: Some(location),
+        }),
+        name: name.clone(),
+        global_data: parse_global(global),
+    });
+    let response = client.replace(request).await?.into_inner();
+
+    ProgTable::new_get_bpfman(&response.info)?.print();
+    ProgTable::new_get_unsupported(&response.kernel_info)?.print();
+    Ok(())
+}
@@ -5,8 +5,10 @@ use anyhow::bail;
 use bpfman_api::{
     v1::{
         attach_info::Info, bytecode_location::Location, list_response::ListResult,
-        KernelProgramInfo, KprobeAttachInfo, ProgramInfo, TcAttachInfo, TracepointAttachInfo,
-        UprobeAttachInfo, XdpAttachInfo,
+        BtfTracepointAttachInfo, CgroupAttachInfo, FentryAttachInfo, FexitAttachInfo,
+        KernelProgramInfo, KprobeAttachInfo, LsmAttachInfo, PerfEventAttachInfo, ProgramInfo,
+        RawTracepointAttachInfo, TcAttachInfo, TracepointAttachInfo, UprobeAttachInfo,
+        XdpAttachInfo,
     },
     ImagePullPolicy,
     ProbeType::{Kprobe, Kretprobe, Uprobe, Uretprobe},
@@ -101,6 +103,16 @@ impl ProgTable {
             None => table.add_row(vec!["Map Owner ID:", "None"]),
         };
 
+        // Only programs loaded by `bpfman apply` have a uuid.
+        if let Some(uuid) = &info.uuid {
+            table.add_row(vec!["UUID:", uuid]);
+        }
+
+        // Only programs loaded with a ttl expire.
+        if let Some(expires_at) = &info.expires_at {
+            table.add_row(vec!["Expires At:", expires_at]);
+        }
+
         if info.map_used_by.clone().is_empty() {
             table.add_row(vec!["Maps Used By:", "None"]);
         } else {
@@ -115,87 +127,18 @@ impl ProgTable {
             }
         };
 
-        if info.attach.is_some() {
-            match info.attach.clone().unwrap().info.unwrap() {
-                Info::XdpAttachInfo(XdpAttachInfo {
-                    priority,
-                    iface,
-                    position,
-                    proceed_on,
-                }) => {
-                    let proc_on = match XdpProceedOn::from_int32s(proceed_on) {
-                        Ok(p) => p,
-                        Err(e) => bail!("error parsing proceed_on {e}"),
-                    };
-
-                    table.add_row(vec!["Priority:", &priority.to_string()]);
-                    table.add_row(vec!["Iface:", &iface]);
-                    table.add_row(vec!["Position:", &position.to_string()]);
-                    table.add_row(vec!["Proceed On:", &format!("{proc_on}")]);
-                }
-                Info::TcAttachInfo(TcAttachInfo {
-                    priority,
-                    iface,
-                    position,
-                    direction,
-                    proceed_on,
-                }) => {
-                    let proc_on = match TcProceedOn::from_int32s(proceed_on) {
-                        Ok(p) => p,
-                        Err(e) => bail!("error parsing proceed_on {e}"),
-                    };
-
-                    table.add_row(vec!["Priority:", &priority.to_string()]);
-                    table.add_row(vec!["Iface:", &iface]);
-                    table.add_row(vec!["Position:", &position.to_string()]);
-                    table.add_row(vec!["Direction:", &direction]);
-                    table.add_row(vec!["Proceed On:", &format!("{proc_on}")]);
-                }
-                Info::TracepointAttachInfo(TracepointAttachInfo { tracepoint }) => {
-                    table.add_row(vec!["Tracepoint:", &tracepoint]);
-                }
-                Info::KprobeAttachInfo(KprobeAttachInfo {
-                    fn_name,
-                    offset,
-                    retprobe,
-                    container_pid,
-                }) => {
-                    let probe_type = match retprobe {
-                        true => Kretprobe,
-                        false => Kprobe,
-                    };
-
-                    table.add_row(vec!["Probe Type:", &format!["{probe_type}"]]);
-                    table.add_row(vec!["Function Name:", &fn_name]);
-                    table.add_row(vec!["Offset:", &offset.to_string()]);
-                    table.add_row(vec![
-                        "Container PID",
-                        &container_pid.unwrap_or(0).to_string(),
-                    ]);
-                }
-                Info::UprobeAttachInfo(UprobeAttachInfo {
-                    fn_name,
-                    offset,
-                    target,
-                    retprobe,
-                    pid,
-                    container_pid,
-                }) => {
-                    let probe_type = match retprobe {
-                        true => Uretprobe,
-                        false => Uprobe,
-                    };
-
-                    table.add_row(vec!["Probe Type:", &format!["{probe_type}"]]);
-                    table.add_row(vec!["Function Name:", &fn_name.unwrap_or("".to_string())]);
-                    table.add_row(vec!["Offset:", &offset.to_string()]);
-                    table.add_row(vec!["Target:", &target]);
-                    table.add_row(vec!["PID", &pid.unwrap_or(0).to_string()]);
-                    table.add_row(vec![
-                        "Container PID",
-                        &container_pid.unwrap_or(0).to_string(),
-                    ]);
-                }
+        match info.attach.and_then(|a| a.info) {
+            Some(attach_info) => add_attach_info_rows(&mut table, attach_info)?,
+            // loaded without being attached
+            None => {
+                table.add_row(vec!["Attach Info:", "None"]);
+            }
+        }
+
+        for link in info.links {
+            table.add_row(vec!["Link ID:", &link.link_id.to_string()]);
+            if let Some(attach_info) = link.attach.and_then(|a| a.info) {
+                add_attach_info_rows(&mut table, attach_info)?;
             }
         }
 
@@ -271,6 +214,23 @@ impl ProgTable {
         ProgTable(table)
     }
 
+    pub(crate) fn new_list_wide() -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec![
+            "Program ID",
+            "Name",
+            "Type",
+            "Load Time",
+            "Tag",
+            "Map IDs",
+            "Attach Info",
+            "Metadata",
+        ]);
+        ProgTable(table)
+    }
+
     pub(crate) fn add_row_list(
         &mut self,
         id: String,
@@ -298,6 +258,45 @@ impl ProgTable {
         Ok(())
     }
 
+    pub(crate) fn add_response_prog_wide(&mut self, r: ListResult) -> anyhow::Result<()> {
+        if r.kernel_info.is_none() {
+            self.0.add_row(vec!["NONE"]);
+            return Ok(());
+        }
+        let kernel_info = r.kernel_info.unwrap();
+
+        // Programs not loaded by bpfman only have kernel info.
+        let (attach, metadata) = match r.info {
+            Some(info) => {
+                let attach = match info.attach.and_then(|a| a.info) {
+                    Some(attach_info) => attach_info_summary(attach_info)?,
+                    None => "None".to_string(),
+                };
+                let mut metadata: Vec<_> = info
+                    .metadata
+                    .iter()
+                    .map(|(k, v)| format!("{k}={v}"))
+                    .collect();
+                metadata.sort();
+                (attach, metadata.join(","))
+            }
+            None => ("".to_string(), "".to_string()),
+        };
+
+        self.0.add_row(vec![
+            kernel_info.id.to_string(),
+            kernel_info.name,
+            (ProgramType::try_from(kernel_info.program_type)?).to_string(),
+            kernel_info.loaded_at,
+            kernel_info.tag,
+            format!("{:?}", kernel_info.map_ids),
+            attach,
+            metadata,
+        ]);
+
+        Ok(())
+    }
+
     pub(crate) fn print(&self) {
         println!("{self}\n")
     }
@@ -308,3 +307,213 @@ impl std::fmt::Display for ProgTable {
         write!(f, "{}", self.0)
     }
 }
+
+// Describes where a program is attached on a single line.
+fn attach_info_summary(attach_info: Info) -> Result<String, anyhow::Error> {
+    Ok(match attach_info {
+        Info::XdpAttachInfo(XdpAttachInfo {
+            priority, iface, ..
+        }) => format!("xdp {iface} priority {priority}"),
+        Info::TcAttachInfo(TcAttachInfo {
+            priority,
+            iface,
+            direction,
+            ..
+        }) => format!("tc {iface} {direction} priority {priority}"),
+        Info::TracepointAttachInfo(TracepointAttachInfo { tracepoint }) => {
+            format!("tracepoint {tracepoint}")
+        }
+        Info::RawTracepointAttachInfo(RawTracepointAttachInfo { tracepoint }) => {
+            format!("raw_tracepoint {tracepoint}")
+        }
+        Info::BtfTracepointAttachInfo(BtfTracepointAttachInfo { tracepoint }) => {
+            format!("tp_btf {tracepoint}")
+        }
+        Info::KprobeAttachInfo(KprobeAttachInfo {
+            fn_name, retprobe, ..
+        }) => {
+            let probe_type = match retprobe {
+                true => Kretprobe,
+                false => Kprobe,
+            };
+            format!("{probe_type} {fn_name}")
+        }
+        Info::UprobeAttachInfo(UprobeAttachInfo {
+            fn_name,
+            target,
+            retprobe,
+            ..
+        }) => {
+            let probe_type = match retprobe {
+                true => Uretprobe,
+                false => Uprobe,
+            };
+            match fn_name {
+                Some(fn_name) => format!("{probe_type} {target}:{fn_name}"),
+                None => format!("{probe_type} {target}"),
+            }
+        }
+        Info::FentryAttachInfo(FentryAttachInfo { fn_name }) => format!("fentry {fn_name}"),
+        Info::FexitAttachInfo(FexitAttachInfo { fn_name }) => format!("fexit {fn_name}"),
+        Info::LsmAttachInfo(LsmAttachInfo { hook }) => format!("lsm {hook}"),
+        Info::CgroupAttachInfo(CgroupAttachInfo {
+            cgroup_path,
+            attach_type,
+            priority,
+            ..
+        }) => format!("{attach_type} {cgroup_path} priority {priority}"),
+        Info::PerfEventAttachInfo(PerfEventAttachInfo {
+            event,
+            sample_period,
+            sample_frequency,
+            ..
+        }) => match (sample_period, sample_frequency) {
+            (Some(period), _) => format!("perf_event {event} period {period}"),
+            (_, Some(frequency)) => format!("perf_event {event} frequency {frequency}"),
+            _ => format!("perf_event {event}"),
+        },
+    })
+}
+
+fn add_attach_info_rows(table: &mut Table, attach_info: Info) -> Result<(), anyhow::Error> {
+    match attach_info {
+        Info::XdpAttachInfo(XdpAttachInfo {
+            priority,
+            iface,
+            position,
+            proceed_on,
+        }) => {
+            let proc_on = match XdpProceedOn::from_int32s(proceed_on) {
+                Ok(p) => p,
+                Err(e) => bail!("error parsing proceed_on {e}"),
+            };
+
+            table.add_row(vec!["Priority:", &priority.to_string()]);
+            table.add_row(vec!["Iface:", &iface]);
+            table.add_row(vec!["Position:", &position.to_string()]);
+            table.add_row(vec!["Proceed On:", &format!("{proc_on}")]);
+        }
+        Info::TcAttachInfo(TcAttachInfo {
+            priority,
+            iface,
+            position,
+            direction,
+            proceed_on,
+        }) => {
+            let proc_on = match TcProceedOn::from_int32s(proceed_on) {
+                Ok(p) => p,
+                Err(e) => bail!("error parsing proceed_on {e}"),
+            };
+
+            table.add_row(vec!["Priority:", &priority.to_string()]);
+            table.add_row(vec!["Iface:", &iface]);
+            table.add_row(vec!["Position:", &position.to_string()]);
+            table.add_row(vec!["Direction:", &direction]);
+            table.add_row(vec!["Proceed On:", &format!("{proc_on}")]);
+        }
+        Info::TracepointAttachInfo(TracepointAttachInfo { tracepoint }) => {
+            table.add_row(vec!["Tracepoint:", &tracepoint]);
+        }
+        Info::RawTracepointAttachInfo(RawTracepointAttachInfo { tracepoint }) => {
+            table.add_row(vec!["Attach Type:", "raw_tracepoint"]);
+            table.add_row(vec!["Tracepoint:", &tracepoint]);
+        }
+        Info::BtfTracepointAttachInfo(BtfTracepointAttachInfo { tracepoint }) => {
+            table.add_row(vec!["Attach Type:", "tp_btf"]);
+            table.add_row(vec!["Tracepoint:", &tracepoint]);
+        }
+        Info::KprobeAttachInfo(KprobeAttachInfo {
+            fn_name,
+            offset,
+            retprobe,
+            container_pid,
+        }) => {
+            let probe_type = match retprobe {
+                true => Kretprobe,
+                false => Kprobe,
+            };
+
+            table.add_row(vec!["Probe Type:", &format!["{probe_type}"]]);
+            table.add_row(vec!["Function Name:", &fn_name]);
+            table.add_row(vec!["Offset:", &offset.to_string()]);
+            table.add_row(vec![
+                "Container PID",
+                &container_pid.unwrap_or(0).to_string(),
+            ]);
+        }
+        Info::UprobeAttachInfo(UprobeAttachInfo {
+            fn_name,
+            offset,
+            target,
+            retprobe,
+            pid,
+            container_pid,
+        }) => {
+            let probe_type = match retprobe {
+                true => Uretprobe,
+                false => Uprobe,
+            };
+
+            table.add_row(vec!["Probe Type:", &format!["{probe_type}"]]);
+            table.add_row(vec!["Function Name:", &fn_name.unwrap_or("".to_string())]);
+            table.add_row(vec!["Offset:", &offset.to_string()]);
+            table.add_row(vec!["Target:", &target]);
+            table.add_row(vec!["PID", &pid.unwrap_or(0).to_string()]);
+            table.add_row(vec![
+                "Container PID",
+                &container_pid.unwrap_or(0).to_string(),
+            ]);
+        }
+        Info::FentryAttachInfo(FentryAttachInfo { fn_name }) => {
+            table.add_row(vec!["Attach Type:", "fentry"]);
+            table.add_row(vec!["Function Name:", &fn_name]);
+        }
+        Info::FexitAttachInfo(FexitAttachInfo { fn_name }) => {
+            table.add_row(vec!["Attach Type:", "fexit"]);
+            table.add_row(vec!["Function Name:", &fn_name]);
+        }
+        Info::LsmAttachInfo(LsmAttachInfo { hook }) => {
+            table.add_row(vec!["Attach Type:", "lsm"]);
+            table.add_row(vec!["Hook:", &hook]);
+        }
+        Info::CgroupAttachInfo(CgroupAttachInfo {
+            cgroup_path,
+            attach_type,
+            priority,
+            position,
+        }) => {
+            table.add_row(vec!["Attach Type:", &attach_type]);
+            table.add_row(vec!["Cgroup Path:", &cgroup_path]);
+            table.add_row(vec!["Priority:", &priority.to_string()]);
+            table.add_row(vec!["Position:", &position.to_string()]);
+        }
+        Info::PerfEventAttachInfo(PerfEventAttachInfo {
+            event_type,
+            event,
+            sample_period,
+            sample_frequency,
+            cpu,
+            pid,
+        }) => {
+            table.add_row(vec!["Attach Type:", "perf_event"]);
+            table.add_row(vec!["Event Type:", &event_type]);
+            table.add_row(vec!["Event:", &event]);
+            if let Some(period) = sample_period {
+                table.add_row(vec!["Sample Period:", &period.to_string()]);
+            }
+            if let Some(frequency) = sample_frequency {
+                table.add_row(vec!["Sample Frequency:", &frequency.to_string()]);
+            }
+            table.add_row(vec![
+                "CPU:",
+                &cpu.map_or("all".to_string(), |c| c.to_string()),
+            ]);
+            table.add_row(vec![
+                "PID:",
+                &pid.map_or("all".to_string(), |p| p.to_string()),
+            ]);
+        }
+    }
+
+    Ok(())
+}
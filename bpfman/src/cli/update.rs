@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use anyhow::bail;
+use bpfman_api::{
+    v1::{attach_info::Info, bpfman_client::BpfmanClient, GetRequest, UpdateRequest},
+    TcProceedOn, XdpProceedOn,
+};
+use tonic::transport::Channel;
+
+use crate::cli::{args::UpdateArgs, table::ProgTable};
+
+pub(crate) async fn execute_update(
+    args: &UpdateArgs,
+    channel: Channel,
+) -> Result<(), anyhow::Error> {
+    let mut client = BpfmanClient::new(channel);
+
+    // The proceed-on values are named per program type, so look up the
+    // program to know how to parse them.
+    let proceed_on = if args.proceed_on.is_empty() {
+        vec![]
+    } else {
+        let request = tonic::Request::new(GetRequest { id: args.id });
+        let response = client.get(request).await?.into_inner();
+        match response.info.and_then(|i| i.attach).and_then(|a| a.info) {
+            Some(Info::XdpAttachInfo(_)) => match XdpProceedOn::from_strings(&args.proceed_on) {
+                Ok(p) => p.as_action_vec(),
+                Err(e) => bail!("error parsing proceed_on {e}"),
+            },
+            Some(Info::TcAttachInfo(_)) => match TcProceedOn::from_strings(&args.proceed_on) {
+                Ok(p) => p.as_action_vec(),
+                Err(e) => bail!("error parsing proceed_on {e}"),
+            },
+            _ => bail!("--proceed-on is only supported for XDP and TC programs"),
+        }
+    };
+
+    let request = tonic::Request::new(UpdateRequest {
+        id: args.id,
+        priority: args.priority,
+        proceed_on,
+        metadata: args
+            .metadata
+            .clone()
+            .unwrap_or_default()
+            .iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect(),
+    });
+    let response = client.update(request).await?.into_inner();
+
+    ProgTable::new_get_bpfman(&response.info)?.print();
+    ProgTable::new_get_unsupported(&response.kernel_info)?.print();
+    Ok(())
+}
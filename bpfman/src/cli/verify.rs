@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman_api::{
+    v1::{
+        bpfman_client::BpfmanClient, bytecode_location::Location, BytecodeImage, BytecodeLocation,
+        VerifyRequest,
+    },
+    ProgramType,
+};
+use comfy_table::{Cell, Color, Table};
+use tonic::transport::Channel;
+
+use crate::cli::{
+    args::VerifySubcommand,
+    load::{parse_global, print_verifier_log},
+};
+
+pub(crate) async fn execute_verify(
+    args: &VerifySubcommand,
+    channel: Channel,
+) -> anyhow::Result<()> {
+    let mut client = BpfmanClient::new(channel);
+
+    let (location, name, verify) = match args {
+        VerifySubcommand::File(f) => (Location::File(f.path.clone()), &f.name, &f.verify),
+        VerifySubcommand::Image(i) => (
+            Location::Image(BytecodeImage::try_from(&i.pull_args)?),
+            &i.name,
+            &i.verify,
+        ),
+    };
+
+    let request = tonic::Request::new(VerifyRequest {
+        bytecode: Some(BytecodeLocation {
+            location: Some(location),
+        }),
+        name: name.clone(),
+        global_data: parse_global(&verify.global),
+        fn_name: verify.fn_name.clone(),
+        verifier_log_level: verify.verifier_log_level,
+    });
+    let response = client
+        .verify(request)
+        .await
+        .map_err(print_verifier_log)?
+        .into_inner();
+
+    let mut table = Table::new();
+    table.load_preset(comfy_table::presets::NOTHING);
+    table.set_header(vec![Cell::new("Verified")
+        .add_attribute(comfy_table::Attribute::Bold)
+        .add_attribute(comfy_table::Attribute::Underlined)
+        .fg(Color::Green)]);
+    table.add_rows(vec![
+        vec!["Name:".to_string(), response.name],
+        vec![
+            "Type:".to_string(),
+            ProgramType::try_from(response.program_type)?.to_string(),
+        ],
+        vec![
+            "Verified Instruction Count:".to_string(),
+            response.verified_insns.to_string(),
+        ],
+        vec![
+            "Size Translated (bytes):".to_string(),
+            response.bytes_xlated.to_string(),
+        ],
+        vec!["Size JITted:".to_string(), response.bytes_jited.to_string()],
+    ]);
+    println!("{table}\n");
+
+    let mut table = Table::new();
+    table.load_preset(comfy_table::presets::NOTHING);
+    table.set_header(vec![
+        "Map Name",
+        "Map Type",
+        "Key Size",
+        "Value Size",
+        "Max Entries",
+    ]);
+    for map in response.maps {
+        table.add_row(vec![
+            map.name,
+            map.map_type.to_string(),
+            map.key_size.to_string(),
+            map.value_size.to_string(),
+            map.max_entries.to_string(),
+        ]);
+    }
+    println!("{table}");
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman_api::{
+    v1::{bpfman_client::BpfmanClient, WatchRequest},
+    ProgramEventType, ProgramType,
+};
+use tonic::transport::Channel;
+
+use crate::cli::args::WatchArgs;
+
+pub(crate) async fn execute_watch(args: &WatchArgs, channel: Channel) -> anyhow::Result<()> {
+    let mut client = BpfmanClient::new(channel);
+
+    let request = tonic::Request::new(WatchRequest {
+        program_type: args.program_type.map(|p| p as u32),
+        // Transform metadata from a vec of tuples to an owned map.
+        match_metadata: args
+            .metadata_selector
+            .clone()
+            .unwrap_or_default()
+            .iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect(),
+    });
+    let mut stream = client.watch(request).await?.into_inner();
+
+    while let Some(event) = stream.message().await? {
+        let event_type = ProgramEventType::try_from(event.event_type)?;
+        let (id, program_type) = match event.kernel_info {
+            Some(k) => (k.id, ProgramType::try_from(k.program_type)?.to_string()),
+            None => (0, "None".to_string()),
+        };
+        let name = event.info.map(|i| i.name).unwrap_or_default();
+
+        let mut line = format!("{event_type} id={id} type={program_type} name={name}");
+        if let Some(link_id) = event.link_id {
+            line.push_str(&format!(" link_id={link_id}"));
+        }
+        if let Some(replaced_id) = event.replaced_id {
+            line.push_str(&format!(" replaced_id={replaced_id}"));
+        }
+        println!("{line}");
+    }
+
+    Ok(())
+}
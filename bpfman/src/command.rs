@@ -6,18 +6,22 @@ use std::{
     collections::HashMap,
     fmt, fs,
     path::{Path, PathBuf},
-    time::SystemTime,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use aya::programs::ProgramInfo as AyaProgInfo;
 use bpfman_api::{
     util::directories::RTDIR_FS,
     v1::{
-        attach_info::Info, bytecode_location::Location as V1Location, AttachInfo, BytecodeLocation,
-        KernelProgramInfo as V1KernelProgramInfo, KprobeAttachInfo, ProgramInfo as V1ProgramInfo,
-        TcAttachInfo, TracepointAttachInfo, UprobeAttachInfo, XdpAttachInfo,
+        attach_info::Info, bytecode_location::Location as V1Location, AttachInfo,
+        BtfTracepointAttachInfo, BytecodeLocation, CgroupAttachInfo, FentryAttachInfo,
+        FexitAttachInfo, KernelProgramInfo as V1KernelProgramInfo, KprobeAttachInfo,
+        LinkInfo as V1LinkInfo, LsmAttachInfo, PerfEventAttachInfo, ProgramInfo as V1ProgramInfo,
+        RawTracepointAttachInfo, TcAttachInfo, TracepointAttachInfo, UprobeAttachInfo,
+        WatchResponse, XdpAttachInfo,
     },
-    ParseError, ProgramType, TcProceedOn, TcProceedOnEntry, XdpProceedOn, XdpProceedOnEntry,
+    ApplyAction, CgroupAttachType, ParseError, PerfEventType, ProgramEventType, ProgramType,
+    TcProceedOn, TcProceedOnEntry, XdpProceedOn, XdpProceedOnEntry,
 };
 use chrono::{prelude::DateTime, Local};
 use log::info;
@@ -26,9 +30,12 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc::Sender, oneshot};
 
 use crate::{
+    bound_process::BoundProcess,
     errors::BpfmanError,
+    maps::KernelMapInfo,
     multiprog::{DispatcherId, DispatcherInfo},
-    oci_utils::image_manager::{BytecodeImage, Command as ImageManagerCommand},
+    oci_utils::image_manager::{BytecodeImage, Command as ImageManagerCommand, PulledImage},
+    static_program::StaticPrograms,
     utils::{
         bytes_to_bool, bytes_to_i32, bytes_to_string, bytes_to_u32, bytes_to_u64, bytes_to_usize,
     },
@@ -39,6 +46,37 @@ use crate::{
 /// the command response back to the requester.
 type Responder<T> = oneshot::Sender<T>;
 
+// Program database keys which describe how a program is attached. They are
+// not copied to the database tree of a link, but are copied to the program that
+// replaces it.
+const ATTACH_KEY_PREFIXES: &[&str] = &[
+    "xdp_",
+    "tc_",
+    "tracepoint_",
+    "raw_tracepoint_",
+    "tp_btf_",
+    "kprobe_",
+    "uprobe_",
+    "fentry_",
+    "fexit_",
+    "lsm_",
+    "cgroup_",
+    "perf_event_",
+    "load_only",
+];
+
+// Formats a time the way times are shown to users, such as when a program was
+// loaded.
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time)
+        .format("%Y-%m-%dT%H:%M:%S%z")
+        .to_string()
+}
+
+pub(crate) fn link_tree_name(link_id: u32) -> String {
+    format!("link_{link_id}")
+}
+
 /// Multiple different commands are multiplexed over a single channel.
 #[derive(Debug)]
 pub(crate) enum Command {
@@ -50,11 +88,26 @@ pub(crate) enum Command {
     },
     Get(GetArgs),
     PullBytecode(PullBytecodeArgs),
+    Attach(AttachArgs),
+    Detach(DetachArgs),
+    Update(UpdateArgs),
+    Replace(ReplaceArgs),
+    Map(MapArgs),
+    Verify(VerifyArgs),
+    Apply(ApplyArgs),
+    /// Reconcile the loaded static programs with the programs read from the
+    /// static programs directory.
+    StaticPrograms(StaticPrograms),
+    /// Unload the programs bound to a process that has exited.
+    ProcessExited(BoundProcess),
 }
 
 #[derive(Debug)]
 pub(crate) struct LoadArgs {
     pub(crate) program: Program,
+    /// When set, the command fails unless the program whose maps are shared
+    /// through the map owner id was loaded by this owner.
+    pub(crate) owner: Option<String>,
     pub(crate) responder: Responder<Result<Program, BpfmanError>>,
 }
 
@@ -63,14 +116,24 @@ pub(crate) enum Program {
     Xdp(XdpProgram),
     Tc(TcProgram),
     Tracepoint(TracepointProgram),
+    RawTracepoint(RawTracepointProgram),
+    BtfTracepoint(BtfTracepointProgram),
     Kprobe(KprobeProgram),
     Uprobe(UprobeProgram),
+    Fentry(FentryProgram),
+    Fexit(FexitProgram),
+    Lsm(LsmProgram),
+    Cgroup(CgroupProgram),
+    PerfEvent(PerfEventProgram),
     Unsupported(ProgramData),
 }
 
 #[derive(Debug)]
 pub(crate) struct UnloadArgs {
     pub(crate) id: u32,
+    /// When set, the command fails unless the program was loaded by this
+    /// owner.
+    pub(crate) owner: Option<String>,
     pub(crate) responder: Responder<Result<(), BpfmanError>>,
 }
 
@@ -83,6 +146,123 @@ pub(crate) struct GetArgs {
 #[derive(Debug)]
 pub(crate) struct PullBytecodeArgs {
     pub(crate) image: BytecodeImage,
+    /// The digest of the bytecode layer of the image that was pulled.
+    pub(crate) responder: Responder<Result<String, BpfmanError>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct AttachArgs {
+    pub(crate) id: u32,
+    pub(crate) link: Program,
+    /// When set, the command fails unless the program was loaded by this
+    /// owner.
+    pub(crate) owner: Option<String>,
+    pub(crate) responder: Responder<Result<u32, BpfmanError>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct UpdateArgs {
+    pub(crate) id: u32,
+    pub(crate) priority: Option<i32>,
+    pub(crate) proceed_on: Option<Vec<i32>>,
+    pub(crate) metadata: Option<HashMap<String, String>>,
+    /// When set, the command fails unless the program was loaded by this
+    /// owner.
+    pub(crate) owner: Option<String>,
+    pub(crate) responder: Responder<Result<Program, BpfmanError>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ReplaceArgs {
+    pub(crate) id: u32,
+    pub(crate) location: Location,
+    pub(crate) name: Option<String>,
+    pub(crate) global_data: Option<HashMap<String, Vec<u8>>>,
+    /// When set, the command fails unless the program was loaded by this
+    /// owner.
+    pub(crate) owner: Option<String>,
+    pub(crate) responder: Responder<Result<Program, BpfmanError>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct MapArgs {
+    pub(crate) id: u32,
+    pub(crate) map_name: String,
+    pub(crate) operation: MapOperation,
+    /// When set, the command fails unless the program was loaded by this
+    /// owner.
+    pub(crate) owner: Option<String>,
+    /// The key/value pairs that were looked up or dumped.
+    pub(crate) responder: Responder<Result<Vec<(Vec<u8>, Vec<u8>)>, BpfmanError>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ApplyArgs {
+    /// The programs that should be loaded, each with a uuid.
+    pub(crate) programs: Vec<Program>,
+    pub(crate) responder: Responder<Result<Vec<ApplyOutcome>, BpfmanError>>,
+}
+
+/// What Apply did with the program identified by `uuid`.
+#[derive(Debug)]
+pub(crate) struct ApplyOutcome {
+    pub(crate) uuid: String,
+    pub(crate) action: ApplyAction,
+    pub(crate) id: u32,
+    /// Why the program couldn't be applied, when the action is Failed.
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct VerifyArgs {
+    pub(crate) location: Location,
+    pub(crate) name: String,
+    pub(crate) global_data: HashMap<String, Vec<u8>>,
+    /// The kernel function fentry and fexit programs are verified against, the
+    /// hook of an LSM program or the tracepoint of a tp_btf program.
+    pub(crate) fn_name: Option<String>,
+    pub(crate) verifier_log_level: Option<u32>,
+    /// When set, the command fails unless the program is one of these types.
+    pub(crate) program_types: Option<Vec<ProgramType>>,
+    pub(crate) responder: Responder<Result<VerifyResult, BpfmanError>>,
+}
+
+/// What the kernel reported for a program that passed verification.
+#[derive(Debug)]
+pub(crate) struct VerifyResult {
+    pub(crate) name: String,
+    pub(crate) program_type: u32,
+    pub(crate) verified_insns: u32,
+    pub(crate) bytes_xlated: u32,
+    pub(crate) bytes_jited: u32,
+    pub(crate) maps: Vec<KernelMapInfo>,
+}
+
+#[derive(Debug)]
+pub(crate) enum MapOperation {
+    Lookup {
+        key: Vec<u8>,
+    },
+    Update {
+        key: Vec<u8>,
+        value: Vec<u8>,
+        flags: u64,
+    },
+    Delete {
+        key: Vec<u8>,
+    },
+    Dump,
+}
+
+#[derive(Debug)]
+pub(crate) struct DetachArgs {
+    pub(crate) link_id: u32,
+    /// When set, the attachment this program was loaded with is detached
+    /// instead of a link.
+    pub(crate) id: Option<u32>,
+    /// When set, the command fails unless the program was loaded by this
+    /// owner.
+    pub(crate) owner: Option<String>,
     pub(crate) responder: Responder<Result<(), BpfmanError>>,
 }
 
@@ -93,12 +273,12 @@ pub(crate) enum Location {
 }
 
 impl Location {
-    async fn get_program_bytes(
+    pub(crate) async fn get_program_bytes(
         &self,
         image_manager: Sender<ImageManagerCommand>,
-    ) -> Result<(Vec<u8>, String), BpfmanError> {
+    ) -> Result<(Vec<u8>, String, String), BpfmanError> {
         match self {
-            Location::File(l) => Ok((crate::utils::read(l).await?, "".to_owned())),
+            Location::File(l) => Ok((crate::utils::read(l).await?, "".to_owned(), "".to_owned())),
             Location::Image(l) => {
                 let (tx, rx) = oneshot::channel();
                 image_manager
@@ -111,7 +291,11 @@ impl Location {
                     })
                     .await
                     .map_err(|e| BpfmanError::RpcSendError(e.into()))?;
-                let (path, bpf_function_name) = rx
+                let PulledImage {
+                    path,
+                    bpf_function_name,
+                    digest,
+                } = rx
                     .await
                     .map_err(BpfmanError::RpcRecvError)?
                     .map_err(BpfmanError::BpfBytecodeError)?;
@@ -127,7 +311,7 @@ impl Location {
                     .map_err(BpfmanError::RpcRecvError)?
                     .map_err(BpfmanError::BpfBytecodeError)?;
 
-                Ok((bytecode, bpf_function_name))
+                Ok((bytecode, bpf_function_name, digest))
             }
         }
     }
@@ -185,47 +369,27 @@ impl TryFrom<&Program> for V1ProgramInfo {
             }),
         };
 
-        let attach_info = AttachInfo {
-            info: match program.clone() {
-                Program::Xdp(p) => Some(Info::XdpAttachInfo(XdpAttachInfo {
-                    priority: p.get_priority()?,
-                    iface: p.get_iface()?.to_string(),
-                    position: p.get_current_position()?.unwrap_or(0) as i32,
-                    proceed_on: p.get_proceed_on()?.as_action_vec(),
-                })),
-                Program::Tc(p) => Some(Info::TcAttachInfo(TcAttachInfo {
-                    priority: p.get_priority()?,
-                    iface: p.get_iface()?.to_string(),
-                    position: p.get_current_position()?.unwrap_or(0) as i32,
-                    direction: p.get_direction()?.to_string(),
-                    proceed_on: p.get_proceed_on()?.as_action_vec(),
-                })),
-                Program::Tracepoint(p) => Some(Info::TracepointAttachInfo(TracepointAttachInfo {
-                    tracepoint: p.get_tracepoint()?.to_string(),
-                })),
-                Program::Kprobe(p) => Some(Info::KprobeAttachInfo(KprobeAttachInfo {
-                    fn_name: p.get_fn_name()?.to_string(),
-                    offset: p.get_offset()?,
-                    retprobe: p.get_retprobe()?,
-                    container_pid: p.get_container_pid()?,
-                })),
-                Program::Uprobe(p) => Some(Info::UprobeAttachInfo(UprobeAttachInfo {
-                    fn_name: p.get_fn_name()?.map(|v| v.to_string()),
-                    offset: p.get_offset()?,
-                    target: p.get_target()?.to_string(),
-                    retprobe: p.get_retprobe()?,
-                    pid: p.get_pid()?,
-                    container_pid: p.get_container_pid()?,
-                })),
-                Program::Unsupported(_) => None,
-            },
-        };
+        let links = data
+            .get_link_ids()?
+            .iter()
+            .map(|link_id| {
+                let link = Program::new_link_from_db(*link_id)?;
+                Ok(V1LinkInfo {
+                    link_id: *link_id,
+                    attach: Some(AttachInfo {
+                        info: link.attach_info()?,
+                    }),
+                })
+            })
+            .collect::<Result<Vec<_>, BpfmanError>>()?;
 
         // Populate the Program Info with bpfman data
         Ok(V1ProgramInfo {
             name: data.get_name()?.to_string(),
             bytecode,
-            attach: Some(attach_info),
+            attach: program
+                .attach_info()?
+                .map(|info| AttachInfo { info: Some(info) }),
             global_data: data.get_global_data()?,
             map_owner_id: data.get_map_owner_id()?,
             map_pin_path: data
@@ -237,6 +401,86 @@ impl TryFrom<&Program> for V1ProgramInfo {
                 .map(|m| m.to_string())
                 .collect(),
             metadata: data.get_metadata()?,
+            links,
+            uuid: data.get_uuid()?,
+            expires_at: data.get_expires_at()?.map(format_time),
+        })
+    }
+}
+
+impl Program {
+    // Returns the attach info of the program, or None if it was loaded
+    // without being attached.
+    pub(crate) fn attach_info(&self) -> Result<Option<Info>, BpfmanError> {
+        if self.get_data().get_load_only()? {
+            return Ok(None);
+        }
+
+        Ok(match self.clone() {
+            Program::Xdp(p) => Some(Info::XdpAttachInfo(XdpAttachInfo {
+                priority: p.get_priority()?,
+                iface: p.get_iface()?.to_string(),
+                position: p.get_current_position()?.unwrap_or(0) as i32,
+                proceed_on: p.get_proceed_on()?.as_action_vec(),
+            })),
+            Program::Tc(p) => Some(Info::TcAttachInfo(TcAttachInfo {
+                priority: p.get_priority()?,
+                iface: p.get_iface()?.to_string(),
+                position: p.get_current_position()?.unwrap_or(0) as i32,
+                direction: p.get_direction()?.to_string(),
+                proceed_on: p.get_proceed_on()?.as_action_vec(),
+            })),
+            Program::Tracepoint(p) => Some(Info::TracepointAttachInfo(TracepointAttachInfo {
+                tracepoint: p.get_tracepoint()?.to_string(),
+            })),
+            Program::Kprobe(p) => Some(Info::KprobeAttachInfo(KprobeAttachInfo {
+                fn_name: p.get_fn_name()?.to_string(),
+                offset: p.get_offset()?,
+                retprobe: p.get_retprobe()?,
+                container_pid: p.get_container_pid()?,
+            })),
+            Program::Uprobe(p) => Some(Info::UprobeAttachInfo(UprobeAttachInfo {
+                fn_name: p.get_fn_name()?.map(|v| v.to_string()),
+                offset: p.get_offset()?,
+                target: p.get_target()?.to_string(),
+                retprobe: p.get_retprobe()?,
+                pid: p.get_pid()?,
+                container_pid: p.get_container_pid()?,
+            })),
+            Program::RawTracepoint(p) => {
+                Some(Info::RawTracepointAttachInfo(RawTracepointAttachInfo {
+                    tracepoint: p.get_tracepoint()?,
+                }))
+            }
+            Program::BtfTracepoint(p) => {
+                Some(Info::BtfTracepointAttachInfo(BtfTracepointAttachInfo {
+                    tracepoint: p.get_tracepoint()?,
+                }))
+            }
+            Program::Fentry(p) => Some(Info::FentryAttachInfo(FentryAttachInfo {
+                fn_name: p.get_fn_name()?.to_string(),
+            })),
+            Program::Fexit(p) => Some(Info::FexitAttachInfo(FexitAttachInfo {
+                fn_name: p.get_fn_name()?.to_string(),
+            })),
+            Program::Lsm(p) => Some(Info::LsmAttachInfo(LsmAttachInfo {
+                hook: p.get_hook()?.to_string(),
+            })),
+            Program::Cgroup(p) => Some(Info::CgroupAttachInfo(CgroupAttachInfo {
+                cgroup_path: p.get_cgroup_path()?,
+                attach_type: p.get_attach_type()?.to_string(),
+                priority: p.get_priority()?,
+                position: p.get_current_position()?.unwrap_or(0) as i32,
+            })),
+            Program::PerfEvent(p) => Some(Info::PerfEventAttachInfo(PerfEventAttachInfo {
+                event_type: p.get_event_type()?.to_string(),
+                event: p.get_event()?,
+                sample_period: p.get_sample_period()?,
+                sample_frequency: p.get_sample_frequency()?,
+                cpu: p.get_cpu()?,
+                pid: p.get_pid()?,
+            })),
+            Program::Unsupported(_) => None,
         })
     }
 }
@@ -267,6 +511,64 @@ impl TryFrom<&Program> for V1KernelProgramInfo {
     }
 }
 
+/// A program lifecycle event, sent from the manager task to the Watch
+/// requesters. The program's state is captured when the event happens, since
+/// it may be deleted by the time the event is received.
+#[derive(Debug, Clone)]
+pub(crate) struct ProgramEvent {
+    pub(crate) event_type: ProgramEventType,
+    pub(crate) program_type: ProgramType,
+    pub(crate) link_id: Option<u32>,
+    pub(crate) replaced_id: Option<u32>,
+    pub(crate) info: V1ProgramInfo,
+    pub(crate) kernel_info: V1KernelProgramInfo,
+}
+
+impl ProgramEvent {
+    pub(crate) fn new(
+        event_type: ProgramEventType,
+        program: &Program,
+    ) -> Result<Self, BpfmanError> {
+        Ok(ProgramEvent {
+            event_type,
+            program_type: program.kind(),
+            link_id: program.get_data().get_link_id()?,
+            replaced_id: None,
+            info: program.try_into()?,
+            kernel_info: program.try_into()?,
+        })
+    }
+
+    // Events are filtered in the same way as programs are by the List command.
+    pub(crate) fn matches(
+        &self,
+        program_type: Option<u32>,
+        match_metadata: &HashMap<String, String>,
+    ) -> bool {
+        if let Some(p) = program_type {
+            if p != self.program_type as u32 {
+                return false;
+            }
+        }
+
+        match_metadata
+            .iter()
+            .all(|(key, value)| self.info.metadata.get(key) == Some(value))
+    }
+}
+
+impl From<ProgramEvent> for WatchResponse {
+    fn from(event: ProgramEvent) -> Self {
+        WatchResponse {
+            event_type: event.event_type as i32,
+            info: Some(event.info),
+            kernel_info: Some(event.kernel_info),
+            link_id: event.link_id,
+            replaced_id: event.replaced_id,
+        }
+    }
+}
+
 /// ProgramInfo stores information about bpf programs that are loaded and managed
 /// by bpfman.
 #[derive(Debug, Clone)]
@@ -323,9 +625,84 @@ impl ProgramData {
         Ok(pd)
     }
 
+    // Links are created with a temporary tree that only holds the attach info,
+    // following attach it is replaced with the link database tree.
+    pub(crate) fn new_pre_attach() -> Self {
+        let mut rng = rand::thread_rng();
+        let id_rand = rng.gen::<u32>();
+
+        let db_tree = ROOT_DB
+            .open_tree(id_rand.to_string())
+            .expect("Unable to open link database tree");
+
+        Self {
+            db_tree,
+            id: id_rand,
+            program_bytes: Vec::new(),
+        }
+    }
+
     pub(crate) fn swap_tree(&mut self, new_id: u32) -> Result<(), BpfmanError> {
+        self.move_to_tree(&new_id.to_string())?;
+        self.id = new_id;
+
+        Ok(())
+    }
+
+    // Turn a link's pre-attach tree into the database tree of link `link_id`,
+    // pulling in everything but the attach info from the program it belongs to.
+    pub(crate) fn set_link(
+        &mut self,
+        program: &ProgramData,
+        link_id: u32,
+    ) -> Result<(), BpfmanError> {
+        for r in program.db_tree.into_iter() {
+            let (k, v) = r.expect("unable to iterate db_tree");
+            let key = bytes_to_string(&k);
+            if ATTACH_KEY_PREFIXES
+                .iter()
+                .any(|prefix| key.starts_with(prefix))
+                || key.starts_with("link_ids_")
+                || self.get_option(&key)?.is_some()
+            {
+                continue;
+            }
+            self.insert(&key, &v)?;
+        }
+        self.insert("link_id", &link_id.to_ne_bytes())?;
+        self.move_to_tree(&link_tree_name(link_id))?;
+        self.id = program.id;
+
+        Ok(())
+    }
+
+    // Copy how `program` is attached, but not whether it currently is, into
+    // this pre-load tree.
+    fn copy_attach_info(&mut self, program: &ProgramData) -> Result<(), BpfmanError> {
+        for r in program.db_tree.into_iter() {
+            let (k, v) = r.expect("unable to iterate db_tree");
+            let key = bytes_to_string(&k);
+            if (key == "kind"
+                || ATTACH_KEY_PREFIXES
+                    .iter()
+                    .any(|prefix| key.starts_with(prefix)))
+                && !key.ends_with("_attached")
+            {
+                self.insert(&key, &v)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Removes the temporary database tree of a program that won't be loaded.
+    pub(crate) fn discard(&self) {
+        let _ = ROOT_DB.drop_tree(self.db_tree.name());
+    }
+
+    fn move_to_tree(&mut self, name: &str) -> Result<(), BpfmanError> {
         let new_tree = ROOT_DB
-            .open_tree(new_id.to_string())
+            .open_tree(name)
             .expect("Unable to open program database tree");
 
         // Copy over all key's and values to new tree
@@ -344,7 +721,6 @@ impl ProgramData {
             .expect("unable to delete temporary program tree");
 
         self.db_tree = new_tree;
-        self.id = new_id;
 
         Ok(())
     }
@@ -402,6 +778,15 @@ impl ProgramData {
         })
     }
 
+    // Removes all entries of a list or map value, so that it can be set again.
+    fn remove_prefix(&self, prefix: &str) {
+        self.db_tree.scan_prefix(prefix).for_each(|n| {
+            self.db_tree
+                .remove(n.unwrap().0)
+                .expect("unable to remove database entry");
+        });
+    }
+
     /*
      * Methods for setting and getting program data for programs managed by
      * bpfman.
@@ -525,6 +910,8 @@ impl ProgramData {
         &mut self,
         data: HashMap<String, String>,
     ) -> Result<(), BpfmanError> {
+        self.remove_prefix("metadata_");
+
         data.iter()
             .try_for_each(|(k, v)| self.insert(format!("metadata_{k}").as_str(), v.as_bytes()))
     }
@@ -560,6 +947,115 @@ impl ProgramData {
             .map(|v| v.map(|v| bytes_to_u32(v.to_vec())))
     }
 
+    pub(crate) fn set_verifier_log_level(&mut self, level: u32) -> Result<(), BpfmanError> {
+        self.insert("verifier_log_level", &level.to_ne_bytes())
+    }
+
+    pub(crate) fn get_verifier_log_level(&self) -> Result<Option<u32>, BpfmanError> {
+        self.get_option("verifier_log_level")
+            .map(|v| v.map(|v| bytes_to_u32(v.to_vec())))
+    }
+
+    // Programs loaded with unload_on_exit record the process they are bound
+    // to.
+    pub(crate) fn set_bound_process(&mut self, process: BoundProcess) -> Result<(), BpfmanError> {
+        self.insert("bound_pid", &process.pid.to_ne_bytes())?;
+        self.insert("bound_start_time", &process.start_time.to_ne_bytes())
+    }
+
+    pub(crate) fn get_bound_process(&self) -> Result<Option<BoundProcess>, BpfmanError> {
+        match (
+            self.get_option("bound_pid")?,
+            self.get_option("bound_start_time")?,
+        ) {
+            (Some(pid), Some(start_time)) => Ok(Some(BoundProcess {
+                pid: bytes_to_i32(pid.to_vec()),
+                start_time: bytes_to_u64(start_time.to_vec()),
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    // Programs loaded with a ttl record when they are to be unloaded, in
+    // seconds since the epoch.
+    pub(crate) fn set_expires_at(&mut self, expires_at: SystemTime) -> Result<(), BpfmanError> {
+        let secs = expires_at
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| BpfmanError::Error(format!("invalid expiry time: {e}")))?
+            .as_secs();
+        self.insert("expires_at", &secs.to_ne_bytes())
+    }
+
+    pub(crate) fn get_expires_at(&self) -> Result<Option<SystemTime>, BpfmanError> {
+        self.get_option("expires_at")
+            .map(|v| v.map(|v| UNIX_EPOCH + Duration::from_secs(bytes_to_u64(v.to_vec()))))
+    }
+
+    // The digest of the bytecode layer of the image the program was loaded
+    // from.
+    pub(crate) fn set_image_digest(&mut self, digest: &str) -> Result<(), BpfmanError> {
+        self.insert("image_digest", digest.as_bytes())
+    }
+
+    pub(crate) fn get_image_digest(&self) -> Result<Option<String>, BpfmanError> {
+        self.get_option("image_digest")
+            .map(|v| v.map(|v| bytes_to_string(&v)))
+    }
+
+    pub(crate) fn set_uuid(&mut self, uuid: &str) -> Result<(), BpfmanError> {
+        self.insert("uuid", uuid.as_bytes())
+    }
+
+    pub(crate) fn get_uuid(&self) -> Result<Option<String>, BpfmanError> {
+        self.get_option("uuid")
+            .map(|v| v.map(|v| bytes_to_string(&v)))
+    }
+
+    // The caller that loaded the program, as described by Caller::owner.
+    // Programs loaded without a known caller have no owner.
+    pub(crate) fn set_owner(&mut self, owner: &str) -> Result<(), BpfmanError> {
+        self.insert("owner", owner.as_bytes())
+    }
+
+    pub(crate) fn get_owner(&self) -> Result<Option<String>, BpfmanError> {
+        self.get_option("owner")
+            .map(|v| v.map(|v| bytes_to_string(&v)))
+    }
+
+    // Programs loaded by Apply record a digest of the request they were loaded
+    // from, which leaves out what can be updated in place.
+    pub(crate) fn set_apply_digest(&mut self, digest: &str) -> Result<(), BpfmanError> {
+        self.insert("apply_digest", digest.as_bytes())
+    }
+
+    pub(crate) fn get_apply_digest(&self) -> Result<Option<String>, BpfmanError> {
+        self.get_option("apply_digest")
+            .map(|v| v.map(|v| bytes_to_string(&v)))
+    }
+
+    // Static programs record the file they were read from and a digest of
+    // their entry in it.
+    pub(crate) fn set_static_program(
+        &mut self,
+        file: &str,
+        digest: &str,
+    ) -> Result<(), BpfmanError> {
+        self.insert("static_file", file.as_bytes())?;
+        self.insert("static_digest", digest.as_bytes())
+    }
+
+    pub(crate) fn get_static_program(&self) -> Result<Option<(String, String)>, BpfmanError> {
+        match (
+            self.get_option("static_file")?,
+            self.get_option("static_digest")?,
+        ) {
+            (Some(file), Some(digest)) => {
+                Ok(Some((bytes_to_string(&file), bytes_to_string(&digest))))
+            }
+            _ => Ok(None),
+        }
+    }
+
     pub(crate) fn set_map_pin_path(&mut self, path: &Path) -> Result<(), BpfmanError> {
         self.insert("map_pin_path", path.to_str().unwrap().as_bytes())
     }
@@ -601,6 +1097,45 @@ impl ProgramData {
         });
     }
 
+    pub(crate) fn set_load_only(&mut self, load_only: bool) -> Result<(), BpfmanError> {
+        self.insert("load_only", &(load_only as i8 % 2).to_ne_bytes())
+    }
+
+    pub(crate) fn get_load_only(&self) -> Result<bool, BpfmanError> {
+        Ok(self
+            .get_option("load_only")?
+            .map(|n| bytes_to_bool(n.to_vec()))
+            .unwrap_or(false))
+    }
+
+    // Only set on the database trees of links, see set_link.
+    pub(crate) fn get_link_id(&self) -> Result<Option<u32>, BpfmanError> {
+        Ok(self
+            .get_option("link_id")?
+            .map(|v| bytes_to_u32(v.to_vec())))
+    }
+
+    // set_link_ids is explicitly idempotent, like set_maps_used_by.
+    pub(crate) fn set_link_ids(&mut self, ids: Vec<u32>) -> Result<(), BpfmanError> {
+        self.remove_prefix("link_ids_");
+
+        ids.iter()
+            .enumerate()
+            .try_for_each(|(i, v)| self.insert(format!("link_ids_{i}").as_str(), &v.to_ne_bytes()))
+    }
+
+    pub(crate) fn get_link_ids(&self) -> Result<Vec<u32>, BpfmanError> {
+        self.db_tree
+            .scan_prefix("link_ids_")
+            .map(|n| n.map(|(_, v)| bytes_to_u32(v.to_vec())))
+            .map(|n| {
+                n.map_err(|e| {
+                    BpfmanError::DatabaseError("Failed to get link ids".to_string(), e.to_string())
+                })
+            })
+            .collect()
+    }
+
     /*
      * End bpfman program info getters/setters.
      */
@@ -633,13 +1168,7 @@ impl ProgramData {
         &mut self,
         loaded_at: SystemTime,
     ) -> Result<(), BpfmanError> {
-        self.insert(
-            "kernel_loaded_at",
-            DateTime::<Local>::from(loaded_at)
-                .format("%Y-%m-%dT%H:%M:%S%z")
-                .to_string()
-                .as_bytes(),
-        )
+        self.insert("kernel_loaded_at", format_time(loaded_at).as_bytes())
     }
 
     pub(crate) fn get_kernel_tag(&self) -> Result<String, BpfmanError> {
@@ -782,13 +1311,14 @@ impl ProgramData {
         let loc = self.get_location()?;
         match loc.get_program_bytes(image_manager).await {
             Err(e) => Err(e),
-            Ok((v, s)) => {
+            Ok((v, s, digest)) => {
                 match loc {
                     Location::Image(l) => {
                         info!(
                             "Loading program bytecode from container image: {}",
                             l.get_url()
                         );
+                        self.set_image_digest(&digest)?;
                         // If program name isn't provided and we're loading from a container
                         // image use the program name provided in the image metadata, otherwise
                         // always use the provided program name.
@@ -853,6 +1383,8 @@ impl XdpProgram {
     }
 
     pub(crate) fn set_proceed_on(&mut self, proceed_on: XdpProceedOn) -> Result<(), BpfmanError> {
+        self.data.remove_prefix("xdp_proceed_on_");
+
         proceed_on
             .as_action_vec()
             .iter()
@@ -967,6 +1499,8 @@ impl TcProgram {
     }
 
     pub(crate) fn set_proceed_on(&mut self, proceed_on: TcProceedOn) -> Result<(), BpfmanError> {
+        self.data.remove_prefix("tc_proceed_on_");
+
         proceed_on
             .as_action_vec()
             .iter()
@@ -1261,14 +1795,382 @@ impl UprobeProgram {
     }
 }
 
+/// A raw tracepoint program, which is passed the tracepoint's raw arguments
+/// rather than a record of them.
+#[derive(Debug, Clone)]
+pub(crate) struct RawTracepointProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl RawTracepointProgram {
+    pub(crate) fn new(data: ProgramData, tracepoint: String) -> Result<Self, BpfmanError> {
+        let mut raw_tp_prog = Self { data };
+        raw_tp_prog.set_tracepoint(tracepoint)?;
+        raw_tp_prog
+            .get_data_mut()
+            .set_kind(ProgramType::RawTracepoint)?;
+
+        Ok(raw_tp_prog)
+    }
+
+    pub(crate) fn set_tracepoint(&mut self, tracepoint: String) -> Result<(), BpfmanError> {
+        self.data
+            .insert("raw_tracepoint_name", tracepoint.as_bytes())
+    }
+
+    pub(crate) fn get_tracepoint(&self) -> Result<String, BpfmanError> {
+        self.data
+            .get("raw_tracepoint_name")
+            .map(|v| bytes_to_string(&v))
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
+/// A BTF-enabled tp_btf program, which shares the tracing program type with
+/// fentry and fexit programs.
+#[derive(Debug, Clone)]
+pub(crate) struct BtfTracepointProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl BtfTracepointProgram {
+    pub(crate) fn new(data: ProgramData, tracepoint: String) -> Result<Self, BpfmanError> {
+        let mut tp_btf_prog = Self { data };
+        tp_btf_prog.set_tracepoint(tracepoint)?;
+        tp_btf_prog.get_data_mut().set_kind(ProgramType::Tracing)?;
+
+        Ok(tp_btf_prog)
+    }
+
+    pub(crate) fn set_tracepoint(&mut self, tracepoint: String) -> Result<(), BpfmanError> {
+        self.data.insert("tp_btf_name", tracepoint.as_bytes())
+    }
+
+    pub(crate) fn get_tracepoint(&self) -> Result<String, BpfmanError> {
+        self.data.get("tp_btf_name").map(|v| bytes_to_string(&v))
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FentryProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl FentryProgram {
+    pub(crate) fn new(data: ProgramData, fn_name: String) -> Result<Self, BpfmanError> {
+        let mut fentry_prog = Self { data };
+        fentry_prog.set_fn_name(fn_name)?;
+        fentry_prog.get_data_mut().set_kind(ProgramType::Tracing)?;
+
+        Ok(fentry_prog)
+    }
+
+    pub(crate) fn set_fn_name(&mut self, fn_name: String) -> Result<(), BpfmanError> {
+        self.data.insert("fentry_fn_name", fn_name.as_bytes())
+    }
+
+    pub(crate) fn get_fn_name(&self) -> Result<String, BpfmanError> {
+        self.data.get("fentry_fn_name").map(|v| bytes_to_string(&v))
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FexitProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl FexitProgram {
+    pub(crate) fn new(data: ProgramData, fn_name: String) -> Result<Self, BpfmanError> {
+        let mut fexit_prog = Self { data };
+        fexit_prog.set_fn_name(fn_name)?;
+        fexit_prog.get_data_mut().set_kind(ProgramType::Tracing)?;
+
+        Ok(fexit_prog)
+    }
+
+    pub(crate) fn set_fn_name(&mut self, fn_name: String) -> Result<(), BpfmanError> {
+        self.data.insert("fexit_fn_name", fn_name.as_bytes())
+    }
+
+    pub(crate) fn get_fn_name(&self) -> Result<String, BpfmanError> {
+        self.data.get("fexit_fn_name").map(|v| bytes_to_string(&v))
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct LsmProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl LsmProgram {
+    pub(crate) fn new(data: ProgramData, hook: String) -> Result<Self, BpfmanError> {
+        let mut lsm_prog = Self { data };
+        lsm_prog.set_hook(hook)?;
+        lsm_prog.get_data_mut().set_kind(ProgramType::Lsm)?;
+
+        Ok(lsm_prog)
+    }
+
+    pub(crate) fn set_hook(&mut self, hook: String) -> Result<(), BpfmanError> {
+        self.data.insert("lsm_hook", hook.as_bytes())
+    }
+
+    pub(crate) fn get_hook(&self) -> Result<String, BpfmanError> {
+        self.data.get("lsm_hook").map(|v| bytes_to_string(&v))
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
+/// A cgroup_skb, cgroup_sock, cgroup_sock_addr, cgroup_sockopt or
+/// cgroup_sysctl program, which of them being set by its attach type.
+#[derive(Debug, Clone)]
+pub(crate) struct CgroupProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl CgroupProgram {
+    pub(crate) fn new(
+        data: ProgramData,
+        cgroup_path: String,
+        attach_type: CgroupAttachType,
+        priority: i32,
+    ) -> Result<Self, BpfmanError> {
+        let mut cgroup_prog = Self { data };
+        cgroup_prog.set_cgroup_path(cgroup_path)?;
+        cgroup_prog.set_attach_type(attach_type)?;
+        cgroup_prog.set_priority(priority)?;
+        cgroup_prog
+            .get_data_mut()
+            .set_kind(attach_type.program_type())?;
+
+        Ok(cgroup_prog)
+    }
+
+    pub(crate) fn set_cgroup_path(&mut self, cgroup_path: String) -> Result<(), BpfmanError> {
+        self.data.insert("cgroup_path", cgroup_path.as_bytes())
+    }
+
+    pub(crate) fn get_cgroup_path(&self) -> Result<String, BpfmanError> {
+        self.data.get("cgroup_path").map(|v| bytes_to_string(&v))
+    }
+
+    pub(crate) fn set_attach_type(
+        &mut self,
+        attach_type: CgroupAttachType,
+    ) -> Result<(), BpfmanError> {
+        self.data
+            .insert("cgroup_attach_type", attach_type.to_string().as_bytes())
+    }
+
+    pub(crate) fn get_attach_type(&self) -> Result<CgroupAttachType, BpfmanError> {
+        self.data
+            .get("cgroup_attach_type")
+            .map(|v| bytes_to_string(&v))?
+            .try_into()
+            .map_err(|e: ParseError| BpfmanError::Error(e.to_string()))
+    }
+
+    pub(crate) fn set_priority(&mut self, priority: i32) -> Result<(), BpfmanError> {
+        self.data.insert("cgroup_priority", &priority.to_ne_bytes())
+    }
+
+    pub(crate) fn get_priority(&self) -> Result<i32, BpfmanError> {
+        self.data.get("cgroup_priority").map(bytes_to_i32)
+    }
+
+    // The position of the program among the programs on the same cgroup and
+    // attach type, which the kernel runs in the order they were attached.
+    pub(crate) fn set_current_position(&mut self, pos: usize) -> Result<(), BpfmanError> {
+        self.data
+            .insert("cgroup_current_position", &pos.to_ne_bytes())
+    }
+
+    pub(crate) fn get_current_position(&self) -> Result<Option<usize>, BpfmanError> {
+        Ok(self
+            .data
+            .get_option("cgroup_current_position")?
+            .map(|v| bytes_to_usize(v.to_vec())))
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
+/// A perf_event program, which is run on samples of a software, hardware or
+/// tracepoint perf event.
+#[derive(Debug, Clone)]
+pub(crate) struct PerfEventProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl PerfEventProgram {
+    pub(crate) fn new(
+        data: ProgramData,
+        event_type: PerfEventType,
+        event: String,
+        sample_period: Option<u64>,
+        sample_frequency: Option<u64>,
+        cpu: Option<u32>,
+        pid: Option<i32>,
+    ) -> Result<Self, BpfmanError> {
+        let mut perf_event_prog = Self { data };
+        perf_event_prog.set_event_type(event_type)?;
+        perf_event_prog.set_event(event)?;
+        if let Some(period) = sample_period {
+            perf_event_prog.set_sample_period(period)?;
+        }
+        if let Some(frequency) = sample_frequency {
+            perf_event_prog.set_sample_frequency(frequency)?;
+        }
+        if let Some(cpu) = cpu {
+            perf_event_prog.set_cpu(cpu)?;
+        }
+        if let Some(pid) = pid {
+            perf_event_prog.set_pid(pid)?;
+        }
+        perf_event_prog
+            .get_data_mut()
+            .set_kind(ProgramType::PerfEvent)?;
+
+        Ok(perf_event_prog)
+    }
+
+    pub(crate) fn set_event_type(&mut self, event_type: PerfEventType) -> Result<(), BpfmanError> {
+        self.data
+            .insert("perf_event_type", event_type.to_string().as_bytes())
+    }
+
+    pub(crate) fn get_event_type(&self) -> Result<PerfEventType, BpfmanError> {
+        self.data
+            .get("perf_event_type")
+            .map(|v| bytes_to_string(&v))?
+            .try_into()
+            .map_err(|e: ParseError| BpfmanError::Error(e.to_string()))
+    }
+
+    pub(crate) fn set_event(&mut self, event: String) -> Result<(), BpfmanError> {
+        self.data.insert("perf_event_name", event.as_bytes())
+    }
+
+    pub(crate) fn get_event(&self) -> Result<String, BpfmanError> {
+        self.data
+            .get("perf_event_name")
+            .map(|v| bytes_to_string(&v))
+    }
+
+    pub(crate) fn set_sample_period(&mut self, period: u64) -> Result<(), BpfmanError> {
+        self.data
+            .insert("perf_event_sample_period", &period.to_ne_bytes())
+    }
+
+    pub(crate) fn get_sample_period(&self) -> Result<Option<u64>, BpfmanError> {
+        Ok(self
+            .data
+            .get_option("perf_event_sample_period")?
+            .map(|v| bytes_to_u64(v.to_vec())))
+    }
+
+    pub(crate) fn set_sample_frequency(&mut self, frequency: u64) -> Result<(), BpfmanError> {
+        self.data
+            .insert("perf_event_sample_frequency", &frequency.to_ne_bytes())
+    }
+
+    pub(crate) fn get_sample_frequency(&self) -> Result<Option<u64>, BpfmanError> {
+        Ok(self
+            .data
+            .get_option("perf_event_sample_frequency")?
+            .map(|v| bytes_to_u64(v.to_vec())))
+    }
+
+    pub(crate) fn set_cpu(&mut self, cpu: u32) -> Result<(), BpfmanError> {
+        self.data.insert("perf_event_cpu", &cpu.to_ne_bytes())
+    }
+
+    pub(crate) fn get_cpu(&self) -> Result<Option<u32>, BpfmanError> {
+        Ok(self
+            .data
+            .get_option("perf_event_cpu")?
+            .map(|v| bytes_to_u32(v.to_vec())))
+    }
+
+    pub(crate) fn set_pid(&mut self, pid: i32) -> Result<(), BpfmanError> {
+        self.data.insert("perf_event_pid", &pid.to_ne_bytes())
+    }
+
+    pub(crate) fn get_pid(&self) -> Result<Option<i32>, BpfmanError> {
+        Ok(self
+            .data
+            .get_option("perf_event_pid")?
+            .map(|v| bytes_to_i32(v.to_vec())))
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
 impl Program {
     pub(crate) fn kind(&self) -> ProgramType {
         match self {
             Program::Xdp(_) => ProgramType::Xdp,
             Program::Tc(_) => ProgramType::Tc,
             Program::Tracepoint(_) => ProgramType::Tracepoint,
+            Program::RawTracepoint(_) => ProgramType::RawTracepoint,
+            Program::BtfTracepoint(_) => ProgramType::Tracing,
             Program::Kprobe(_) => ProgramType::Probe,
             Program::Uprobe(_) => ProgramType::Probe,
+            Program::Fentry(_) => ProgramType::Tracing,
+            Program::Fexit(_) => ProgramType::Tracing,
+            Program::Lsm(_) => ProgramType::Lsm,
+            Program::Cgroup(p) => p.get_attach_type().unwrap().program_type(),
+            Program::PerfEvent(_) => ProgramType::PerfEvent,
             Program::Unsupported(i) => i.get_kernel_program_type().unwrap().try_into().unwrap(),
         }
     }
@@ -1293,9 +2195,16 @@ impl Program {
         match self {
             Program::Xdp(p) => &mut p.data,
             Program::Tracepoint(p) => &mut p.data,
+            Program::RawTracepoint(p) => &mut p.data,
+            Program::BtfTracepoint(p) => &mut p.data,
             Program::Tc(p) => &mut p.data,
             Program::Kprobe(p) => &mut p.data,
             Program::Uprobe(p) => &mut p.data,
+            Program::Fentry(p) => &mut p.data,
+            Program::Fexit(p) => &mut p.data,
+            Program::Lsm(p) => &mut p.data,
+            Program::Cgroup(p) => &mut p.data,
+            Program::PerfEvent(p) => &mut p.data,
             Program::Unsupported(p) => p,
         }
     }
@@ -1320,14 +2229,27 @@ impl Program {
         match self {
             Program::Xdp(p) => p.set_current_position(pos),
             Program::Tc(p) => p.set_current_position(pos),
+            Program::Cgroup(p) => p.set_current_position(pos),
             _ => Err(BpfmanError::Error(
-                "cannot set position on programs other than TC or XDP".to_string(),
+                "cannot set position on programs other than TC, XDP or cgroup".to_string(),
             )),
         }
     }
 
     pub(crate) fn delete(&self) -> Result<(), anyhow::Error> {
         let id = self.get_data().get_id()?;
+
+        // A link shares its program's pins, so only remove what belongs to the link.
+        if let Some(link_id) = self.get_data().get_link_id()? {
+            ROOT_DB.drop_tree(link_tree_name(link_id))?;
+
+            let path = format!("{RTDIR_FS}/prog_{id}_link_{link_id}");
+            if PathBuf::from(&path).exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+
         ROOT_DB.drop_tree(id.to_string())?;
 
         let path = format!("{RTDIR_FS}/prog_{id}");
@@ -1338,6 +2260,16 @@ impl Program {
         if PathBuf::from(&path).exists() {
             fs::remove_file(path)?;
         }
+        // Perf event programs have a link for each CPU.
+        if let Program::PerfEvent(_) = self {
+            let prefix = format!("prog_{id}_link_cpu");
+            for entry in fs::read_dir(RTDIR_FS)? {
+                let entry = entry?;
+                if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                    fs::remove_file(entry.path())?;
+                }
+            }
+        }
         Ok(())
     }
 
@@ -1375,8 +2307,9 @@ impl Program {
         match self {
             Program::Xdp(p) => p.get_priority(),
             Program::Tc(p) => p.get_priority(),
+            Program::Cgroup(p) => p.get_priority(),
             _ => Err(BpfmanError::Error(
-                "cannot get priority on programs other than TC or XDP".to_string(),
+                "cannot get priority on programs other than TC, XDP or cgroup".to_string(),
             )),
         }
     }
@@ -1385,9 +2318,16 @@ impl Program {
         match self {
             Program::Xdp(p) => p.data.get_location(),
             Program::Tracepoint(p) => p.data.get_location(),
+            Program::RawTracepoint(p) => p.data.get_location(),
+            Program::BtfTracepoint(p) => p.data.get_location(),
             Program::Tc(p) => p.data.get_location(),
             Program::Kprobe(p) => p.data.get_location(),
             Program::Uprobe(p) => p.data.get_location(),
+            Program::Fentry(p) => p.data.get_location(),
+            Program::Fexit(p) => p.data.get_location(),
+            Program::Lsm(p) => p.data.get_location(),
+            Program::Cgroup(p) => p.data.get_location(),
+            Program::PerfEvent(p) => p.data.get_location(),
             Program::Unsupported(_) => Err(BpfmanError::Error(
                 "cannot get location for unsupported programs".to_string(),
             )),
@@ -1405,13 +2345,62 @@ impl Program {
         match self {
             Program::Xdp(p) => p.get_data(),
             Program::Tracepoint(p) => p.get_data(),
+            Program::RawTracepoint(p) => p.get_data(),
+            Program::BtfTracepoint(p) => p.get_data(),
             Program::Tc(p) => p.get_data(),
             Program::Kprobe(p) => p.get_data(),
             Program::Uprobe(p) => p.get_data(),
+            Program::Fentry(p) => p.get_data(),
+            Program::Fexit(p) => p.get_data(),
+            Program::Lsm(p) => p.get_data(),
+            Program::Cgroup(p) => p.get_data(),
+            Program::PerfEvent(p) => p.get_data(),
             Program::Unsupported(p) => p,
         }
     }
 
+    // Programs loaded without attach info. Probe programs are created as kprobes
+    // and resolved to a kprobe or uprobe once the bytecode has been parsed.
+    pub(crate) fn new_load_only(
+        mut data: ProgramData,
+        program_type: ProgramType,
+    ) -> Result<Self, BpfmanError> {
+        data.set_load_only(true)?;
+        data.set_kind(program_type)?;
+
+        match program_type {
+            ProgramType::Tracepoint => Ok(Program::Tracepoint(TracepointProgram { data })),
+            ProgramType::Probe => Ok(Program::Kprobe(KprobeProgram { data })),
+            _ => {
+                data.discard();
+                Err(BpfmanError::Error(format!(
+                    "{program_type} programs must be attached when they are loaded"
+                )))
+            }
+        }
+    }
+
+    // Builds the program that replaces this one with new bytecode, attached at
+    // the same hook point.
+    pub(crate) fn new_replacement(&self, mut data: ProgramData) -> Result<Self, BpfmanError> {
+        data.copy_attach_info(self.get_data())?;
+        // The replacement keeps the identity used by Apply and its owner.
+        if let Some(uuid) = self.get_data().get_uuid()? {
+            data.set_uuid(&uuid)?;
+        }
+        if let Some(owner) = self.get_data().get_owner()? {
+            data.set_owner(&owner)?;
+        }
+        // It also goes away when the original would have.
+        if let Some(process) = self.get_data().get_bound_process()? {
+            data.set_bound_process(process)?;
+        }
+        if let Some(expires_at) = self.get_data().get_expires_at()? {
+            data.set_expires_at(expires_at)?;
+        }
+        Self::from_data(data)
+    }
+
     pub(crate) fn new_from_db(id: u32, tree: sled::Tree) -> Result<Self, BpfmanError> {
         let data = ProgramData::new(tree, id);
 
@@ -1420,19 +2409,74 @@ impl Program {
                 "Program id does not match database id program isn't fully loaded".to_string(),
             ));
         }
+        Self::from_data(data)
+    }
+
+    pub(crate) fn new_link_from_db(link_id: u32) -> Result<Self, BpfmanError> {
+        let name = link_tree_name(link_id);
+        if !ROOT_DB.tree_names().iter().any(|n| n == name.as_bytes()) {
+            return Err(BpfmanError::Error(format!("Link {link_id} does not exist")));
+        }
+
+        let tree = ROOT_DB
+            .open_tree(name)
+            .expect("Unable to open link database tree");
+        let id = tree
+            .get("id")
+            .map_err(|e| {
+                BpfmanError::DatabaseError(
+                    format!("Unable to get program id of link {link_id}"),
+                    e.to_string(),
+                )
+            })?
+            .map(|v| bytes_to_u32(v.to_vec()))
+            .ok_or_else(|| BpfmanError::Error(format!("Link {link_id} does not exist")))?;
+        let data = ProgramData::new(tree, id);
+
+        if data.get_link_id()? != Some(link_id) {
+            return Err(BpfmanError::Error(
+                "Link id does not match database id link isn't fully attached".to_string(),
+            ));
+        }
+        Self::from_data(data)
+    }
+
+    fn from_data(data: ProgramData) -> Result<Self, BpfmanError> {
         match data.get_kind()? {
             Some(p) => match p {
                 ProgramType::Xdp => Ok(Program::Xdp(XdpProgram { data })),
                 ProgramType::Tc => Ok(Program::Tc(TcProgram { data })),
                 ProgramType::Tracepoint => Ok(Program::Tracepoint(TracepointProgram { data })),
-                // kernel does not distinguish between kprobe and uprobe program types
+                ProgramType::RawTracepoint => {
+                    Ok(Program::RawTracepoint(RawTracepointProgram { data }))
+                }
+                // kernel does not distinguish between kprobe and uprobe program types.
+                // Load-only uprobes have no offset until they're attached, but
+                // every uprobe records whether it's a retprobe once it's loaded.
                 ProgramType::Probe => {
-                    if data.db_tree.get("uprobe_offset").unwrap().is_some() {
+                    if data.db_tree.get("uprobe_retprobe").unwrap().is_some() {
                         Ok(Program::Uprobe(UprobeProgram { data }))
                     } else {
                         Ok(Program::Kprobe(KprobeProgram { data }))
                     }
                 }
+                // fentry, fexit and tp_btf programs share the tracing program type
+                ProgramType::Tracing => {
+                    if data.db_tree.get("fentry_fn_name").unwrap().is_some() {
+                        Ok(Program::Fentry(FentryProgram { data }))
+                    } else if data.db_tree.get("tp_btf_name").unwrap().is_some() {
+                        Ok(Program::BtfTracepoint(BtfTracepointProgram { data }))
+                    } else {
+                        Ok(Program::Fexit(FexitProgram { data }))
+                    }
+                }
+                ProgramType::Lsm => Ok(Program::Lsm(LsmProgram { data })),
+                ProgramType::CgroupSkb
+                | ProgramType::CgroupSock
+                | ProgramType::CgroupSockAddr
+                | ProgramType::CgroupSockopt
+                | ProgramType::CgroupSysctl => Ok(Program::Cgroup(CgroupProgram { data })),
+                ProgramType::PerfEvent => Ok(Program::PerfEvent(PerfEventProgram { data })),
                 _ => Err(BpfmanError::Error("Unsupported program type".to_string())),
             },
             None => Err(BpfmanError::Error("Unsupported program type".to_string())),
@@ -1443,8 +2487,10 @@ impl Program {
 // BpfMap represents a single map pin path used by a Program.  It has to be a
 // separate object because it's lifetime is slightly different from a Program.
 // More specifically a BpfMap can outlive a Program if other Programs are using
-// it.
+// it. The map pin path is kept with it, since a program that replaces the
+// map owner takes over the maps without moving them.
 #[derive(Debug, Clone)]
 pub(crate) struct BpfMap {
+    pub(crate) map_pin_path: PathBuf,
     pub(crate) used_by: Vec<u32>,
 }
@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! Reads which section of an eBPF object file a program was written in.
+//!
+//! aya decides how some programs are loaded from their section, such as the
+//! attach type of a `cgroup/connect4` program, but doesn't say which section a
+//! program came from. Only the section and symbol tables are read here, which
+//! is enough to find the section holding a program's function.
+
+const SHT_SYMTAB: u32 = 2;
+const STT_FUNC: u8 = 2;
+const SECTION_HEADER_SIZE: usize = 64;
+const SYMBOL_SIZE: usize = 24;
+
+/// Returns the name of the section holding the function `name` in the ELF
+/// object `bytes`, or None if the object can't be read or has no such
+/// function.
+pub(crate) fn program_section(bytes: &[u8], name: &str) -> Option<String> {
+    let elf = Elf::new(bytes)?;
+    let shstrtab = elf.section(elf.u16(0x3e)? as usize)?;
+
+    for index in 0..elf.u16(0x3c)? as usize {
+        let symtab = elf.section(index)?;
+        if symtab.kind != SHT_SYMTAB {
+            continue;
+        }
+        let strtab = elf.section(symtab.link as usize)?;
+        let count = symtab.size / SYMBOL_SIZE;
+        for i in 0..count {
+            let symbol = symtab.offset.checked_add(i * SYMBOL_SIZE)?;
+            let info = *bytes.get(symbol + 4)?;
+            if info & 0xf != STT_FUNC {
+                continue;
+            }
+            if elf.string(&strtab, elf.u32(symbol)?)? != name {
+                continue;
+            }
+            let section = elf.section(elf.u16(symbol + 6)? as usize)?;
+            return elf.string(&shstrtab, section.name).map(str::to_string);
+        }
+    }
+    None
+}
+
+struct Elf<'a> {
+    bytes: &'a [u8],
+    big_endian: bool,
+    section_headers: usize,
+}
+
+struct Section {
+    name: u32,
+    kind: u32,
+    offset: usize,
+    size: usize,
+    link: u32,
+}
+
+impl<'a> Elf<'a> {
+    // Only 64-bit objects are read, which is what eBPF objects are.
+    fn new(bytes: &'a [u8]) -> Option<Self> {
+        if bytes.get(..4)? != b"\x7fELF" || *bytes.get(4)? != 2 {
+            return None;
+        }
+        let mut elf = Elf {
+            bytes,
+            big_endian: *bytes.get(5)? == 2,
+            section_headers: 0,
+        };
+        elf.section_headers = elf.u64(0x28)? as usize;
+        Some(elf)
+    }
+
+    fn section(&self, index: usize) -> Option<Section> {
+        let header = index
+            .checked_mul(SECTION_HEADER_SIZE)?
+            .checked_add(self.section_headers)?;
+        Some(Section {
+            name: self.u32(header)?,
+            kind: self.u32(header + 4)?,
+            offset: self.u64(header + 24)? as usize,
+            size: self.u64(header + 32)? as usize,
+            link: self.u32(header + 40)?,
+        })
+    }
+
+    fn string(&self, strtab: &Section, offset: u32) -> Option<&'a str> {
+        let start = strtab.offset.checked_add(offset as usize)?;
+        let end = strtab.offset.checked_add(strtab.size)?;
+        let bytes = self.bytes.get(start..end)?;
+        let len = bytes.iter().position(|b| *b == 0)?;
+        std::str::from_utf8(&bytes[..len]).ok()
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self
+            .bytes
+            .get(offset..offset.checked_add(2)?)?
+            .try_into()
+            .ok()?;
+        Some(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self
+            .bytes
+            .get(offset..offset.checked_add(4)?)?
+            .try_into()
+            .ok()?;
+        Some(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+
+    fn u64(&self, offset: usize) -> Option<u64> {
+        let bytes = self
+            .bytes
+            .get(offset..offset.checked_add(8)?)?
+            .try_into()
+            .ok()?;
+        Some(match self.big_endian {
+            true => u64::from_be_bytes(bytes),
+            false => u64::from_le_bytes(bytes),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Builds a little endian object with a null section, the section header
+    // string table, one program section, a string table and a symbol table.
+    fn object(section: &str, function: &str) -> Vec<u8> {
+        let shstrtab = format!("\0.shstrtab\0{section}\0.strtab\0.symtab\0");
+        let strtab = format!("\0{function}\0");
+
+        let mut symtab = vec![0u8; SYMBOL_SIZE];
+        let mut symbol = vec![0u8; SYMBOL_SIZE];
+        symbol[..4].copy_from_slice(&1u32.to_le_bytes());
+        symbol[4] = 0x10 | STT_FUNC;
+        symbol[6..8].copy_from_slice(&2u16.to_le_bytes());
+        symtab.extend(symbol);
+
+        let mut bytes = vec![0u8; 64];
+        bytes[..6].copy_from_slice(b"\x7fELF\x02\x01");
+
+        let mut sections = vec![(0, 0, 0, 0, 0)];
+        let mut add = |bytes: &mut Vec<u8>, name: usize, kind: u32, data: &[u8], link: u32| {
+            sections.push((name as u32, kind, bytes.len(), data.len(), link));
+            bytes.extend(data);
+        };
+        add(&mut bytes, 1, 3, shstrtab.as_bytes(), 0);
+        add(&mut bytes, 11, 1, &[0u8; 8], 0);
+        add(&mut bytes, 12 + section.len(), 3, strtab.as_bytes(), 0);
+        add(&mut bytes, 20 + section.len(), SHT_SYMTAB, &symtab, 3);
+
+        let section_headers = bytes.len();
+        bytes[0x28..0x30].copy_from_slice(&(section_headers as u64).to_le_bytes());
+        bytes[0x3c..0x3e].copy_from_slice(&(sections.len() as u16).to_le_bytes());
+        bytes[0x3e..0x40].copy_from_slice(&1u16.to_le_bytes());
+        for (name, kind, offset, size, link) in sections {
+            let mut header = vec![0u8; SECTION_HEADER_SIZE];
+            header[..4].copy_from_slice(&name.to_le_bytes());
+            header[4..8].copy_from_slice(&kind.to_le_bytes());
+            header[24..32].copy_from_slice(&(offset as u64).to_le_bytes());
+            header[32..40].copy_from_slice(&(size as u64).to_le_bytes());
+            header[40..44].copy_from_slice(&link.to_le_bytes());
+            bytes.extend(header);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_program_section() {
+        let bytes = object("cgroup/connect4", "connect_prog");
+        assert_eq!(
+            program_section(&bytes, "connect_prog").as_deref(),
+            Some("cgroup/connect4")
+        );
+        assert_eq!(program_section(&bytes, "other_prog"), None);
+    }
+
+    #[test]
+    fn test_program_section_invalid() {
+        assert_eq!(program_section(b"", "connect_prog"), None);
+        assert_eq!(program_section(b"not an object", "connect_prog"), None);
+
+        let mut bytes = object("cgroup/connect4", "connect_prog");
+        bytes.truncate(bytes.len() - SECTION_HEADER_SIZE);
+        assert_eq!(program_section(&bytes, "connect_prog"), None);
+    }
+}
@@ -16,6 +16,8 @@ pub enum BpfmanError {
     BpfProgramError(#[from] aya::programs::ProgramError),
     #[error(transparent)]
     BpfLoadError(#[from] aya::BpfError),
+    #[error(transparent)]
+    BtfError(#[from] aya::BtfError),
     #[error("Unable to find a valid program with function name {0}")]
     BpfFunctionNameNotValid(String),
     #[error("No room to attach program. Please remove one and try again.")]
@@ -49,11 +51,22 @@ pub enum BpfmanError {
     RpcSendError(#[from] anyhow::Error),
     #[error("Failed to pin map {0}")]
     UnableToPinMap(#[source] aya::pin::PinError),
-    #[error("Unable to attach {program_type} in container with pid {container_pid}")]
+    #[error("Unable to attach {program_type} in container with pid {container_pid}: {error}")]
     ContainerAttachError {
         program_type: String,
         container_pid: i32,
+        error: String,
     },
+    #[error("Unable to locate the bpfman-ns executable: {0}")]
+    BpfmanNsNotFound(String),
     #[error("{0}: {1}")]
     DatabaseError(String, String),
+    #[error("Program {0} was loaded by another caller")]
+    NotOwner(u32),
+    #[error("The caller may not load {0} programs")]
+    ProgramTypeNotAllowed(String),
+    #[error(
+        "The bpf LSM is not enabled, add bpf to the lsm= kernel parameter to load LSM programs"
+    )]
+    BpfLsmNotEnabled,
 }
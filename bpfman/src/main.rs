@@ -6,11 +6,16 @@ use clap::Parser;
 use lazy_static::lazy_static;
 use sled::{Config, Db};
 
+mod audit;
+mod auth;
+mod bound_process;
 mod bpf;
 mod cli;
 mod command;
 mod dispatcher_config;
+mod elf;
 mod errors;
+mod maps;
 mod multiprog;
 mod oci_utils;
 mod rpc;
@@ -40,5 +45,5 @@ lazy_static! {
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = cli::args::Cli::parse();
-    cli.command.execute().await
+    cli.command.execute(cli.endpoint.as_deref()).await
 }
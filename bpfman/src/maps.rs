@@ -0,0 +1,271 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! Access to the contents of the maps pinned by bpfman.
+//!
+//! Keys and values are opaque byte strings, so the elements are read and
+//! written with the bpf() syscall directly rather than through aya's typed
+//! maps, which need the key and value types at compile time.
+use std::{
+    mem::size_of,
+    os::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd, RawFd},
+    path::Path,
+};
+
+use aya::{maps::MapData, util::nr_cpus};
+use nix::libc;
+
+use crate::errors::BpfmanError;
+
+const BPF_MAP_LOOKUP_ELEM: libc::c_long = 1;
+const BPF_MAP_UPDATE_ELEM: libc::c_long = 2;
+const BPF_MAP_DELETE_ELEM: libc::c_long = 3;
+const BPF_MAP_GET_NEXT_KEY: libc::c_long = 4;
+const BPF_MAP_GET_FD_BY_ID: libc::c_long = 14;
+const BPF_OBJ_GET_INFO_BY_FD: libc::c_long = 15;
+
+const BPF_MAP_TYPE_PERCPU_HASH: u32 = 5;
+const BPF_MAP_TYPE_PERCPU_ARRAY: u32 = 6;
+const BPF_MAP_TYPE_LRU_PERCPU_HASH: u32 = 10;
+const BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE: u32 = 21;
+
+// The part of union bpf_attr used by the map element commands.
+#[repr(C)]
+#[derive(Default)]
+struct MapElemAttr {
+    map_fd: u32,
+    _pad: u32,
+    key: u64,
+    // value, or next_key for BPF_MAP_GET_NEXT_KEY
+    value: u64,
+    flags: u64,
+}
+
+// The part of union bpf_attr used by BPF_MAP_GET_FD_BY_ID.
+#[repr(C)]
+#[derive(Default)]
+struct GetFdByIdAttr {
+    map_id: u32,
+    next_id: u32,
+    open_flags: u32,
+}
+
+// The part of union bpf_attr used by BPF_OBJ_GET_INFO_BY_FD.
+#[repr(C)]
+#[derive(Default)]
+struct InfoAttr {
+    bpf_fd: u32,
+    info_len: u32,
+    info: u64,
+}
+
+// The leading fields of struct bpf_map_info, the kernel only fills in as much
+// as info_len allows.
+#[repr(C)]
+#[derive(Default)]
+struct BpfMapInfo {
+    map_type: u32,
+    id: u32,
+    key_size: u32,
+    value_size: u32,
+    max_entries: u32,
+    map_flags: u32,
+    name: [u8; 16],
+}
+
+/// A map as described by the kernel.
+#[derive(Debug)]
+pub(crate) struct KernelMapInfo {
+    pub(crate) name: String,
+    pub(crate) map_type: u32,
+    pub(crate) key_size: u32,
+    pub(crate) value_size: u32,
+    pub(crate) max_entries: u32,
+}
+
+/// Returns the kernel's description of the map with the given id.
+pub(crate) fn map_info_by_id(id: u32) -> Result<KernelMapInfo, BpfmanError> {
+    let mut attr = GetFdByIdAttr {
+        map_id: id,
+        ..Default::default()
+    };
+    let ret = sys_bpf(BPF_MAP_GET_FD_BY_ID, &mut attr)?;
+    // SAFETY: on success BPF_MAP_GET_FD_BY_ID returns a new file descriptor
+    // that nothing else owns.
+    let map_fd = unsafe { OwnedFd::from_raw_fd(ret as RawFd) };
+
+    let info = map_info(map_fd.as_raw_fd())?;
+    let name_len = info.name.iter().position(|b| *b == 0).unwrap_or(16);
+
+    Ok(KernelMapInfo {
+        name: String::from_utf8_lossy(&info.name[..name_len]).into_owned(),
+        map_type: info.map_type,
+        key_size: info.key_size,
+        value_size: info.value_size,
+        max_entries: info.max_entries,
+    })
+}
+
+/// A map pinned by bpfman, opened for reading and writing its elements.
+pub(crate) struct PinnedMap {
+    map: MapData,
+    key_size: usize,
+    value_size: usize,
+}
+
+impl PinnedMap {
+    pub(crate) fn open<P: AsRef<Path>>(path: P) -> Result<Self, BpfmanError> {
+        let path = path.as_ref();
+        let map = MapData::from_pin(path).map_err(|e| {
+            BpfmanError::Error(format!("unable to open map {}: {e}", path.display()))
+        })?;
+
+        let info = map_info(fd(&map))?;
+
+        // Per-CPU maps hold a value for each possible CPU, each of which is
+        // rounded up to 8 bytes.
+        let value_size = match info.map_type {
+            BPF_MAP_TYPE_PERCPU_HASH
+            | BPF_MAP_TYPE_PERCPU_ARRAY
+            | BPF_MAP_TYPE_LRU_PERCPU_HASH
+            | BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE => {
+                let cpus = nr_cpus().map_err(|e| {
+                    BpfmanError::Error(format!("unable to get the number of cpus: {e}"))
+                })?;
+                (info.value_size as usize).next_multiple_of(8) * cpus
+            }
+            _ => info.value_size as usize,
+        };
+
+        Ok(PinnedMap {
+            map,
+            key_size: info.key_size as usize,
+            value_size,
+        })
+    }
+
+    pub(crate) fn lookup(&self, key: &[u8]) -> Result<Vec<u8>, BpfmanError> {
+        self.check_key(key)?;
+
+        let mut value = vec![0u8; self.value_size];
+        let mut attr = MapElemAttr {
+            map_fd: fd(&self.map) as u32,
+            key: key.as_ptr() as u64,
+            value: value.as_mut_ptr() as u64,
+            ..Default::default()
+        };
+        bpf(BPF_MAP_LOOKUP_ELEM, &mut attr)?;
+
+        Ok(value)
+    }
+
+    pub(crate) fn update(&self, key: &[u8], value: &[u8], flags: u64) -> Result<(), BpfmanError> {
+        self.check_key(key)?;
+        if value.len() != self.value_size {
+            return Err(BpfmanError::Error(format!(
+                "value is {} bytes, the map's values are {} bytes",
+                value.len(),
+                self.value_size
+            )));
+        }
+
+        let mut attr = MapElemAttr {
+            map_fd: fd(&self.map) as u32,
+            key: key.as_ptr() as u64,
+            value: value.as_ptr() as u64,
+            flags,
+            ..Default::default()
+        };
+        bpf(BPF_MAP_UPDATE_ELEM, &mut attr)
+    }
+
+    pub(crate) fn delete(&self, key: &[u8]) -> Result<(), BpfmanError> {
+        self.check_key(key)?;
+
+        let mut attr = MapElemAttr {
+            map_fd: fd(&self.map) as u32,
+            key: key.as_ptr() as u64,
+            ..Default::default()
+        };
+        bpf(BPF_MAP_DELETE_ELEM, &mut attr)
+    }
+
+    // Returns all of the entries in the map. Entries that are deleted while the
+    // map is being walked are skipped.
+    pub(crate) fn dump(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>, BpfmanError> {
+        let mut entries = vec![];
+        let mut key: Option<Vec<u8>> = None;
+
+        loop {
+            let mut next_key = vec![0u8; self.key_size];
+            let mut attr = MapElemAttr {
+                map_fd: fd(&self.map) as u32,
+                // a null key returns the first key in the map
+                key: key.as_ref().map_or(0, |k| k.as_ptr() as u64),
+                value: next_key.as_mut_ptr() as u64,
+                ..Default::default()
+            };
+            match bpf(BPF_MAP_GET_NEXT_KEY, &mut attr) {
+                Ok(()) => (),
+                Err(BpfmanError::BpfIOError(e)) if e.raw_os_error() == Some(libc::ENOENT) => break,
+                Err(e) => return Err(e),
+            }
+
+            match self.lookup(&next_key) {
+                Ok(value) => entries.push((next_key.clone(), value)),
+                Err(BpfmanError::BpfIOError(e)) if e.raw_os_error() == Some(libc::ENOENT) => (),
+                Err(e) => return Err(e),
+            }
+            key = Some(next_key);
+        }
+
+        Ok(entries)
+    }
+
+    fn check_key(&self, key: &[u8]) -> Result<(), BpfmanError> {
+        if key.len() != self.key_size {
+            return Err(BpfmanError::Error(format!(
+                "key is {} bytes, the map's keys are {} bytes",
+                key.len(),
+                self.key_size
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn fd(map: &MapData) -> RawFd {
+    map.fd().as_fd().as_raw_fd()
+}
+
+fn map_info(map_fd: RawFd) -> Result<BpfMapInfo, BpfmanError> {
+    let mut info = BpfMapInfo::default();
+    let mut attr = InfoAttr {
+        bpf_fd: map_fd as u32,
+        info_len: size_of::<BpfMapInfo>() as u32,
+        info: &mut info as *mut _ as u64,
+    };
+    bpf(BPF_OBJ_GET_INFO_BY_FD, &mut attr)?;
+    Ok(info)
+}
+
+fn bpf<T>(cmd: libc::c_long, attr: &mut T) -> Result<(), BpfmanError> {
+    sys_bpf(cmd, attr).map(|_| ())
+}
+
+fn sys_bpf<T>(cmd: libc::c_long, attr: &mut T) -> Result<libc::c_long, BpfmanError> {
+    // SAFETY: attr is one of the bpf_attr layouts above, and any buffers it
+    // points to are sized from the map's key and value sizes.
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            cmd,
+            attr as *mut T,
+            size_of::<T>() as libc::c_uint,
+        )
+    };
+    if ret < 0 {
+        return Err(BpfmanError::BpfIOError(std::io::Error::last_os_error()));
+    }
+    Ok(ret)
+}
@@ -9,7 +9,7 @@ use aya::{
         tc::{self, SchedClassifierLink, TcOptions},
         Extension, Link, SchedClassifier, TcAttachType,
     },
-    Bpf, BpfLoader,
+    Bpf, BpfLoader, VerifierLogLevel,
 };
 use bpfman_api::{util::directories::*, ImagePullPolicy};
 use futures::stream::TryStreamExt;
@@ -28,7 +28,7 @@ use crate::{
     dispatcher_config::TcDispatcherConfig,
     errors::BpfmanError,
     multiprog::Dispatcher,
-    oci_utils::image_manager::{BytecodeImage, Command as ImageManagerCommand},
+    oci_utils::image_manager::{BytecodeImage, Command as ImageManagerCommand, PulledImage},
     utils::should_map_be_pinned,
 };
 
@@ -97,7 +97,11 @@ impl TcDispatcher {
             .await
             .map_err(|e| BpfmanError::RpcSendError(e.into()))?;
 
-        let (path, bpf_function_name) = rx
+        let PulledImage {
+            path,
+            bpf_function_name,
+            ..
+        } = rx
             .await
             .map_err(BpfmanError::RpcRecvError)?
             .map_err(BpfmanError::BpfBytecodeError)?;
@@ -292,6 +296,10 @@ impl TcDispatcher {
                     bpf.map_pin_path(map_pin_path);
                 }
 
+                if let Some(level) = v.data.get_verifier_log_level()? {
+                    bpf.verifier_log_level(VerifierLogLevel::from_bits_truncate(level));
+                }
+
                 let mut loader = bpf
                     .load(v.data.program_bytes())
                     .map_err(BpfmanError::BpfLoadError)?;
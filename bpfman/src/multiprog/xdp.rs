@@ -8,7 +8,7 @@ use aya::{
         links::{FdLink, PinnedLink},
         Extension, Xdp,
     },
-    Bpf, BpfLoader,
+    Bpf, BpfLoader, VerifierLogLevel,
 };
 use bpfman_api::{config::XdpMode, util::directories::*, ImagePullPolicy};
 use log::debug;
@@ -21,7 +21,7 @@ use crate::{
     dispatcher_config::XdpDispatcherConfig,
     errors::BpfmanError,
     multiprog::Dispatcher,
-    oci_utils::image_manager::{BytecodeImage, Command as ImageManagerCommand},
+    oci_utils::image_manager::{BytecodeImage, Command as ImageManagerCommand, PulledImage},
     utils::should_map_be_pinned,
 };
 
@@ -95,7 +95,11 @@ impl XdpDispatcher {
             .await
             .map_err(|e| BpfmanError::RpcSendError(e.into()))?;
 
-        let (path, bpf_function_name) = rx
+        let PulledImage {
+            path,
+            bpf_function_name,
+            ..
+        } = rx
             .await
             .map_err(BpfmanError::RpcRecvError)?
             .map_err(BpfmanError::BpfBytecodeError)?;
@@ -234,6 +238,10 @@ impl XdpDispatcher {
                     bpf.map_pin_path(map_pin_path);
                 }
 
+                if let Some(level) = v.get_data().get_verifier_log_level()? {
+                    bpf.verifier_log_level(VerifierLogLevel::from_bits_truncate(level));
+                }
+
                 let mut loader = bpf
                     .load(v.get_data().program_bytes())
                     .map_err(BpfmanError::BpfLoadError)?;
@@ -110,6 +110,17 @@ pub(crate) struct ImageManager {
 /// the command response back to the requester.
 type Responder<T> = oneshot::Sender<T>;
 
+/// An image in the image store, as returned by a pull.
+#[derive(Debug)]
+pub(crate) struct PulledImage {
+    /// The key the content of the image is stored under.
+    pub(crate) path: String,
+    pub(crate) bpf_function_name: String,
+    /// The digest of the bytecode layer, which identifies the bytecode
+    /// whichever tag it was pulled by.
+    pub(crate) digest: String,
+}
+
 #[derive(Debug)]
 pub(crate) enum Command {
     Pull {
@@ -117,7 +128,7 @@ pub(crate) enum Command {
         pull_policy: ImagePullPolicy,
         username: Option<String>,
         password: Option<String>,
-        resp: Responder<Result<(String, String), ImageError>>,
+        resp: Responder<Result<PulledImage, ImageError>>,
     },
     GetBytecode {
         path: String,
@@ -158,7 +169,7 @@ impl ImageManager {
                 Some(cmd) = self.rx.recv() => {
                     match cmd {
                         Command::Pull { image, pull_policy, username, password, resp } => {
-                            let result = self.get_image(&image, pull_policy, username, password).await;
+                            let result = self.pull(&image, pull_policy, username, password).await;
                             let _ = resp.send(result);
                         },
                         Command::GetBytecode { path, resp } => {
@@ -172,6 +183,25 @@ impl ImageManager {
         info!("image_manager: Stopped processing commands");
     }
 
+    // Gets the image as get_image does, along with the digest of its bytecode.
+    async fn pull(
+        &mut self,
+        image_url: &str,
+        pull_policy: ImagePullPolicy,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<PulledImage, ImageError> {
+        let (path, bpf_function_name) = self
+            .get_image(image_url, pull_policy, username, password)
+            .await?;
+        let digest = self.get_bytecode_digest(&path)?;
+        Ok(PulledImage {
+            path,
+            bpf_function_name,
+            digest,
+        })
+    }
+
     pub(crate) async fn get_image(
         &mut self,
         image_url: &str,
@@ -336,15 +366,12 @@ impl ImageManager {
         Ok(image_labels)
     }
 
-    pub(crate) async fn get_bytecode_from_image_store(
-        &self,
-        base_key: String,
-    ) -> Result<Vec<u8>, ImageError> {
-        let manifest = serde_json::from_str::<OciImageManifest>(
+    fn load_image_manifest(&self, base_key: &str) -> Result<OciImageManifest, ImageError> {
+        serde_json::from_str::<OciImageManifest>(
             std::str::from_utf8(
                 &self
                     .database
-                    .get(base_key.clone() + "manifest.json")
+                    .get(base_key.to_string() + "manifest.json")
                     .map_err(|e| {
                         ImageError::DatabaseError("failed to read db".to_string(), e.to_string())
                     })?
@@ -357,7 +384,20 @@ impl ImageManager {
                 "failed to parse image manifest from db".to_string(),
                 e.to_string(),
             )
-        })?;
+        })
+    }
+
+    // Returns the digest of the bytecode layer of a stored image, such as
+    // "sha256:4b2c...".
+    pub(crate) fn get_bytecode_digest(&self, base_key: &str) -> Result<String, ImageError> {
+        Ok(self.load_image_manifest(base_key)?.layers[0].digest.clone())
+    }
+
+    pub(crate) async fn get_bytecode_from_image_store(
+        &self,
+        base_key: String,
+    ) -> Result<Vec<u8>, ImageError> {
+        let manifest = self.load_image_manifest(&base_key)?;
 
         let bytecode_sha = &manifest.layers[0].digest;
 
@@ -1,136 +1,159 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    os::fd::OwnedFd,
+    time::{Duration, SystemTime},
+};
+
+use aya::programs::ProgramError;
 use bpfman_api::{
     v1::{
         attach_info::Info, bpfman_server::Bpfman, bytecode_location::Location,
-        list_response::ListResult, GetRequest, GetResponse, KprobeAttachInfo, ListRequest,
-        ListResponse, LoadRequest, LoadResponse, PullBytecodeRequest, PullBytecodeResponse,
-        TcAttachInfo, TracepointAttachInfo, UnloadRequest, UnloadResponse, UprobeAttachInfo,
-        XdpAttachInfo,
+        list_response::ListResult, ApplyRequest, ApplyResponse, ApplyResult, AttachRequest,
+        AttachResponse, BtfTracepointAttachInfo, CgroupAttachInfo, DetachRequest, DetachResponse,
+        FentryAttachInfo, FexitAttachInfo, GetRequest, GetResponse, KprobeAttachInfo, ListRequest,
+        ListResponse, LoadRequest, LoadResponse, LsmAttachInfo, MapDeleteRequest,
+        MapDeleteResponse, MapDumpRequest, MapDumpResponse, MapEntry, MapLookupRequest,
+        MapLookupResponse, MapUpdateRequest, MapUpdateResponse, PerfEventAttachInfo,
+        PullBytecodeRequest, PullBytecodeResponse, RawTracepointAttachInfo, ReplaceRequest,
+        ReplaceResponse, TcAttachInfo, TracepointAttachInfo, UnloadRequest, UnloadResponse,
+        UpdateRequest, UpdateResponse, UprobeAttachInfo, VerifierLog, VerifyMapInfo, VerifyRequest,
+        VerifyResponse, WatchRequest, WatchResponse, XdpAttachInfo,
     },
-    TcProceedOn, XdpProceedOn,
+    CgroupAttachType, PerfEventType, ProgramType, TcProceedOn, XdpProceedOn,
 };
-use log::warn;
-use tokio::sync::{mpsc, mpsc::Sender, oneshot};
+use log::{debug, warn};
+use sha2::{Digest, Sha256};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc,
+    mpsc::Sender,
+    oneshot,
+};
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 
-use crate::command::{
-    Command, GetArgs, KprobeProgram, LoadArgs, Program, ProgramData, PullBytecodeArgs, TcProgram,
-    TracepointProgram, UnloadArgs, UprobeProgram, XdpProgram,
+use crate::{
+    audit::{AuditLog, AuditRecord, AuditRequest},
+    auth::{Authorizer, Caller},
+    bound_process::{wait_for_exit, BoundProcess},
+    command::{
+        ApplyArgs, ApplyOutcome, AttachArgs, BtfTracepointProgram, CgroupProgram, Command,
+        DetachArgs, FentryProgram, FexitProgram, GetArgs, KprobeProgram, LoadArgs, LsmProgram,
+        MapArgs, MapOperation, PerfEventProgram, Program, ProgramData, ProgramEvent,
+        PullBytecodeArgs, RawTracepointProgram, ReplaceArgs, TcProgram, TracepointProgram,
+        UnloadArgs, UpdateArgs, UprobeProgram, VerifyArgs, XdpProgram,
+    },
+    errors::BpfmanError,
+    oci_utils::image_manager::BytecodeImage,
 };
 
-#[derive(Debug)]
+// The status details are sent in a trailer, so the verifier log is cut down
+// to its end, which is where the verifier reports why it rejected the program,
+// to stay within the header size limits of the client.
+const MAX_VERIFIER_LOG_LEN: usize = 8 * 1024;
+
+#[derive(Debug, Clone)]
 pub struct BpfmanLoader {
     tx: Sender<Command>,
+    events: broadcast::Sender<ProgramEvent>,
+    authorizer: Authorizer,
+    audit: AuditLog,
 }
 
 impl BpfmanLoader {
-    pub(crate) fn new(tx: mpsc::Sender<Command>) -> BpfmanLoader {
-        BpfmanLoader { tx }
+    pub(crate) fn new(
+        tx: mpsc::Sender<Command>,
+        events: broadcast::Sender<ProgramEvent>,
+        authorizer: Authorizer,
+        audit: AuditLog,
+    ) -> BpfmanLoader {
+        BpfmanLoader {
+            tx,
+            events,
+            authorizer,
+            audit,
+        }
     }
-}
 
-#[tonic::async_trait]
-impl Bpfman for BpfmanLoader {
-    async fn load(&self, request: Request<LoadRequest>) -> Result<Response<LoadResponse>, Status> {
-        let request = request.into_inner();
+    // Sends a map operation to the manager task and returns the key/value
+    // pairs it looked up or dumped.
+    async fn map_command(
+        &self,
+        id: u32,
+        map_name: String,
+        operation: MapOperation,
+        owner: Option<String>,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Status> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let cmd = Command::Map(MapArgs {
+            id,
+            map_name,
+            operation,
+            owner,
+            responder: resp_tx,
+        });
 
+        self.tx.send(cmd).await.unwrap();
+
+        // Await the response
+        match resp_rx.await {
+            Ok(res) => res.map_err(|e| {
+                warn!("BPFMAN map error: {}", e);
+                error_status(e)
+            }),
+            Err(e) => {
+                warn!("RPC map error: {}", e);
+                Err(Status::aborted(format!("{e}")))
+            }
+        }
+    }
+
+    // Loads the program described by the request on behalf of the caller.
+    async fn load_program(&self, caller: &Caller, request: LoadRequest) -> Result<Program, Status> {
         let (resp_tx, resp_rx) = oneshot::channel();
 
-        let bytecode_source = match request
-            .bytecode
-            .ok_or(Status::aborted("missing bytecode info"))?
-            .location
-            .ok_or(Status::aborted("missing location"))?
+        // The pidfd is opened before loading, while the caller is known to be
+        // waiting for the response.
+        let bound = if request.unload_on_exit {
+            Some(bind_to_caller(caller)?)
+        } else {
+            None
+        };
+        if let Some(Location::File(path)) =
+            request.bytecode.as_ref().and_then(|b| b.location.as_ref())
         {
-            Location::Image(i) => crate::command::Location::Image(i.into()),
-            Location::File(p) => crate::command::Location::File(p),
+            self.authorizer.authorize_file(caller, path)?;
+        }
+        // Sharing another program's maps lets the program change them, so it
+        // needs the same permission as updating them.
+        let owner = match request.map_owner_id {
+            Some(_) => self.authorizer.owner_filter(caller)?,
+            None => None,
         };
 
-        let data = ProgramData::new_pre_load(
-            bytecode_source,
-            request.name,
-            request.metadata,
-            request.global_data,
-            request.map_owner_id,
-        )
-        .map_err(|e| Status::aborted(format!("failed to create ProgramData: {e}")))?;
+        let mut program = program_from_load_request(request)?;
+        if let Err(e) = self.authorizer.authorize_program(caller, &program) {
+            program.get_data().discard();
+            return Err(e);
+        }
+        if let Some(owner) = caller.owner() {
+            program
+                .get_data_mut()
+                .set_owner(&owner)
+                .map_err(|e| Status::aborted(format!("failed to set owner: {e}")))?;
+        }
+        if let Some((process, _)) = &bound {
+            program
+                .get_data_mut()
+                .set_bound_process(*process)
+                .map_err(|e| Status::aborted(format!("failed to set bound process: {e}")))?;
+        }
 
         let load_args = LoadArgs {
-            program: match request
-                .attach
-                .ok_or(Status::aborted("missing attach info"))?
-                .info
-                .ok_or(Status::aborted("missing info"))?
-            {
-                Info::XdpAttachInfo(XdpAttachInfo {
-                    priority,
-                    iface,
-                    position: _,
-                    proceed_on,
-                }) => Program::Xdp(
-                    XdpProgram::new(
-                        data,
-                        priority,
-                        iface,
-                        XdpProceedOn::from_int32s(proceed_on)
-                            .map_err(|_| Status::aborted("failed to parse proceed_on"))?,
-                    )
-                    .map_err(|e| Status::aborted(format!("failed to create xdpprogram: {e}")))?,
-                ),
-                Info::TcAttachInfo(TcAttachInfo {
-                    priority,
-                    iface,
-                    position: _,
-                    direction,
-                    proceed_on,
-                }) => {
-                    let direction = direction
-                        .try_into()
-                        .map_err(|_| Status::aborted("direction is not a string"))?;
-                    Program::Tc(
-                        TcProgram::new(
-                            data,
-                            priority,
-                            iface,
-                            TcProceedOn::from_int32s(proceed_on)
-                                .map_err(|_| Status::aborted("failed to parse proceed_on"))?,
-                            direction,
-                        )
-                        .map_err(|e| Status::aborted(format!("failed to create tcprogram: {e}")))?,
-                    )
-                }
-                Info::TracepointAttachInfo(TracepointAttachInfo { tracepoint }) => {
-                    Program::Tracepoint(
-                        TracepointProgram::new(data, tracepoint).map_err(|e| {
-                            Status::aborted(format!("failed to create tcprogram: {e}"))
-                        })?,
-                    )
-                }
-                Info::KprobeAttachInfo(KprobeAttachInfo {
-                    fn_name,
-                    offset,
-                    retprobe,
-                    container_pid,
-                }) => Program::Kprobe(
-                    KprobeProgram::new(data, fn_name, offset, retprobe, container_pid).map_err(
-                        |e| Status::aborted(format!("failed to create kprobeprogram: {e}")),
-                    )?,
-                ),
-                Info::UprobeAttachInfo(UprobeAttachInfo {
-                    fn_name,
-                    offset,
-                    target,
-                    retprobe,
-                    pid,
-                    container_pid,
-                }) => Program::Uprobe(
-                    UprobeProgram::new(data, fn_name, offset, target, retprobe, pid, container_pid)
-                        .map_err(|e| {
-                            Status::aborted(format!("failed to create uprobeprogram: {e}"))
-                        })?,
-                ),
-            },
+            program,
+            owner,
             responder: resp_tx,
         };
 
@@ -139,26 +162,16 @@ impl Bpfman for BpfmanLoader {
 
         // Await the response
         match resp_rx.await {
-            Ok(res) => match res {
-                Ok(program) => {
-                    let reply_entry = LoadResponse {
-                        info: Some((&program).try_into().map_err(|e| {
-                            Status::aborted(format!("convert Program to GRPC program: {e}"))
-                        })?),
-                        kernel_info: Some((&program).try_into().map_err(|e| {
-                            Status::aborted(format!(
-                                "convert Program to GRPC kernel program info: {e}"
-                            ))
-                        })?),
-                    };
-                    Ok(Response::new(reply_entry))
-                }
-                Err(e) => {
+            Ok(res) => {
+                let program = res.map_err(|e| {
                     warn!("BPFMAN load error: {:#?}", e);
-                    Err(Status::aborted(format!("{e}")))
+                    load_error_status(e)
+                })?;
+                if let Some((process, pidfd)) = bound {
+                    tokio::spawn(wait_for_exit(process, Some(pidfd), self.tx.clone()));
                 }
-            },
-
+                Ok(program)
+            }
             Err(e) => {
                 warn!("RPC load error: {:#?}", e);
                 Err(Status::aborted(format!("{e}")))
@@ -166,17 +179,14 @@ impl Bpfman for BpfmanLoader {
         }
     }
 
-    async fn unload(
-        &self,
-        request: Request<UnloadRequest>,
-    ) -> Result<Response<UnloadResponse>, Status> {
-        let reply = UnloadResponse {};
-        let request = request.into_inner();
-        let id = request.id;
+    // Unloads the program on behalf of the caller.
+    async fn unload_program(&self, caller: &Caller, id: u32) -> Result<(), Status> {
+        let owner = self.authorizer.owner_filter(caller)?;
 
         let (resp_tx, resp_rx) = oneshot::channel();
         let cmd = Command::Unload(UnloadArgs {
             id,
+            owner,
             responder: resp_tx,
         });
 
@@ -185,19 +195,320 @@ impl Bpfman for BpfmanLoader {
 
         // Await the response
         match resp_rx.await {
-            Ok(res) => match res {
-                Ok(_) => Ok(Response::new(reply)),
+            Ok(res) => res.map_err(|e| {
+                warn!("BPFMAN unload error: {}", e);
+                error_status(e)
+            }),
+            Err(e) => {
+                warn!("RPC unload error: {}", e);
+                Err(Status::aborted(format!("{e}")))
+            }
+        }
+    }
+
+    // Pulls the image and returns the digest of its bytecode layer.
+    async fn pull_image(&self, image: BytecodeImage) -> Result<String, Status> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let cmd = Command::PullBytecode(PullBytecodeArgs {
+            image,
+            responder: resp_tx,
+        });
+
+        self.tx.send(cmd).await.unwrap();
+
+        // Await the response
+        match resp_rx.await {
+            Ok(res) => res.map_err(|e| {
+                warn!("BPFMAN pull_bytecode error: {:#?}", e);
+                Status::aborted(format!("{e}"))
+            }),
+            Err(e) => {
+                warn!("RPC pull_bytecode error: {:#?}", e);
+                Err(Status::aborted(format!("{e}")))
+            }
+        }
+    }
+
+    // Attaches the loaded program on behalf of the caller and returns the id
+    // of the new link.
+    async fn attach_link(&self, caller: &Caller, id: u32, info: Info) -> Result<u32, Status> {
+        let owner = self.authorizer.owner_filter(caller)?;
+
+        let link = program_from_info(ProgramData::new_pre_attach(), info)?;
+        if let Err(e) = self.authorizer.authorize_program(caller, &link) {
+            link.get_data().discard();
+            return Err(e);
+        }
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let cmd = Command::Attach(AttachArgs {
+            id,
+            link,
+            owner,
+            responder: resp_tx,
+        });
+
+        self.tx.send(cmd).await.unwrap();
+
+        // Await the response
+        match resp_rx.await {
+            Ok(res) => res.map_err(|e| {
+                warn!("BPFMAN attach error: {}", e);
+                error_status(e)
+            }),
+            Err(e) => {
+                warn!("RPC attach error: {}", e);
+                Err(Status::aborted(format!("{e}")))
+            }
+        }
+    }
+
+    // Removes the link on behalf of the caller.
+    async fn detach_link(&self, caller: &Caller, request: DetachRequest) -> Result<(), Status> {
+        match (request.link_id, request.id) {
+            (0, None) => return Err(Status::invalid_argument("link_id or id is required")),
+            (link_id, Some(_)) if link_id != 0 => {
+                return Err(Status::invalid_argument(
+                    "only one of link_id and id may be set",
+                ))
+            }
+            _ => (),
+        }
+        let owner = self.authorizer.owner_filter(caller)?;
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let cmd = Command::Detach(DetachArgs {
+            link_id: request.link_id,
+            id: request.id,
+            owner,
+            responder: resp_tx,
+        });
+
+        self.tx.send(cmd).await.unwrap();
+
+        // Await the response
+        match resp_rx.await {
+            Ok(res) => res.map_err(|e| {
+                warn!("BPFMAN detach error: {}", e);
+                error_status(e)
+            }),
+            Err(e) => {
+                warn!("RPC detach error: {}", e);
+                Err(Status::aborted(format!("{e}")))
+            }
+        }
+    }
+
+    // Changes the program in place on behalf of the caller.
+    async fn update_program(
+        &self,
+        caller: &Caller,
+        request: UpdateRequest,
+    ) -> Result<Program, Status> {
+        let owner = self.authorizer.owner_filter(caller)?;
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let cmd = Command::Update(UpdateArgs {
+            id: request.id,
+            priority: request.priority,
+            proceed_on: (!request.proceed_on.is_empty()).then_some(request.proceed_on),
+            metadata: (!request.metadata.is_empty()).then_some(request.metadata),
+            owner,
+            responder: resp_tx,
+        });
+
+        self.tx.send(cmd).await.unwrap();
+
+        // Await the response
+        match resp_rx.await {
+            Ok(res) => res.map_err(|e| {
+                warn!("BPFMAN update error: {}", e);
+                error_status(e)
+            }),
+            Err(e) => {
+                warn!("RPC update error: {}", e);
+                Err(Status::aborted(format!("{e}")))
+            }
+        }
+    }
+
+    // Replaces the program's bytecode on behalf of the caller and returns the
+    // new program.
+    async fn replace_program(
+        &self,
+        caller: &Caller,
+        id: u32,
+        location: Location,
+        name: String,
+        global_data: HashMap<String, Vec<u8>>,
+    ) -> Result<Program, Status> {
+        let owner = self.authorizer.owner_filter(caller)?;
+
+        let location = match location {
+            Location::Image(i) => crate::command::Location::Image(i.into()),
+            Location::File(p) => {
+                self.authorizer.authorize_file(caller, &p)?;
+                crate::command::Location::File(p)
+            }
+        };
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let cmd = Command::Replace(ReplaceArgs {
+            id,
+            location,
+            name,
+            global_data: (!global_data.is_empty()).then_some(global_data),
+            owner,
+            responder: resp_tx,
+        });
+
+        self.tx.send(cmd).await.unwrap();
+
+        // Await the response
+        match resp_rx.await {
+            Ok(res) => res.map_err(|e| {
+                warn!("BPFMAN replace error: {}", e);
+                error_status(e)
+            }),
+            Err(e) => {
+                warn!("RPC replace error: {}", e);
+                Err(Status::aborted(format!("{e}")))
+            }
+        }
+    }
+
+    // Loads, updates and unloads programs on behalf of the caller until the
+    // programs with a uuid are the ones in the request.
+    async fn apply_programs(
+        &self,
+        caller: &Caller,
+        request: ApplyRequest,
+    ) -> Result<Vec<ApplyOutcome>, Status> {
+        self.authorizer.authorize_manage_all(caller)?;
+
+        let mut uuids = HashSet::new();
+        for r in &request.programs {
+            if r.unload_on_exit {
+                return Err(Status::invalid_argument(format!(
+                    "program {} sets unload_on_exit, which apply does not support",
+                    r.name
+                )));
+            }
+            match r.uuid.as_deref() {
+                None | Some("") => {
+                    return Err(Status::invalid_argument(format!(
+                        "program {} has no uuid",
+                        r.name
+                    )))
+                }
+                Some(uuid) if !uuids.insert(uuid.to_string()) => {
+                    return Err(Status::invalid_argument(format!(
+                        "uuid {uuid} is used by more than one program"
+                    )))
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut programs = vec![];
+        for r in request.programs {
+            let digest = apply_digest(&r);
+            let program = program_from_load_request(r).and_then(|mut p| {
+                p.get_data_mut()
+                    .set_apply_digest(&digest)
+                    .map_err(|e| Status::aborted(format!("failed to set apply digest: {e}")))?;
+                if let Some(owner) = caller.owner() {
+                    p.get_data_mut()
+                        .set_owner(&owner)
+                        .map_err(|e| Status::aborted(format!("failed to set owner: {e}")))?;
+                }
+                if let Err(e) = self.authorizer.authorize_program(caller, &p) {
+                    p.get_data().discard();
+                    return Err(e);
+                }
+                Ok(p)
+            });
+            match program {
+                Ok(p) => programs.push(p),
                 Err(e) => {
-                    warn!("BPFMAN unload error: {}", e);
-                    Err(Status::aborted(format!("{e}")))
+                    programs.iter().for_each(|p| p.get_data().discard());
+                    return Err(e);
                 }
-            },
+            }
+        }
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let cmd = Command::Apply(ApplyArgs {
+            programs,
+            responder: resp_tx,
+        });
+
+        self.tx.send(cmd).await.unwrap();
+
+        // Await the response
+        match resp_rx.await {
+            Ok(res) => res.map_err(|e| {
+                warn!("BPFMAN apply error: {:#?}", e);
+                load_error_status(e)
+            }),
             Err(e) => {
-                warn!("RPC unload error: {}", e);
+                warn!("RPC apply error: {}", e);
                 Err(Status::aborted(format!("{e}")))
             }
         }
     }
+}
+
+#[tonic::async_trait]
+impl Bpfman for BpfmanLoader {
+    type WatchStream = ReceiverStream<Result<WatchResponse, Status>>;
+
+    async fn load(&self, request: Request<LoadRequest>) -> Result<Response<LoadResponse>, Status> {
+        let caller = Caller::from_request(&request);
+        debug!("Load request from {}", caller);
+        let request = request.into_inner();
+        let mut audit =
+            AuditRecord::new(AuditRequest::Load, caller.clone()).with_load_request(&request);
+
+        let result = self.load_program(&caller, request).await;
+        match &result {
+            Ok(program) => audit.loaded(program),
+            Err(status) => audit.failed(status.message()),
+        }
+        self.audit.record(&audit);
+
+        let program = result?;
+        let reply_entry =
+            LoadResponse {
+                info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC program: {e}"))
+                })?),
+                kernel_info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+                })?),
+            };
+        Ok(Response::new(reply_entry))
+    }
+
+    async fn unload(
+        &self,
+        request: Request<UnloadRequest>,
+    ) -> Result<Response<UnloadResponse>, Status> {
+        let caller = Caller::from_request(&request);
+        debug!("Unload request from {}", caller);
+        let id = request.into_inner().id;
+        let mut audit = AuditRecord::new(AuditRequest::Unload, caller.clone()).with_id(id);
+
+        let result = self.unload_program(&caller, id).await;
+        match &result {
+            Ok(()) => audit.succeeded(),
+            Err(status) => audit.failed(status.message()),
+        }
+        self.audit.record(&audit);
+
+        result?;
+        Ok(Response::new(UnloadResponse {}))
+    }
 
     async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
         let request = request.into_inner();
@@ -356,14 +667,331 @@ impl Bpfman for BpfmanLoader {
         &self,
         request: tonic::Request<PullBytecodeRequest>,
     ) -> std::result::Result<tonic::Response<PullBytecodeResponse>, tonic::Status> {
+        let caller = Caller::from_request(&request);
         let request = request.into_inner();
-        let image = match request.image {
+        let image: BytecodeImage = match request.image {
             Some(i) => i.into(),
             None => return Err(Status::aborted("Empty pull_bytecode request received")),
         };
+        let mut audit = AuditRecord::new(AuditRequest::PullBytecode, caller.clone())
+            .with_image(image.get_url());
+
+        // Pulling is only for callers that may load programs.
+        let result = match self.authorizer.allowed_program_types(&caller) {
+            Ok(_) => self.pull_image(image).await,
+            Err(e) => Err(e),
+        };
+        match &result {
+            Ok(digest) => audit.pulled(digest.clone()),
+            Err(status) => audit.failed(status.message()),
+        }
+        self.audit.record(&audit);
+
+        result?;
+        Ok(Response::new(PullBytecodeResponse {}))
+    }
+
+    async fn attach(
+        &self,
+        request: Request<AttachRequest>,
+    ) -> Result<Response<AttachResponse>, Status> {
+        let caller = Caller::from_request(&request);
+        let request = request.into_inner();
+
+        let info = request
+            .attach
+            .ok_or(Status::aborted("missing attach info"))?
+            .info
+            .ok_or(Status::aborted("missing info"))?;
+        let mut audit = AuditRecord::new(AuditRequest::Attach, caller.clone())
+            .with_id(request.id)
+            .with_attach_info(&info);
+
+        let result = self.attach_link(&caller, request.id, info).await;
+        match &result {
+            Ok(link_id) => audit.attached(*link_id),
+            Err(status) => audit.failed(status.message()),
+        }
+        self.audit.record(&audit);
+
+        Ok(Response::new(AttachResponse { link_id: result? }))
+    }
+
+    async fn detach(
+        &self,
+        request: Request<DetachRequest>,
+    ) -> Result<Response<DetachResponse>, Status> {
+        let caller = Caller::from_request(&request);
+        let request = request.into_inner();
+        let mut audit = match request.id {
+            Some(id) => AuditRecord::new(AuditRequest::Detach, caller.clone()).with_id(id),
+            None => {
+                AuditRecord::new(AuditRequest::Detach, caller.clone()).with_link_id(request.link_id)
+            }
+        };
+
+        let result = self.detach_link(&caller, request).await;
+        match &result {
+            Ok(()) => audit.succeeded(),
+            Err(status) => audit.failed(status.message()),
+        }
+        self.audit.record(&audit);
+
+        result?;
+        Ok(Response::new(DetachResponse {}))
+    }
+
+    async fn update(
+        &self,
+        request: Request<UpdateRequest>,
+    ) -> Result<Response<UpdateResponse>, Status> {
+        let caller = Caller::from_request(&request);
+        let request = request.into_inner();
+        let mut audit = AuditRecord::new(AuditRequest::Update, caller.clone()).with_id(request.id);
+
+        let result = self.update_program(&caller, request).await;
+        match &result {
+            Ok(program) => audit.updated(program),
+            Err(status) => audit.failed(status.message()),
+        }
+        self.audit.record(&audit);
+
+        let program = result?;
+        let reply_entry =
+            UpdateResponse {
+                info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC program: {e}"))
+                })?),
+                kernel_info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+                })?),
+            };
+        Ok(Response::new(reply_entry))
+    }
+
+    async fn replace(
+        &self,
+        request: Request<ReplaceRequest>,
+    ) -> Result<Response<ReplaceResponse>, Status> {
+        let caller = Caller::from_request(&request);
+        let request = request.into_inner();
+
+        let location = request
+            .bytecode
+            .ok_or(Status::aborted("missing bytecode info"))?
+            .location
+            .ok_or(Status::aborted("missing location"))?;
+        let mut audit = AuditRecord::new(AuditRequest::Replace, caller.clone())
+            .with_id(request.id)
+            .with_bytecode(&location);
+
+        let result = self
+            .replace_program(
+                &caller,
+                request.id,
+                location,
+                request.name,
+                request.global_data,
+            )
+            .await;
+        match &result {
+            Ok(program) => audit.replaced(program),
+            Err(status) => audit.failed(status.message()),
+        }
+        self.audit.record(&audit);
+
+        let program = result?;
+        let reply_entry =
+            ReplaceResponse {
+                info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC program: {e}"))
+                })?),
+                kernel_info: Some((&program).try_into().map_err(|e| {
+                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
+                })?),
+            };
+        Ok(Response::new(reply_entry))
+    }
+
+    async fn watch(
+        &self,
+        request: Request<WatchRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let request = request.into_inner();
+        let mut events = self.events.subscribe();
+        let (tx, rx) = mpsc::channel(32);
+
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(n)) => {
+                        warn!("Watch requester fell behind, {n} events were dropped");
+                        let _ = tx
+                            .send(Err(Status::data_loss(format!("{n} events were dropped"))))
+                            .await;
+                        break;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                if !event.matches(request.program_type, &request.match_metadata) {
+                    continue;
+                }
+
+                // The requester has gone away
+                if tx.send(Ok(event.into())).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn map_lookup(
+        &self,
+        request: Request<MapLookupRequest>,
+    ) -> Result<Response<MapLookupResponse>, Status> {
+        let owner = self
+            .authorizer
+            .owner_filter(&Caller::from_request(&request))?;
+        let request = request.into_inner();
+        let entries = self
+            .map_command(
+                request.id,
+                request.map_name,
+                MapOperation::Lookup { key: request.key },
+                owner,
+            )
+            .await?;
+
+        let value = entries
+            .into_iter()
+            .next()
+            .map(|(_, value)| value)
+            .unwrap_or_default();
+        Ok(Response::new(MapLookupResponse { value }))
+    }
+
+    async fn map_update(
+        &self,
+        request: Request<MapUpdateRequest>,
+    ) -> Result<Response<MapUpdateResponse>, Status> {
+        let caller = Caller::from_request(&request);
+        let request = request.into_inner();
+        let mut audit = AuditRecord::new(AuditRequest::MapUpdate, caller.clone())
+            .with_id(request.id)
+            .with_map(&request.map_name);
+
+        let result = match self.authorizer.owner_filter(&caller) {
+            Ok(owner) => {
+                self.map_command(
+                    request.id,
+                    request.map_name,
+                    MapOperation::Update {
+                        key: request.key,
+                        value: request.value,
+                        flags: request.flags,
+                    },
+                    owner,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        match &result {
+            Ok(_) => audit.succeeded(),
+            Err(status) => audit.failed(status.message()),
+        }
+        self.audit.record(&audit);
+
+        result?;
+        Ok(Response::new(MapUpdateResponse {}))
+    }
+
+    async fn map_delete(
+        &self,
+        request: Request<MapDeleteRequest>,
+    ) -> Result<Response<MapDeleteResponse>, Status> {
+        let caller = Caller::from_request(&request);
+        let request = request.into_inner();
+        let mut audit = AuditRecord::new(AuditRequest::MapDelete, caller.clone())
+            .with_id(request.id)
+            .with_map(&request.map_name);
+
+        let result = match self.authorizer.owner_filter(&caller) {
+            Ok(owner) => {
+                self.map_command(
+                    request.id,
+                    request.map_name,
+                    MapOperation::Delete { key: request.key },
+                    owner,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        match &result {
+            Ok(_) => audit.succeeded(),
+            Err(status) => audit.failed(status.message()),
+        }
+        self.audit.record(&audit);
+
+        result?;
+        Ok(Response::new(MapDeleteResponse {}))
+    }
+
+    async fn map_dump(
+        &self,
+        request: Request<MapDumpRequest>,
+    ) -> Result<Response<MapDumpResponse>, Status> {
+        let owner = self
+            .authorizer
+            .owner_filter(&Caller::from_request(&request))?;
+        let request = request.into_inner();
+        let entries = self
+            .map_command(request.id, request.map_name, MapOperation::Dump, owner)
+            .await?;
+
+        Ok(Response::new(MapDumpResponse {
+            entries: entries
+                .into_iter()
+                .map(|(key, value)| MapEntry { key, value })
+                .collect(),
+        }))
+    }
+
+    async fn verify(
+        &self,
+        request: Request<VerifyRequest>,
+    ) -> Result<Response<VerifyResponse>, Status> {
+        let caller = Caller::from_request(&request);
+        let program_types = self.authorizer.allowed_program_types(&caller)?;
+        let request = request.into_inner();
+
         let (resp_tx, resp_rx) = oneshot::channel();
-        let cmd = Command::PullBytecode(PullBytecodeArgs {
-            image,
+
+        let location = match request
+            .bytecode
+            .ok_or(Status::aborted("missing bytecode info"))?
+            .location
+            .ok_or(Status::aborted("missing location"))?
+        {
+            Location::Image(i) => crate::command::Location::Image(i.into()),
+            Location::File(p) => {
+                self.authorizer.authorize_file(&caller, &p)?;
+                crate::command::Location::File(p)
+            }
+        };
+
+        let cmd = Command::Verify(VerifyArgs {
+            location,
+            name: request.name,
+            global_data: request.global_data,
+            fn_name: request.fn_name,
+            verifier_log_level: request.verifier_log_level,
+            program_types,
             responder: resp_tx,
         });
 
@@ -372,39 +1000,374 @@ impl Bpfman for BpfmanLoader {
         // Await the response
         match resp_rx.await {
             Ok(res) => match res {
-                Ok(_) => {
-                    let reply = PullBytecodeResponse {};
-                    Ok(Response::new(reply))
-                }
+                Ok(result) => Ok(Response::new(VerifyResponse {
+                    name: result.name,
+                    program_type: result.program_type,
+                    verified_insns: result.verified_insns,
+                    bytes_xlated: result.bytes_xlated,
+                    bytes_jited: result.bytes_jited,
+                    maps: result
+                        .maps
+                        .into_iter()
+                        .map(|m| VerifyMapInfo {
+                            name: m.name,
+                            map_type: m.map_type,
+                            key_size: m.key_size,
+                            value_size: m.value_size,
+                            max_entries: m.max_entries,
+                        })
+                        .collect(),
+                })),
                 Err(e) => {
-                    warn!("BPFMAN pull_bytecode error: {:#?}", e);
-                    Err(Status::aborted(format!("{e}")))
+                    warn!("BPFMAN verify error: {}", e);
+                    Err(load_error_status(e))
                 }
             },
-
             Err(e) => {
-                warn!("RPC pull_bytecode error: {:#?}", e);
+                warn!("RPC verify error: {}", e);
                 Err(Status::aborted(format!("{e}")))
             }
         }
     }
+
+    async fn apply(
+        &self,
+        request: Request<ApplyRequest>,
+    ) -> Result<Response<ApplyResponse>, Status> {
+        let caller = Caller::from_request(&request);
+        let request = request.into_inner();
+        let mut audit = AuditRecord::new(AuditRequest::Apply, caller.clone());
+
+        let result = self.apply_programs(&caller, request).await;
+        match &result {
+            Ok(outcomes) => audit.applied(outcomes),
+            Err(status) => audit.failed(status.message()),
+        }
+        self.audit.record(&audit);
+
+        Ok(Response::new(ApplyResponse {
+            results: result?
+                .into_iter()
+                .map(|o| ApplyResult {
+                    uuid: o.uuid,
+                    action: o.action as i32,
+                    id: o.id,
+                    error: o.error,
+                })
+                .collect(),
+        }))
+    }
+}
+
+// Opens a pidfd for the caller, whose programs are then unloaded when it
+// exits.
+fn bind_to_caller(caller: &Caller) -> Result<(BoundProcess, OwnedFd), Status> {
+    match caller {
+        Caller::Local { pid: Some(pid), .. } => BoundProcess::open(*pid)
+            .map_err(|e| Status::aborted(format!("unable to watch process {pid}: {e}"))),
+        _ => Err(Status::invalid_argument(
+            "unload_on_exit is only supported for callers on the unix socket",
+        )),
+    }
+}
+
+// Callers that may not make the request get PERMISSION_DENIED, and other
+// failures are reported as ABORTED.
+fn error_status(e: BpfmanError) -> Status {
+    match e {
+        BpfmanError::NotOwner(_) | BpfmanError::ProgramTypeNotAllowed(_) => {
+            Status::permission_denied(format!("{e}"))
+        }
+        e => Status::aborted(format!("{e}")),
+    }
+}
+
+// Program load failures return the verifier log in the status details rather
+// than in the message, so that clients can show it as the kernel wrote it.
+fn load_error_status(e: BpfmanError) -> Status {
+    match e {
+        BpfmanError::BpfProgramError(ProgramError::LoadError {
+            io_error,
+            verifier_log,
+        }) => VerifierLog {
+            log: verifier_log_tail(verifier_log.to_string()),
+        }
+        .into_status(format!("the BPF_PROG_LOAD syscall failed: {io_error}")),
+        e => error_status(e),
+    }
+}
+
+fn verifier_log_tail(log: String) -> String {
+    if log.len() <= MAX_VERIFIER_LOG_LEN {
+        return log;
+    }
+    let mut start = log.len() - MAX_VERIFIER_LOG_LEN;
+    while !log.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("...\n{}", &log[start..])
+}
+
+// Returns a digest of the parts of a load request that can only be changed by
+// loading the program again. The priority, proceed-on and metadata can be
+// updated in place, so they're left out, as is the uuid.
+fn apply_digest(request: &LoadRequest) -> String {
+    let mut request = request.clone();
+    let global_data: BTreeMap<_, _> = std::mem::take(&mut request.global_data)
+        .into_iter()
+        .collect();
+    request.metadata.clear();
+    request.uuid = None;
+    request.verifier_log_level = None;
+    if let Some(info) = request.attach.as_mut().and_then(|a| a.info.as_mut()) {
+        match info {
+            Info::XdpAttachInfo(i) => {
+                i.priority = 0;
+                i.position = 0;
+                i.proceed_on.clear();
+            }
+            Info::TcAttachInfo(i) => {
+                i.priority = 0;
+                i.position = 0;
+                i.proceed_on.clear();
+            }
+            Info::CgroupAttachInfo(i) => {
+                i.priority = 0;
+                i.position = 0;
+            }
+            _ => {}
+        }
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{request:?}{global_data:?}"));
+    hex::encode(hasher.finalize())
+}
+
+// Builds the program described by a load request, ready to be loaded.
+fn program_from_load_request(request: LoadRequest) -> Result<Program, Status> {
+    let bytecode_source = match request
+        .bytecode
+        .ok_or(Status::aborted("missing bytecode info"))?
+        .location
+        .ok_or(Status::aborted("missing location"))?
+    {
+        Location::Image(i) => crate::command::Location::Image(i.into()),
+        Location::File(p) => crate::command::Location::File(p),
+    };
+
+    if request.ttl_seconds == Some(0) {
+        return Err(Status::invalid_argument(
+            "ttl_seconds must be greater than 0",
+        ));
+    }
+    let expires_at = match request.ttl_seconds {
+        Some(ttl) => Some(
+            SystemTime::now()
+                .checked_add(Duration::from_secs(ttl))
+                .ok_or(Status::invalid_argument("ttl_seconds is too large"))?,
+        ),
+        None => None,
+    };
+
+    let mut data = ProgramData::new_pre_load(
+        bytecode_source,
+        request.name,
+        request.metadata,
+        request.global_data,
+        request.map_owner_id,
+    )
+    .map_err(|e| Status::aborted(format!("failed to create ProgramData: {e}")))?;
+
+    if let Some(level) = request.verifier_log_level {
+        data.set_verifier_log_level(level)
+            .map_err(|e| Status::aborted(format!("failed to set verifier log level: {e}")))?;
+    }
+
+    if let Some(uuid) = request.uuid.filter(|u| !u.is_empty()) {
+        data.set_uuid(&uuid)
+            .map_err(|e| Status::aborted(format!("failed to set uuid: {e}")))?;
+    }
+
+    if let Some(expires_at) = expires_at {
+        data.set_expires_at(expires_at)
+            .map_err(|e| Status::aborted(format!("failed to set expiry: {e}")))?;
+    }
+
+    // Programs without attach info are only loaded, and can be attached
+    // later with the Attach command.
+    Ok(match request.attach.and_then(|a| a.info) {
+        Some(info) => program_from_info(data, info)?,
+        None => {
+            let program_type = ProgramType::try_from(request.program_type)
+                .map_err(|e| Status::aborted(format!("invalid program type: {e}")))?;
+            Program::new_load_only(data, program_type)
+                .map_err(|e| Status::invalid_argument(format!("{e}")))?
+        }
+    })
+}
+
+// Builds the program (or link) described by the provided attach info.
+fn program_from_info(data: ProgramData, info: Info) -> Result<Program, Status> {
+    Ok(match info {
+        Info::XdpAttachInfo(XdpAttachInfo {
+            priority,
+            iface,
+            position: _,
+            proceed_on,
+        }) => Program::Xdp(
+            XdpProgram::new(
+                data,
+                priority,
+                iface,
+                XdpProceedOn::from_int32s(proceed_on)
+                    .map_err(|_| Status::aborted("failed to parse proceed_on"))?,
+            )
+            .map_err(|e| Status::aborted(format!("failed to create xdpprogram: {e}")))?,
+        ),
+        Info::TcAttachInfo(TcAttachInfo {
+            priority,
+            iface,
+            position: _,
+            direction,
+            proceed_on,
+        }) => {
+            let direction = direction
+                .try_into()
+                .map_err(|_| Status::aborted("direction is not a string"))?;
+            Program::Tc(
+                TcProgram::new(
+                    data,
+                    priority,
+                    iface,
+                    TcProceedOn::from_int32s(proceed_on)
+                        .map_err(|_| Status::aborted("failed to parse proceed_on"))?,
+                    direction,
+                )
+                .map_err(|e| Status::aborted(format!("failed to create tcprogram: {e}")))?,
+            )
+        }
+        Info::TracepointAttachInfo(TracepointAttachInfo { tracepoint }) => Program::Tracepoint(
+            TracepointProgram::new(data, tracepoint)
+                .map_err(|e| Status::aborted(format!("failed to create tcprogram: {e}")))?,
+        ),
+        Info::RawTracepointAttachInfo(RawTracepointAttachInfo { tracepoint }) => {
+            Program::RawTracepoint(RawTracepointProgram::new(data, tracepoint).map_err(|e| {
+                Status::aborted(format!("failed to create rawtracepointprogram: {e}"))
+            })?)
+        }
+        Info::BtfTracepointAttachInfo(BtfTracepointAttachInfo { tracepoint }) => {
+            Program::BtfTracepoint(BtfTracepointProgram::new(data, tracepoint).map_err(|e| {
+                Status::aborted(format!("failed to create btftracepointprogram: {e}"))
+            })?)
+        }
+        Info::KprobeAttachInfo(KprobeAttachInfo {
+            fn_name,
+            offset,
+            retprobe,
+            container_pid,
+        }) => Program::Kprobe(
+            KprobeProgram::new(data, fn_name, offset, retprobe, container_pid)
+                .map_err(|e| Status::aborted(format!("failed to create kprobeprogram: {e}")))?,
+        ),
+        Info::UprobeAttachInfo(UprobeAttachInfo {
+            fn_name,
+            offset,
+            target,
+            retprobe,
+            pid,
+            container_pid,
+        }) => Program::Uprobe(
+            UprobeProgram::new(data, fn_name, offset, target, retprobe, pid, container_pid)
+                .map_err(|e| Status::aborted(format!("failed to create uprobeprogram: {e}")))?,
+        ),
+        Info::FentryAttachInfo(FentryAttachInfo { fn_name }) => Program::Fentry(
+            FentryProgram::new(data, fn_name)
+                .map_err(|e| Status::aborted(format!("failed to create fentryprogram: {e}")))?,
+        ),
+        Info::FexitAttachInfo(FexitAttachInfo { fn_name }) => Program::Fexit(
+            FexitProgram::new(data, fn_name)
+                .map_err(|e| Status::aborted(format!("failed to create fexitprogram: {e}")))?,
+        ),
+        Info::LsmAttachInfo(LsmAttachInfo { hook }) => Program::Lsm(
+            LsmProgram::new(data, hook)
+                .map_err(|e| Status::aborted(format!("failed to create lsmprogram: {e}")))?,
+        ),
+        Info::CgroupAttachInfo(CgroupAttachInfo {
+            cgroup_path,
+            attach_type,
+            priority,
+            ..
+        }) => Program::Cgroup(
+            CgroupProgram::new(
+                data,
+                cgroup_path,
+                CgroupAttachType::try_from(attach_type)
+                    .map_err(|e| Status::invalid_argument(format!("{e}")))?,
+                priority,
+            )
+            .map_err(|e| Status::aborted(format!("failed to create cgroupprogram: {e}")))?,
+        ),
+        Info::PerfEventAttachInfo(PerfEventAttachInfo {
+            event_type,
+            event,
+            sample_period,
+            sample_frequency,
+            cpu,
+            pid,
+        }) => {
+            let event_type = PerfEventType::try_from(event_type)
+                .map_err(|e| Status::invalid_argument(format!("{e}")))?;
+            event_type
+                .event_config(&event)
+                .map_err(|e| Status::invalid_argument(format!("{e}")))?;
+            if sample_period.is_some() == sample_frequency.is_some() {
+                return Err(Status::invalid_argument(
+                    "exactly one of sample_period and sample_frequency must be set",
+                ));
+            }
+            if let Some(pid) = pid.filter(|pid| *pid < 0) {
+                return Err(Status::invalid_argument(format!("invalid pid {pid}")));
+            }
+            Program::PerfEvent(
+                PerfEventProgram::new(
+                    data,
+                    event_type,
+                    event,
+                    sample_period,
+                    sample_frequency,
+                    cpu,
+                    pid,
+                )
+                .map_err(|e| Status::aborted(format!("failed to create perfeventprogram: {e}")))?,
+            )
+        }
+    })
 }
 
 #[cfg(test)]
 mod test {
     use std::{collections::HashMap, time::SystemTime};
 
-    use bpfman_api::v1::{
-        bytecode_location::Location, AttachInfo, BytecodeLocation, LoadRequest, XdpAttachInfo,
+    use bpfman_api::{
+        v1::{
+            bytecode_location::Location, AttachInfo, BytecodeLocation, LoadRequest, XdpAttachInfo,
+        },
+        ApplyAction, ProgramEventType,
     };
     use tokio::sync::mpsc::Receiver;
 
     use super::*;
+    use crate::command::{ApplyOutcome, VerifyResult};
 
     #[tokio::test]
     async fn test_load_with_valid_id() {
         let (tx, rx) = mpsc::channel(32);
-        let loader = BpfmanLoader::new(tx.clone());
+        let loader = BpfmanLoader::new(
+            tx.clone(),
+            broadcast::channel(32).0,
+            Authorizer::default(),
+            AuditLog::default(),
+        );
 
         let attach_info = AttachInfo {
             info: Some(Info::XdpAttachInfo(XdpAttachInfo {
@@ -433,10 +1396,211 @@ mod test {
         assert!(res.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_load_unload_on_exit_needs_caller() {
+        let (tx, _rx) = mpsc::channel(32);
+        let loader = BpfmanLoader::new(
+            tx.clone(),
+            broadcast::channel(32).0,
+            Authorizer::default(),
+            AuditLog::default(),
+        );
+
+        // Requests made directly have no SO_PEERCRED credentials.
+        let request = LoadRequest {
+            bytecode: Some(BytecodeLocation {
+                location: Some(Location::File("/tmp/xdp_pass.o".to_string())),
+            }),
+            unload_on_exit: true,
+            ..Default::default()
+        };
+        let err = loader.load(Request::new(request)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_load_zero_ttl() {
+        let (tx, _rx) = mpsc::channel(32);
+        let loader = BpfmanLoader::new(
+            tx.clone(),
+            broadcast::channel(32).0,
+            Authorizer::default(),
+            AuditLog::default(),
+        );
+
+        let request = LoadRequest {
+            bytecode: Some(BytecodeLocation {
+                location: Some(Location::File("/tmp/xdp_pass.o".to_string())),
+            }),
+            ttl_seconds: Some(0),
+            ..Default::default()
+        };
+        let err = loader.load(Request::new(request)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        let request = LoadRequest {
+            bytecode: Some(BytecodeLocation {
+                location: Some(Location::File("/tmp/xdp_pass.o".to_string())),
+            }),
+            ttl_seconds: Some(u64::MAX),
+            ..Default::default()
+        };
+        let err = loader.load(Request::new(request)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_load_only_dispatcher_types() {
+        let (tx, _rx) = mpsc::channel(32);
+        let loader = BpfmanLoader::new(
+            tx.clone(),
+            broadcast::channel(32).0,
+            Authorizer::default(),
+            AuditLog::default(),
+        );
+
+        // XDP and TC programs can't be loaded without an interface to build
+        // their dispatcher on, which is reported before anything is loaded.
+        for program_type in [ProgramType::Xdp, ProgramType::Tc] {
+            let request = LoadRequest {
+                bytecode: Some(BytecodeLocation {
+                    location: Some(Location::File("/tmp/xdp_pass.o".to_string())),
+                }),
+                program_type: program_type as u32,
+                ..Default::default()
+            };
+            let err = loader.load(Request::new(request)).await.unwrap_err();
+            assert_eq!(err.code(), tonic::Code::InvalidArgument);
+            assert!(err
+                .message()
+                .contains("must be attached when they are loaded"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detach_requires_one_target() {
+        let (tx, _rx) = mpsc::channel(32);
+        let loader = BpfmanLoader::new(
+            tx.clone(),
+            broadcast::channel(32).0,
+            Authorizer::default(),
+            AuditLog::default(),
+        );
+
+        let request = DetachRequest {
+            link_id: 0,
+            id: None,
+        };
+        let err = loader.detach(Request::new(request)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        let request = DetachRequest {
+            link_id: 2180913504,
+            id: Some(7693),
+        };
+        let err = loader.detach(Request::new(request)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_load_perf_event_sample_policy() {
+        let (tx, _rx) = mpsc::channel(32);
+        let loader = BpfmanLoader::new(
+            tx.clone(),
+            broadcast::channel(32).0,
+            Authorizer::default(),
+            AuditLog::default(),
+        );
+
+        let request = LoadRequest {
+            bytecode: Some(BytecodeLocation {
+                location: Some(Location::File("/tmp/perf_event.o".to_string())),
+            }),
+            attach: Some(AttachInfo {
+                info: Some(Info::PerfEventAttachInfo(PerfEventAttachInfo {
+                    event_type: "software".to_string(),
+                    event: "cpu_clock".to_string(),
+                    sample_period: Some(100_000),
+                    sample_frequency: Some(99),
+                    cpu: None,
+                    pid: None,
+                })),
+            }),
+            ..Default::default()
+        };
+        let err = loader.load(Request::new(request)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_load_perf_event_negative_pid() {
+        let (tx, _rx) = mpsc::channel(32);
+        let loader = BpfmanLoader::new(
+            tx.clone(),
+            broadcast::channel(32).0,
+            Authorizer::default(),
+            AuditLog::default(),
+        );
+
+        let request = LoadRequest {
+            bytecode: Some(BytecodeLocation {
+                location: Some(Location::File("/tmp/perf_event.o".to_string())),
+            }),
+            attach: Some(AttachInfo {
+                info: Some(Info::PerfEventAttachInfo(PerfEventAttachInfo {
+                    event_type: "software".to_string(),
+                    event: "cpu_clock".to_string(),
+                    sample_period: Some(100_000),
+                    sample_frequency: None,
+                    cpu: None,
+                    pid: Some(-1),
+                })),
+            }),
+            ..Default::default()
+        };
+        let err = loader.load(Request::new(request)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_watch() {
+        let (tx, _rx) = mpsc::channel(32);
+        let (events_tx, _) = broadcast::channel(32);
+        let loader = BpfmanLoader::new(
+            tx.clone(),
+            events_tx.clone(),
+            Authorizer::default(),
+            AuditLog::default(),
+        );
+
+        let request = WatchRequest {
+            program_type: Some(ProgramType::Xdp as u32),
+            match_metadata: HashMap::new(),
+        };
+
+        let mut stream = loader
+            .watch(Request::new(request))
+            .await
+            .unwrap()
+            .into_inner()
+            .into_inner();
+
+        let event = ProgramEvent::new(ProgramEventType::Loaded, &mock_program()).unwrap();
+        events_tx.send(event).unwrap();
+
+        let res = stream.recv().await.unwrap().unwrap();
+        assert_eq!(res.event_type, ProgramEventType::Loaded as i32);
+    }
+
     #[tokio::test]
     async fn test_pull_bytecode() {
         let (tx, rx) = mpsc::channel(32);
-        let loader = BpfmanLoader::new(tx.clone());
+        let loader = BpfmanLoader::new(
+            tx.clone(),
+            broadcast::channel(32).0,
+            Authorizer::default(),
+            AuditLog::default(),
+        );
 
         let request = PullBytecodeRequest {
             image: Some(bpfman_api::v1::BytecodeImage {
@@ -453,7 +1617,133 @@ mod test {
         assert!(res.is_ok());
     }
 
-    async fn mock_serve(mut rx: Receiver<Command>) {
+    #[test]
+    fn test_load_error_status() {
+        let status = load_error_status(BpfmanError::InvalidInterface);
+        assert_eq!(status.code(), tonic::Code::Aborted);
+        assert!(VerifierLog::from_status(&status).is_none());
+    }
+
+    #[test]
+    fn test_error_status() {
+        let status = error_status(BpfmanError::NotOwner(7));
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+        let status = error_status(BpfmanError::Error("oops".to_string()));
+        assert_eq!(status.code(), tonic::Code::Aborted);
+    }
+
+    #[test]
+    fn test_verifier_log_tail() {
+        let log = "0: R1=ctx() R10=fp0\n".to_string();
+        assert_eq!(verifier_log_tail(log.clone()), log);
+
+        let log = "x".repeat(MAX_VERIFIER_LOG_LEN) + "invalid mem access 'scalar'";
+        let tail = verifier_log_tail(log);
+        assert!(tail.starts_with("...\n"));
+        assert!(tail.ends_with("invalid mem access 'scalar'"));
+        assert_eq!(tail.len(), MAX_VERIFIER_LOG_LEN + 4);
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        let (tx, rx) = mpsc::channel(32);
+        let loader = BpfmanLoader::new(
+            tx.clone(),
+            broadcast::channel(32).0,
+            Authorizer::default(),
+            AuditLog::default(),
+        );
+
+        let request = VerifyRequest {
+            bytecode: Some(BytecodeLocation {
+                location: Some(Location::File("/tmp/xdp_pass.o".to_string())),
+            }),
+            name: "xdp_pass".to_string(),
+            ..Default::default()
+        };
+
+        tokio::spawn(async move {
+            mock_serve(rx).await;
+        });
+
+        let res = loader.verify(Request::new(request)).await.unwrap();
+        let res = res.into_inner();
+        assert_eq!(res.name, "xdp_pass");
+        assert_eq!(res.verified_insns, 2);
+        assert!(res.maps.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_apply() {
+        let (tx, rx) = mpsc::channel(32);
+        let loader = BpfmanLoader::new(
+            tx.clone(),
+            broadcast::channel(32).0,
+            Authorizer::default(),
+            AuditLog::default(),
+        );
+
+        let request = |uuid: &str| LoadRequest {
+            bytecode: Some(BytecodeLocation {
+                location: Some(Location::File("/tmp/xdp_pass.o".to_string())),
+            }),
+            name: "xdp_pass".to_string(),
+            program_type: ProgramType::Xdp as u32,
+            uuid: Some(uuid.to_string()),
+            ..Default::default()
+        };
+
+        tokio::spawn(async move {
+            mock_serve(rx).await;
+        });
+
+        let res = loader
+            .apply(Request::new(ApplyRequest {
+                programs: vec![request("a"), request("b")],
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(res.results.len(), 2);
+        assert_eq!(res.results[0].uuid, "a");
+        assert_eq!(res.results[1].action, ApplyAction::Loaded as i32);
+
+        let err = loader
+            .apply(Request::new(ApplyRequest {
+                programs: vec![request("a"), request("a")],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_apply_digest() {
+        let request = |priority: i32, iface: &str| LoadRequest {
+            name: "xdp_pass".to_string(),
+            attach: Some(AttachInfo {
+                info: Some(Info::XdpAttachInfo(XdpAttachInfo {
+                    iface: iface.to_string(),
+                    priority,
+                    position: 0,
+                    proceed_on: vec![],
+                })),
+            }),
+            uuid: Some("a".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            apply_digest(&request(50, "eth0")),
+            apply_digest(&request(60, "eth0"))
+        );
+        assert_ne!(
+            apply_digest(&request(50, "eth0")),
+            apply_digest(&request(50, "eth1"))
+        );
+    }
+
+    fn mock_program() -> Program {
         let mut data = ProgramData::new_pre_load(
             crate::command::Location::File("/tmp/fake".to_string()),
             "xdp_pass".to_string(),
@@ -478,9 +1768,11 @@ mod test {
         data.set_kernel_bytes_memlock(0).unwrap();
         data.set_kernel_verified_insns(0).unwrap();
 
-        let program = Program::Xdp(
-            XdpProgram::new(data, 0, "eth0".to_string(), XdpProceedOn::default()).unwrap(),
-        );
+        Program::Xdp(XdpProgram::new(data, 0, "eth0".to_string(), XdpProceedOn::default()).unwrap())
+    }
+
+    async fn mock_serve(mut rx: Receiver<Command>) {
+        let program = mock_program();
 
         while let Some(cmd) = rx.recv().await {
             match cmd {
@@ -488,7 +1780,43 @@ mod test {
                 Command::Unload(args) => args.responder.send(Ok(())).unwrap(),
                 Command::List { responder, .. } => responder.send(Ok(vec![])).unwrap(),
                 Command::Get(args) => args.responder.send(Ok(program.clone())).unwrap(),
-                Command::PullBytecode(args) => args.responder.send(Ok(())).unwrap(),
+                Command::PullBytecode(args) => {
+                    args.responder.send(Ok("sha256:0123".to_string())).unwrap()
+                }
+                Command::Attach(args) => args.responder.send(Ok(1)).unwrap(),
+                Command::Detach(args) => args.responder.send(Ok(())).unwrap(),
+                Command::Update(args) => args.responder.send(Ok(program.clone())).unwrap(),
+                Command::Replace(args) => args.responder.send(Ok(program.clone())).unwrap(),
+                Command::Map(args) => args
+                    .responder
+                    .send(Ok(vec![(vec![0; 4], vec![0; 8])]))
+                    .unwrap(),
+                Command::Verify(args) => args
+                    .responder
+                    .send(Ok(VerifyResult {
+                        name: "xdp_pass".to_string(),
+                        program_type: 6,
+                        verified_insns: 2,
+                        bytes_xlated: 16,
+                        bytes_jited: 21,
+                        maps: vec![],
+                    }))
+                    .unwrap(),
+                Command::StaticPrograms(_) => {}
+                Command::ProcessExited(_) => {}
+                Command::Apply(args) => args
+                    .responder
+                    .send(Ok(args
+                        .programs
+                        .iter()
+                        .map(|p| ApplyOutcome {
+                            uuid: p.get_data().get_uuid().unwrap().unwrap(),
+                            action: ApplyAction::Loaded,
+                            id: 0,
+                            error: None,
+                        })
+                        .collect()))
+                    .unwrap(),
             }
         }
     }
@@ -7,9 +7,14 @@ use std::{
     path::Path,
 };
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use bpfman_api::{
-    config::Config, util::directories::RTPATH_BPFMAN_SOCKET, v1::bpfman_server::BpfmanServer,
+    config::{Config, TcpConfig},
+    util::directories::{
+        CFGDIR_STATIC_PROGRAMS, CFGPATH_BPFMAN_CERTS_KEY, CFGPATH_BPFMAN_CERTS_PEM,
+        CFGPATH_CA_CERTS_PEM, RTPATH_BPFMAN_SOCKET,
+    },
+    v1::bpfman_server::BpfmanServer,
 };
 use libsystemd::activation::IsType;
 use log::{debug, error, info};
@@ -21,34 +26,42 @@ use tokio::{
     task::{JoinHandle, JoinSet},
 };
 use tokio_stream::wrappers::UnixListenerStream;
-use tonic::transport::Server;
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
 
 use crate::{
+    audit::AuditLog,
+    auth::{client_identity, Authorizer},
+    bound_process::wait_for_exit,
     bpf::BpfManager,
     oci_utils::ImageManager,
     rpc::BpfmanLoader,
+    static_program::{get_static_programs, watch_static_programs},
     storage::StorageManager,
     utils::{set_file_permissions, SOCK_MODE},
     ROOT_DB,
 };
 
+// Number of program events buffered for each Watch requester before it is
+// considered to have fallen behind.
+const EVENTS_CAPACITY: usize = 1024;
+
 pub async fn serve(config: &Config, csi_support: bool, timeout: u64) -> anyhow::Result<()> {
     let (shutdown_tx, shutdown_rx1) = broadcast::channel(32);
     let shutdown_rx2 = shutdown_tx.subscribe();
     let shutdown_rx3 = shutdown_tx.subscribe();
     let shutdown_rx4 = shutdown_tx.subscribe();
+    let shutdown_rx5 = shutdown_tx.subscribe();
+    let shutdown_rx6 = shutdown_tx.subscribe();
     let shutdown_handle = tokio::spawn(shutdown_handler(timeout, shutdown_tx));
 
     let (tx, rx) = mpsc::channel(32);
+    let (events_tx, _) = broadcast::channel(EVENTS_CAPACITY);
 
-    let loader = BpfmanLoader::new(tx.clone());
+    let authorizer = Authorizer::new(config.authorization.clone());
+    let audit = AuditLog::new(config.audit.as_ref())?;
+    let loader = BpfmanLoader::new(tx.clone(), events_tx.clone(), authorizer, audit.clone());
     let path = RTPATH_BPFMAN_SOCKET.to_string();
-    let service = BpfmanServer::new(loader);
-
-    let mut listeners: Vec<_> = Vec::new();
-
-    let handle = serve_unix(path.clone(), service.clone(), shutdown_rx1).await?;
-    listeners.push(handle);
+    let service = BpfmanServer::new(loader.clone());
 
     let allow_unsigned = config.signing.as_ref().map_or(true, |s| s.allow_unsigned);
     let (itx, irx) = mpsc::channel(32);
@@ -58,32 +71,40 @@ pub async fn serve(config: &Config, csi_support: bool, timeout: u64) -> anyhow::
         image_manager.run(shutdown_rx2).await;
     });
 
-    let mut bpf_manager = BpfManager::new(config.clone(), rx, itx);
+    let mut bpf_manager = BpfManager::new(config.clone(), rx, itx, events_tx, audit);
     bpf_manager.rebuild_state().await?;
 
-    // TODO(astoycos) see issue #881
-    //let static_programs = get_static_programs(static_program_path).await?;
-
-    // Load any static programs first
-    // if !static_programs.is_empty() {
-    //     for prog in static_programs {
-    //         let ret_prog = bpf_manager.add_program(prog).await?;
-    //         // Get the Kernel Info.
-    //         let kernel_info = ret_prog
-    //             .kernel_info()
-    //             .expect("kernel info should be set for all loaded programs");
-    //         info!("Loaded static program with program id {}", kernel_info.id)
-    //     }
-    // };
+    // Watch the processes programs were bound to before the restart. Those
+    // that have exited have their programs unloaded straight away.
+    for process in bpf_manager.bound_processes() {
+        tokio::spawn(wait_for_exit(process, process.reopen(), tx.clone()));
+    }
+
+    // Load any static programs before accepting requests, then keep them in
+    // line with their files.
+    let static_programs = get_static_programs(CFGDIR_STATIC_PROGRAMS).await?;
+    bpf_manager.reconcile_static_programs(static_programs).await;
+    let static_watch_handle = tokio::spawn(watch_static_programs(tx.clone(), shutdown_rx5));
+
+    let mut listeners: Vec<_> = Vec::new();
+
+    let handle = serve_unix(path.clone(), service.clone(), shutdown_rx1).await?;
+    listeners.push(handle);
+
+    if let Some(tcp) = &config.tcp {
+        let handle = serve_tcp(tcp, loader, shutdown_rx6).await?;
+        listeners.push(handle);
+    }
 
     if csi_support {
         let storage_manager = StorageManager::new(tx);
         let storage_manager_handle =
             tokio::spawn(async move { storage_manager.run(shutdown_rx3).await });
-        let (_, res_image, res_storage, _, _) = join!(
+        let (_, res_image, res_storage, _, _, _) = join!(
             join_listeners(listeners),
             image_manager_handle,
             storage_manager_handle,
+            static_watch_handle,
             bpf_manager.process_commands(shutdown_rx4),
             shutdown_handle
         );
@@ -94,9 +115,10 @@ pub async fn serve(config: &Config, csi_support: bool, timeout: u64) -> anyhow::
             return Err(e.into());
         }
     } else {
-        let (_, res_image, _, _) = join!(
+        let (_, res_image, _, _, _) = join!(
             join_listeners(listeners),
             image_manager_handle,
+            static_watch_handle,
             bpf_manager.process_commands(shutdown_rx4),
             shutdown_handle
         );
@@ -171,6 +193,46 @@ async fn serve_unix(
     }))
 }
 
+// Serves requests on a TCP port, from clients with a certificate signed by the
+// bpfman CA. The subject of the client certificate is added to each request.
+async fn serve_tcp(
+    config: &TcpConfig,
+    loader: BpfmanLoader,
+    mut shutdown_channel: broadcast::Receiver<()>,
+) -> anyhow::Result<JoinHandle<()>> {
+    let ca = tokio::fs::read(CFGPATH_CA_CERTS_PEM)
+        .await
+        .with_context(|| format!("unable to read CA certificate {CFGPATH_CA_CERTS_PEM}"))?;
+    let cert = tokio::fs::read(CFGPATH_BPFMAN_CERTS_PEM)
+        .await
+        .with_context(|| format!("unable to read certificate {CFGPATH_BPFMAN_CERTS_PEM}"))?;
+    let key = tokio::fs::read(CFGPATH_BPFMAN_CERTS_KEY)
+        .await
+        .with_context(|| format!("unable to read key {CFGPATH_BPFMAN_CERTS_KEY}"))?;
+    let tls = ServerTlsConfig::new()
+        .identity(Identity::from_pem(cert, key))
+        .client_ca_root(Certificate::from_pem(ca));
+
+    let addr = config.socket_addr();
+    let serve = Server::builder()
+        .tls_config(tls)?
+        .add_service(BpfmanServer::with_interceptor(loader, client_identity))
+        .serve_with_shutdown(addr, async move {
+            match shutdown_channel.recv().await {
+                Ok(()) => debug!("TCP Socket: Received shutdown signal"),
+                Err(e) => error!("Error receiving shutdown signal {:?}", e),
+            };
+        });
+
+    Ok(tokio::spawn(async move {
+        info!("Listening on {addr} with mutual TLS");
+        if let Err(e) = serve.await {
+            eprintln!("Error = {e:?}");
+        }
+        info!("Shutdown TCP Handler {}", addr);
+    }))
+}
+
 fn systemd_unix_stream(_path: String) -> anyhow::Result<UnixListenerStream> {
     let listen_fds = libsystemd::activation::receive_descriptors(true)?;
     if listen_fds.len() == 1 {
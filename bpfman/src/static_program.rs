@@ -1,264 +1,664 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-// TODO(astoycos) see issue #881
-// use std::{
-//     collections::HashMap,
-//     path::{Path, PathBuf},
-// };
-
-// use anyhow::bail;
-// use bpfman_api::{
-//     util::directories::CFGDIR_STATIC_PROGRAMS, ProgramType, TcProceedOn, XdpProceedOn,
-// };
-// use log::{info, warn};
-// use serde::Deserialize;
-// use tokio::fs;
-
-// use crate::{
-//     command::{
-//         Direction,
-//         Location::{File, Image},
-//         Program, ProgramData, TcProgram, TracepointProgram, XdpProgram,
-//     },
-//     oci_utils::image_manager::BytecodeImage,
-//     utils::read_to_string,
-// };
-
-// #[derive(Debug, Clone, Deserialize)]
-// pub(crate) struct XdpAttachInfo {
-//     pub(crate) priority: i32,
-//     pub(crate) iface: String,
-//     pub(crate) proceed_on: XdpProceedOn,
-// }
-
-// #[derive(Debug, Clone, Deserialize)]
-// pub(crate) struct TcAttachInfo {
-//     pub(crate) priority: i32,
-//     pub(crate) iface: String,
-//     pub(crate) proceed_on: TcProceedOn,
-//     pub(crate) direction: Direction,
-// }
-
-// #[derive(Debug, Clone, Deserialize)]
-// pub(crate) struct TracepointAttachInfo {
-//     pub(crate) tracepoint: String,
-// }
-
-// // TODO not yet implemented
-// // #[derive(Debug, Clone, Deserialize)]
-// // pub(crate) struct KprobeAttachInfo {
-// //     pub(crate) fn_name: String,
-// //     pub(crate) offset: u64,
-// //     pub(crate) retprobe: bool,
-// //     pub(crate) container_pid: Option<i32>,
-// // }
-
-// // #[derive(Debug, Clone, Deserialize)]
-// // pub(crate) struct UprobeAttachInfo {
-// //     pub(crate) fn_name: Option<String>,
-// //     pub(crate) offset: u64,
-// //     pub(crate) target: String,
-// //     pub(crate) retprobe: bool,
-// //     pub(crate) pid: Option<i32>,
-// //     pub(crate) container_pid: Option<i32>,
-// // }
-
-// #[derive(Debug, Deserialize, Clone)]
-// pub struct StaticProgramEntry {
-//     bytecode_image: Option<BytecodeImage>,
-//     file_path: Option<String>,
-//     name: String,
-//     global_data: HashMap<String, Vec<u8>>,
-//     program_type: ProgramType,
-//     xdp_attach: Option<XdpAttachInfo>,
-//     tc_attach: Option<TcAttachInfo>,
-//     tracepoint_attach: Option<TracepointAttachInfo>,
-// }
-
-// impl StaticProgramEntry {
-//     pub(crate) fn get_bytecode_image(self) -> Option<BytecodeImage> {
-//         self.bytecode_image
-//     }
-// }
-
-// #[derive(Debug, Deserialize)]
-// pub struct NetworkAttach {
-//     pub interface: String,
-//     pub priority: i32,
-//     pub proceed_on: Vec<String>,
-// }
-
-// #[derive(Debug, Deserialize, Clone)]
-// struct StaticProgramManager {
-//     #[serde(skip)]
-//     path: PathBuf,
-//     programs: Vec<StaticProgramEntry>,
-// }
-
-// impl StaticProgramManager {
-//     async fn programs_from_directory(mut self) -> Result<(), anyhow::Error> {
-//         if let Ok(mut entries) = fs::read_dir(self.path).await {
-//             while let Some(file) = entries.next_entry().await? {
-//                 let path = &file.path();
-//                 // ignore directories
-//                 if path.is_dir() {
-//                     continue;
-//                 }
-
-//                 if let Ok(contents) = read_to_string(path).await {
-//                     let program = toml::from_str(&contents)?;
-
-//                     self.programs.push(program);
-//                 } else {
-//                     warn!("Failed to parse program static file {:?}.", path.to_str());
-//                     continue;
-//                 }
-//             }
-//         }
-//         Ok(())
-//     }
-// }
-
-// pub(crate) async fn get_static_programs<P: AsRef<Path>>(
-//     path: P,
-// ) -> Result<Vec<Program>, anyhow::Error> {
-//     let static_program_manager = StaticProgramManager {
-//         path: path.as_ref().to_path_buf(),
-//         programs: Vec::new(),
-//     };
-
-//     static_program_manager
-//         .clone()
-//         .programs_from_directory()
-//         .await?;
-
-//     let mut programs: Vec<Program> = Vec::new();
-
-//     // Load any static programs first
-//     if !static_program_manager.programs.is_empty() {
-//         info!("Loading static programs from {CFGDIR_STATIC_PROGRAMS}");
-//         for program in static_program_manager.programs {
-//             let location = match program.file_path {
-//                 Some(p) => File(p),
-//                 None => Image(
-//                     program
-//                         .clone()
-//                         .get_bytecode_image()
-//                         .expect("static program did not provide bytecode"),
-//                 ),
-//             };
-
-//             let data = ProgramData::new(
-//                 location,
-//                 program.name,
-//                 HashMap::new(),
-//                 program.global_data,
-//                 None,
-//             );
-//             let prog = match program.program_type {
-//                 ProgramType::Xdp => {
-//                     if let Some(m) = program.xdp_attach {
-//                         Program::Xdp(XdpProgram::new(data, m.priority, m.iface, m.proceed_on))
-//                     } else {
-//                         bail!("invalid info for xdp program")
-//                     }
-//                 }
-//                 ProgramType::Tc => {
-//                     if let Some(m) = program.tc_attach {
-//                         Program::Tc(TcProgram::new(
-//                             data,
-//                             m.priority,
-//                             m.iface,
-//                             m.proceed_on,
-//                             m.direction,
-//                         ))
-//                     } else {
-//                         bail!("invalid attach type for tc program")
-//                     }
-//                 }
-//                 ProgramType::Tracepoint => {
-//                     if let Some(m) = program.tracepoint_attach {
-//                         Program::Tracepoint(TracepointProgram::new(data, m.tracepoint))
-//                     } else {
-//                         bail!("invalid attach type for tc program")
-//                     }
-//                 }
-//                 m => bail!("program type not yet supported to load statically: {:?}", m),
-//             };
-
-//             programs.push(prog)
-//         }
-//     };
-
-//     Ok(programs)
-// }
-
-// #[cfg(test)]
-// mod test {
-//     use super::*;
-
-//     #[tokio::test]
-//     async fn test_parse_program_from_invalid_path() {
-//         let static_program_manager = StaticProgramManager {
-//             path: "/tmp/file.toml".into(),
-//             programs: Vec::new(),
-//         };
-
-//         static_program_manager
-//             .clone()
-//             .programs_from_directory()
-//             .await
-//             .unwrap();
-//         assert!(static_program_manager.programs.is_empty())
-//     }
-
-//     #[test]
-//     fn test_parse_single_file() {
-//         let input: &str = r#"
-//         [[programs]]
-//         name = "firewall"
-//         file_path = "/opt/bin/myapp/lib/myebpf.o"
-//         global_data = { }
-//         program_type ="Xdp"
-//         xdp_attach = { iface = "eth0", priority = 50, proceed_on = [], position=0 }
-
-//         [[programs]]
-//         name = "pass"
-//         bytecode_image = { image_url = "quay.io/bpfman-bytecode/xdp_pass:latest", image_pull_policy="Always" }
-//         global_data = { }
-//         program_type ="Xdp"
-//         xdp_attach = { iface = "eth0", priority = 55, proceed_on = [], position=0 }
-
-//         [[programs]]
-//         name = "counter"
-//         bytecode_image = { image_url = "quay.io/bpfman-bytecode/xdp_pass:latest", image_pull_policy="Always" }
-//         global_data = { }
-//         program_type ="Tc"
-//         tc_attach = { iface = "eth0", priority = 55, proceed_on = [], position=0, direction="Ingress" }
-
-//         [[programs]]
-//         name = "tracepoint"
-//         bytecode_image = { image_url = "quay.io/bpfman-bytecode/tracepoint:latest", image_pull_policy="Always" }
-//         global_data = { }
-//         program_type ="Tracepoint"
-//         tracepoint_attach = { tracepoint = "syscalls/sys_enter_openat" }
-//         "#;
-
-//         let mut programs: StaticProgramManager =
-//             toml::from_str(input).expect("error parsing toml input");
-//         match programs.programs.pop() {
-//             Some(i) => {
-//                 if let Some(m) = i.xdp_attach {
-//                     assert_eq!(m.iface, "eth0");
-//                     assert_eq!(m.priority, 55);
-//                 } else if let Some(m) = i.tracepoint_attach {
-//                     assert_eq!(m.tracepoint, "syscalls/sys_enter_openat")
-//                 } else {
-//                     panic!("incorrect attach type")
-//                 }
-//             }
-//             None => panic!("expected programs to be present"),
-//         }
-//     }
-// }
+//! Programs that bpfman loads when the service starts, from the TOML files in
+//! /etc/bpfman/programs.d.
+//!
+//! Each file holds a list of programs:
+//!
+//! ```toml
+//! [[programs]]
+//! name = "pass"
+//! bytecode_image = { image_url = "quay.io/bpfman-bytecode/xdp_pass:latest", image_pull_policy = "IfNotPresent" }
+//! xdp_attach = { iface = "eth0", priority = 50 }
+//! ```
+//!
+//! Loaded static programs are recorded with the file they came from and a
+//! digest of their entry, so that programs restored from the database after a
+//! restart aren't loaded a second time. The directory is watched while bpfman
+//! runs, and the loaded programs are brought in line with the files whenever
+//! one is added, edited or removed.
+use std::{
+    collections::{BTreeMap, HashSet},
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::bail;
+use bpfman_api::{
+    util::directories::CFGDIR_STATIC_PROGRAMS, CgroupAttachType, PerfEventType, TcProceedOn,
+    XdpProceedOn,
+};
+use log::{debug, info, warn};
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::{
+    fs,
+    io::unix::AsyncFd,
+    select,
+    sync::{broadcast, mpsc::Sender},
+};
+
+use crate::{
+    command::{
+        BtfTracepointProgram, CgroupProgram, Command, Direction, FentryProgram, FexitProgram,
+        KprobeProgram,
+        Location::{File, Image},
+        LsmProgram, PerfEventProgram, Program, ProgramData, RawTracepointProgram, TcProgram,
+        TracepointProgram, UprobeProgram, XdpProgram,
+    },
+    oci_utils::image_manager::BytecodeImage,
+};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct XdpAttachInfo {
+    pub(crate) priority: i32,
+    pub(crate) iface: String,
+    #[serde(default)]
+    pub(crate) proceed_on: XdpProceedOn,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct TcAttachInfo {
+    pub(crate) priority: i32,
+    pub(crate) iface: String,
+    #[serde(default)]
+    pub(crate) proceed_on: TcProceedOn,
+    pub(crate) direction: Direction,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct TracepointAttachInfo {
+    pub(crate) tracepoint: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct KprobeAttachInfo {
+    pub(crate) fn_name: String,
+    #[serde(default)]
+    pub(crate) offset: u64,
+    #[serde(default)]
+    pub(crate) retprobe: bool,
+    pub(crate) container_pid: Option<i32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct UprobeAttachInfo {
+    pub(crate) fn_name: Option<String>,
+    #[serde(default)]
+    pub(crate) offset: u64,
+    pub(crate) target: String,
+    #[serde(default)]
+    pub(crate) retprobe: bool,
+    pub(crate) pid: Option<i32>,
+    pub(crate) container_pid: Option<i32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct FnAttachInfo {
+    pub(crate) fn_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct LsmAttachInfo {
+    pub(crate) hook: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct CgroupAttachInfo {
+    pub(crate) cgroup_path: String,
+    pub(crate) attach_type: CgroupAttachType,
+    /// Defaults to 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) priority: Option<i32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct PerfEventAttachInfo {
+    pub(crate) event_type: PerfEventType,
+    pub(crate) event: String,
+    pub(crate) sample_period: Option<u64>,
+    pub(crate) sample_frequency: Option<u64>,
+    pub(crate) cpu: Option<u32>,
+    pub(crate) pid: Option<i32>,
+}
+
+/// A program in a static program file. Exactly one of the attach tables must
+/// be set, which also sets the program's type.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct StaticProgramEntry {
+    bytecode_image: Option<BytecodeImage>,
+    file_path: Option<String>,
+    name: String,
+    // BTreeMaps keep the serialized entry, and so its digest, stable.
+    #[serde(default)]
+    global_data: BTreeMap<String, Vec<u8>>,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+    xdp_attach: Option<XdpAttachInfo>,
+    tc_attach: Option<TcAttachInfo>,
+    tracepoint_attach: Option<TracepointAttachInfo>,
+    kprobe_attach: Option<KprobeAttachInfo>,
+    uprobe_attach: Option<UprobeAttachInfo>,
+    fentry_attach: Option<FnAttachInfo>,
+    fexit_attach: Option<FnAttachInfo>,
+    // Unset fields are skipped when the entry is serialized, so the digest
+    // only covers the fields that are set. Entries that don't use these attach
+    // types keep the digest they had before the types were supported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lsm_attach: Option<LsmAttachInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cgroup_attach: Option<CgroupAttachInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    raw_tracepoint_attach: Option<TracepointAttachInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tp_btf_attach: Option<TracepointAttachInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    perf_event_attach: Option<PerfEventAttachInfo>,
+}
+
+// Editors and config management tools often write a file in several steps, so
+// changes are given time to settle before the programs are reconciled.
+const WATCH_SETTLE_TIME: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Deserialize)]
+struct StaticProgramFile {
+    #[serde(default)]
+    programs: Vec<StaticProgramEntry>,
+}
+
+/// A program from a static program file, along with the file it was read from.
+#[derive(Debug, Clone)]
+pub(crate) struct StaticProgram {
+    pub(crate) file: PathBuf,
+    pub(crate) entry: StaticProgramEntry,
+}
+
+impl StaticProgram {
+    /// Returns a digest of the program's entry, which changes whenever the
+    /// entry is edited.
+    pub(crate) fn digest(&self) -> String {
+        let entry = serde_json::to_vec(&self.entry).expect("static program entry serializes");
+        hex::encode(Sha256::digest(entry))
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.entry.name
+    }
+
+    pub(crate) fn file_name(&self) -> String {
+        self.file.to_string_lossy().to_string()
+    }
+
+    /// Builds the program described by the entry, ready to be loaded.
+    pub(crate) fn to_program(&self) -> Result<Program, anyhow::Error> {
+        let entry = &self.entry;
+        let location = match (&entry.file_path, &entry.bytecode_image) {
+            (Some(p), None) => File(p.clone()),
+            (None, Some(i)) => Image(i.clone()),
+            _ => bail!(
+                "static program {} must set one of file_path or bytecode_image",
+                entry.name
+            ),
+        };
+
+        let attach_count = [
+            entry.xdp_attach.is_some(),
+            entry.tc_attach.is_some(),
+            entry.tracepoint_attach.is_some(),
+            entry.kprobe_attach.is_some(),
+            entry.uprobe_attach.is_some(),
+            entry.fentry_attach.is_some(),
+            entry.fexit_attach.is_some(),
+            entry.lsm_attach.is_some(),
+            entry.cgroup_attach.is_some(),
+            entry.raw_tracepoint_attach.is_some(),
+            entry.tp_btf_attach.is_some(),
+            entry.perf_event_attach.is_some(),
+        ]
+        .iter()
+        .filter(|a| **a)
+        .count();
+        if attach_count != 1 {
+            bail!(
+                "static program {} must set exactly one attach type",
+                entry.name
+            );
+        }
+
+        let data = ProgramData::new_pre_load(
+            location,
+            entry.name.clone(),
+            entry.metadata.clone().into_iter().collect(),
+            entry.global_data.clone().into_iter().collect(),
+            None,
+        )?;
+
+        // The program's database tree is removed if the entry turns out not to
+        // describe a valid program.
+        self.program_from_data(data.clone()).map_err(|e| {
+            data.discard();
+            e
+        })
+    }
+
+    fn program_from_data(&self, mut data: ProgramData) -> Result<Program, anyhow::Error> {
+        let entry = &self.entry;
+        data.set_static_program(&self.file_name(), &self.digest())?;
+
+        Ok(if let Some(a) = &entry.xdp_attach {
+            Program::Xdp(XdpProgram::new(
+                data,
+                a.priority,
+                a.iface.clone(),
+                // An empty list uses the default proceed-on actions, as it does
+                // for a load request.
+                XdpProceedOn::from_int32s(a.proceed_on.as_action_vec())?,
+            )?)
+        } else if let Some(a) = &entry.tc_attach {
+            Program::Tc(TcProgram::new(
+                data,
+                a.priority,
+                a.iface.clone(),
+                TcProceedOn::from_int32s(a.proceed_on.as_action_vec())?,
+                a.direction,
+            )?)
+        } else if let Some(a) = &entry.tracepoint_attach {
+            Program::Tracepoint(TracepointProgram::new(data, a.tracepoint.clone())?)
+        } else if let Some(a) = &entry.raw_tracepoint_attach {
+            Program::RawTracepoint(RawTracepointProgram::new(data, a.tracepoint.clone())?)
+        } else if let Some(a) = &entry.tp_btf_attach {
+            Program::BtfTracepoint(BtfTracepointProgram::new(data, a.tracepoint.clone())?)
+        } else if let Some(a) = &entry.kprobe_attach {
+            Program::Kprobe(KprobeProgram::new(
+                data,
+                a.fn_name.clone(),
+                a.offset,
+                a.retprobe,
+                a.container_pid,
+            )?)
+        } else if let Some(a) = &entry.uprobe_attach {
+            Program::Uprobe(UprobeProgram::new(
+                data,
+                a.fn_name.clone(),
+                a.offset,
+                a.target.clone(),
+                a.retprobe,
+                a.pid,
+                a.container_pid,
+            )?)
+        } else if let Some(a) = &entry.fentry_attach {
+            Program::Fentry(FentryProgram::new(data, a.fn_name.clone())?)
+        } else if let Some(a) = &entry.fexit_attach {
+            Program::Fexit(FexitProgram::new(data, a.fn_name.clone())?)
+        } else if let Some(a) = &entry.lsm_attach {
+            Program::Lsm(LsmProgram::new(data, a.hook.clone())?)
+        } else if let Some(a) = &entry.cgroup_attach {
+            Program::Cgroup(CgroupProgram::new(
+                data,
+                a.cgroup_path.clone(),
+                a.attach_type,
+                a.priority.unwrap_or(0),
+            )?)
+        } else if let Some(a) = &entry.perf_event_attach {
+            a.event_type.event_config(&a.event)?;
+            if a.sample_period.is_some() == a.sample_frequency.is_some() {
+                bail!(
+                    "static program {} must set exactly one of sample_period and sample_frequency",
+                    entry.name
+                );
+            }
+            if let Some(pid) = a.pid.filter(|pid| *pid < 0) {
+                bail!("static program {} has an invalid pid {pid}", entry.name);
+            }
+            Program::PerfEvent(PerfEventProgram::new(
+                data,
+                a.event_type,
+                a.event.clone(),
+                a.sample_period,
+                a.sample_frequency,
+                a.cpu,
+                a.pid,
+            )?)
+        } else {
+            unreachable!("exactly one attach type is set")
+        })
+    }
+}
+
+/// The programs read from the static programs directory.
+#[derive(Debug, Default)]
+pub(crate) struct StaticPrograms {
+    pub(crate) programs: Vec<StaticProgram>,
+    /// Files that couldn't be read or parsed. Programs already loaded from
+    /// them are left as they are, rather than unloaded, until the file is
+    /// fixed or removed.
+    pub(crate) skipped_files: HashSet<String>,
+}
+
+/// Reads the programs from the .toml files in the given directory. Files that
+/// can't be read or parsed are skipped with a warning.
+pub(crate) async fn get_static_programs<P: AsRef<Path>>(
+    path: P,
+) -> Result<StaticPrograms, anyhow::Error> {
+    let mut programs = StaticPrograms::default();
+
+    let mut entries = match fs::read_dir(path.as_ref()).await {
+        Ok(entries) => entries,
+        // No static programs directory means there are no static programs.
+        Err(_) => return Ok(programs),
+    };
+
+    while let Some(file) = entries.next_entry().await? {
+        let path = file.path();
+        if path.is_dir() || path.extension().map_or(true, |e| e != "toml") {
+            continue;
+        }
+        match programs_from_file(&path).await {
+            Ok(p) => programs.programs.extend(p),
+            Err(e) => {
+                warn!("Skipping static program file {}: {e}", path.display());
+                programs
+                    .skipped_files
+                    .insert(path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    Ok(programs)
+}
+
+/// Reads the programs from a single static program file.
+async fn programs_from_file(path: &Path) -> Result<Vec<StaticProgram>, anyhow::Error> {
+    let contents = fs::read_to_string(path).await?;
+    let file: StaticProgramFile = toml::from_str(&contents)?;
+
+    Ok(file
+        .programs
+        .into_iter()
+        .map(|entry| StaticProgram {
+            file: path.to_path_buf(),
+            entry,
+        })
+        .collect())
+}
+
+/// Watches the static programs directory and sends the programs it holds to
+/// the BpfManager whenever a file in it changes, until shutdown. The directory
+/// is created if it doesn't exist, including when it's removed while watched.
+pub(crate) async fn watch_static_programs(
+    tx: Sender<Command>,
+    mut shutdown_channel: broadcast::Receiver<()>,
+) {
+    let mut inotify = match watch_dir(CFGDIR_STATIC_PROGRAMS) {
+        Ok(i) => i,
+        Err(e) => {
+            warn!("Unable to watch {CFGDIR_STATIC_PROGRAMS}, static program changes won't be applied until restart: {e}");
+            return;
+        }
+    };
+    info!("Watching {CFGDIR_STATIC_PROGRAMS} for static program changes");
+
+    loop {
+        select! {
+            biased;
+            _ = shutdown_channel.recv() => {
+                info!("Signal received to stop watching static programs");
+                break;
+            }
+            res = wait_for_change(&inotify) => {
+                let removed = match res {
+                    Ok(removed) => removed,
+                    Err(e) => {
+                        warn!("Failed to read {CFGDIR_STATIC_PROGRAMS} changes, no longer watching it: {e}");
+                        break;
+                    }
+                };
+                tokio::time::sleep(WATCH_SETTLE_TIME).await;
+                // Events that arrived while settling are read now, as the
+                // directory is read after them.
+                let removed = removed || drain_events(&inotify).unwrap_or(false);
+
+                // The watch ends with the directory, so an empty one is
+                // created and watched instead. Its programs are unloaded the
+                // same as if its files had been removed.
+                if removed {
+                    info!("{CFGDIR_STATIC_PROGRAMS} was removed, creating it again");
+                    inotify = match watch_dir(CFGDIR_STATIC_PROGRAMS) {
+                        Ok(i) => i,
+                        Err(e) => {
+                            warn!("Unable to watch {CFGDIR_STATIC_PROGRAMS}, static program changes won't be applied until restart: {e}");
+                            break;
+                        }
+                    };
+                }
+
+                debug!("{CFGDIR_STATIC_PROGRAMS} changed, reconciling static programs");
+                let programs = match get_static_programs(CFGDIR_STATIC_PROGRAMS).await {
+                    Ok(p) => p,
+                    Err(e) => {
+                        warn!("Failed to read {CFGDIR_STATIC_PROGRAMS}: {e}");
+                        continue;
+                    }
+                };
+                if tx.send(Command::StaticPrograms(programs)).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn watch_dir(path: &str) -> Result<AsyncFd<Inotify>, anyhow::Error> {
+    std::fs::create_dir_all(path)?;
+    let inotify = Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC)?;
+    inotify.add_watch(
+        path,
+        AddWatchFlags::IN_CREATE
+            | AddWatchFlags::IN_CLOSE_WRITE
+            | AddWatchFlags::IN_DELETE
+            | AddWatchFlags::IN_MOVED_FROM
+            | AddWatchFlags::IN_MOVED_TO
+            | AddWatchFlags::IN_DELETE_SELF
+            | AddWatchFlags::IN_MOVE_SELF,
+    )?;
+    Ok(AsyncFd::new(inotify)?)
+}
+
+// Waits for at least one inotify event and reads every pending event. Which
+// files changed doesn't matter, as the whole directory is read again. Returns
+// whether the directory itself was removed or moved away.
+async fn wait_for_change(inotify: &AsyncFd<Inotify>) -> io::Result<bool> {
+    loop {
+        let mut guard = inotify.readable().await?;
+        match guard.try_io(|fd| fd.get_ref().read_events().map_err(io::Error::from)) {
+            Ok(res) => {
+                let removed = res?.iter().any(|e| dir_removed(e.mask));
+                return Ok(drain_events(inotify)? || removed);
+            }
+            // Spurious wakeup, readiness has been cleared.
+            Err(_) => continue,
+        }
+    }
+}
+
+fn drain_events(inotify: &AsyncFd<Inotify>) -> io::Result<bool> {
+    let mut removed = false;
+    loop {
+        match inotify.get_ref().read_events() {
+            Ok(events) => removed |= events.iter().any(|e| dir_removed(e.mask)),
+            Err(nix::errno::Errno::EAGAIN) => return Ok(removed),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+fn dir_removed(mask: AddWatchFlags) -> bool {
+    mask.intersects(AddWatchFlags::IN_DELETE_SELF | AddWatchFlags::IN_MOVE_SELF)
+}
+
+#[cfg(test)]
+mod test {
+    use bpfman_api::ProgramType;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_parse_program_from_invalid_path() {
+        let programs = get_static_programs("/tmp/does/not/exist").await.unwrap();
+        assert!(programs.programs.is_empty());
+        assert!(programs.skipped_files.is_empty());
+    }
+
+    #[test]
+    fn test_parse_single_file() {
+        let input: &str = r#"
+        [[programs]]
+        name = "firewall"
+        file_path = "/opt/bin/myapp/lib/myebpf.o"
+        global_data = { }
+        xdp_attach = { iface = "eth0", priority = 50, proceed_on = [] }
+
+        [[programs]]
+        name = "counter"
+        bytecode_image = { image_url = "quay.io/bpfman-bytecode/tc_pass:latest", image_pull_policy="Always" }
+        tc_attach = { iface = "eth0", priority = 55, direction="Ingress" }
+
+        [[programs]]
+        name = "kprobe"
+        bytecode_image = { image_url = "quay.io/bpfman-bytecode/kprobe:latest", image_pull_policy="Always" }
+        kprobe_attach = { fn_name = "try_to_wake_up" }
+
+        [[programs]]
+        name = "tracepoint"
+        bytecode_image = { image_url = "quay.io/bpfman-bytecode/tracepoint:latest", image_pull_policy="Always" }
+        global_data = { GLOBAL_u8 = [1] }
+        tracepoint_attach = { tracepoint = "syscalls/sys_enter_openat" }
+        "#;
+
+        let mut programs: StaticProgramFile =
+            toml::from_str(input).expect("error parsing toml input");
+        assert_eq!(programs.programs.len(), 4);
+
+        let tracepoint = programs.programs.pop().unwrap();
+        assert_eq!(
+            tracepoint.tracepoint_attach.unwrap().tracepoint,
+            "syscalls/sys_enter_openat"
+        );
+        assert_eq!(tracepoint.global_data["GLOBAL_u8"], vec![1]);
+
+        let kprobe = programs.programs.pop().unwrap().kprobe_attach.unwrap();
+        assert_eq!(kprobe.fn_name, "try_to_wake_up");
+        assert_eq!(kprobe.offset, 0);
+        assert!(!kprobe.retprobe);
+
+        let tc = programs.programs.pop().unwrap().tc_attach.unwrap();
+        assert_eq!(tc.direction, Direction::Ingress);
+        assert_eq!(tc.priority, 55);
+
+        let xdp = programs.programs.pop().unwrap().xdp_attach.unwrap();
+        assert_eq!(xdp.iface, "eth0");
+        assert_eq!(xdp.priority, 50);
+    }
+
+    #[test]
+    fn test_parse_cgroup_attach() {
+        let input: &str = r#"
+        [[programs]]
+        name = "egress_policy"
+        file_path = "/opt/cgroup_skb.o"
+        cgroup_attach = { cgroup_path = "/sys/fs/cgroup/system.slice", attach_type = "egress" }
+        "#;
+
+        let programs: StaticProgramFile = toml::from_str(input).expect("error parsing toml input");
+        let cgroup = programs.programs[0].cgroup_attach.clone().unwrap();
+        assert_eq!(cgroup.cgroup_path, "/sys/fs/cgroup/system.slice");
+        assert_eq!(cgroup.attach_type, CgroupAttachType::Egress);
+        assert_eq!(cgroup.attach_type.program_type(), ProgramType::CgroupSkb);
+        assert_eq!(cgroup.priority, None);
+
+        let input = input.replace("egress", "connect5");
+        assert!(toml::from_str::<StaticProgramFile>(&input).is_err());
+    }
+
+    #[test]
+    fn test_parse_perf_event_attach() {
+        let input: &str = r#"
+        [[programs]]
+        name = "profile"
+        file_path = "/opt/profile.o"
+        perf_event_attach = { event_type = "software", event = "cpu_clock", sample_frequency = 99 }
+        "#;
+
+        let programs: StaticProgramFile = toml::from_str(input).expect("error parsing toml input");
+        let perf_event = programs.programs[0].perf_event_attach.clone().unwrap();
+        assert_eq!(perf_event.event_type, PerfEventType::Software);
+        assert_eq!(
+            perf_event
+                .event_type
+                .event_config(&perf_event.event)
+                .unwrap(),
+            Some(0)
+        );
+        assert_eq!(perf_event.sample_frequency, Some(99));
+        assert_eq!(perf_event.cpu, None);
+
+        let input = input.replace("software", "firmware");
+        assert!(toml::from_str::<StaticProgramFile>(&input).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_skip_invalid_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let valid = dir.path().join("valid.toml");
+        let invalid = dir.path().join("invalid.toml");
+        fs::write(
+            &valid,
+            r#"
+            [[programs]]
+            name = "pass"
+            file_path = "/opt/pass.o"
+            xdp_attach = { iface = "eth0", priority = 50 }
+            "#,
+        )
+        .await
+        .unwrap();
+        fs::write(&invalid, "[[programs]]\nname = ").await.unwrap();
+        fs::write(dir.path().join("README"), "not a program file")
+            .await
+            .unwrap();
+
+        let programs = get_static_programs(dir.path()).await.unwrap();
+        assert_eq!(programs.programs.len(), 1);
+        assert_eq!(programs.programs[0].file, valid);
+        assert!(programs
+            .skipped_files
+            .contains(invalid.to_string_lossy().as_ref()));
+    }
+
+    #[test]
+    fn test_digest_is_stable() {
+        let input: &str = r#"
+        [[programs]]
+        name = "pass"
+        file_path = "/opt/pass.o"
+        global_data = { A = [1], B = [2], C = [3] }
+        metadata = { owner = "acme", tier = "base" }
+        xdp_attach = { iface = "eth0", priority = 50 }
+        "#;
+
+        let digest = |input: &str| {
+            let mut f: StaticProgramFile = toml::from_str(input).unwrap();
+            StaticProgram {
+                file: "/etc/bpfman/programs.d/pass.toml".into(),
+                entry: f.programs.remove(0),
+            }
+            .digest()
+        };
+
+        assert_eq!(digest(input), digest(input));
+        assert_ne!(digest(input), digest(&input.replace("50", "60")));
+    }
+}
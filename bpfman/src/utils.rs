@@ -1,7 +1,12 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use std::{os::unix::fs::PermissionsExt, path::Path, str};
+use std::{
+    env,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    str,
+};
 
 use anyhow::{Context, Result};
 use log::{debug, info, warn};
@@ -68,6 +73,47 @@ pub(crate) fn create_bpffs(directory: &str) -> anyhow::Result<()> {
         .with_context(|| format!("unable to create bpffs at {directory}"))
 }
 
+const BPFMAN_NS_BIN: &str = "bpfman-ns";
+
+// Locate the bpfman-ns executable. The configured path takes precedence,
+// followed by a lookup in PATH and finally the directory holding the running
+// bpfman executable.
+pub(crate) fn find_bpfman_ns(configured: Option<&Path>) -> Result<PathBuf, BpfmanError> {
+    if let Some(path) = configured {
+        if path.is_file() {
+            return Ok(path.to_path_buf());
+        }
+        return Err(BpfmanError::BpfmanNsNotFound(format!(
+            "configured path {} does not exist",
+            path.display()
+        )));
+    }
+
+    if let Some(paths) = env::var_os("PATH") {
+        if let Some(path) = env::split_paths(&paths)
+            .map(|dir| dir.join(BPFMAN_NS_BIN))
+            .find(|path| path.is_file())
+        {
+            return Ok(path);
+        }
+    }
+
+    let exe = env::current_exe()
+        .map_err(|e| BpfmanError::BpfmanNsNotFound(format!("unable to locate bpfman: {e}")))?;
+    if let Some(path) = exe
+        .parent()
+        .map(|dir| dir.join(BPFMAN_NS_BIN))
+        .filter(|path| path.is_file())
+    {
+        return Ok(path);
+    }
+
+    Err(BpfmanError::BpfmanNsNotFound(format!(
+        "not found in PATH or in {}",
+        exe.parent().unwrap_or(Path::new("/")).display()
+    )))
+}
+
 pub(crate) fn should_map_be_pinned(name: &str) -> bool {
     !(name.contains(".rodata") || name.contains(".bss") || name.contains(".data"))
 }
@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: (GPL-2.0-only OR BSD-2-Clause)
+// Copyright Authors of bpfman
+
+// Some fentry and fexit test code
+
+// clang-format off
+#include <linux/bpf.h>
+#include <bpf/bpf_helpers.h>
+// clang-format on
+
+volatile const __u8 GLOBAL_u8 = 0;
+volatile const __u32 GLOBAL_u32 = 0;
+
+SEC("fentry/do_unlinkat")
+int test_fentry(unsigned long long *ctx) {
+  bpf_printk(" FENTRY: GLOBAL_u8: 0x%02X, GLOBAL_u32: 0x%08X", GLOBAL_u8,
+             GLOBAL_u32);
+  return 0;
+}
+
+SEC("fexit/do_unlinkat")
+int test_fexit(unsigned long long *ctx) {
+  bpf_printk(" FEXIT: GLOBAL_u8: 0x%02X, GLOBAL_u32: 0x%08X", GLOBAL_u8,
+             GLOBAL_u32);
+  return 0;
+}
+
+char _license[] SEC("license") = "Dual BSD/GPL";
@@ -331,6 +331,33 @@ fn test_load_unload_kprobe() {
     verify_and_delete_programs(loaded_ids);
 }
 
+#[integration_test]
+fn test_load_attach_detach_kprobe() {
+    let _bpfman_guard = start_bpfman().unwrap();
+
+    debug!("Loading kprobe program without attaching it");
+
+    let prog_id = add_kprobe_load_only(KPROBE_FILE_LOC).unwrap();
+
+    let link_ids: Vec<String> = (0..2)
+        .map(|_| bpfman_attach_kprobe(&prog_id).unwrap())
+        .collect();
+
+    let stdout = bpfman_get(&prog_id).unwrap();
+    for link_id in &link_ids {
+        assert!(stdout.contains(link_id.as_str()));
+    }
+
+    bpfman_detach(&link_ids[0]);
+
+    let stdout = bpfman_get(&prog_id).unwrap();
+    assert!(!stdout.contains(link_ids[0].as_str()));
+    assert!(stdout.contains(link_ids[1].as_str()));
+
+    // The remaining link is detached along with the program.
+    verify_and_delete_programs(vec![prog_id]);
+}
+
 #[integration_test]
 fn test_load_unload_kretprobe() {
     let _bpfman_guard = start_bpfman().unwrap();
@@ -356,6 +383,44 @@ fn test_load_unload_kretprobe() {
     verify_and_delete_programs(loaded_ids);
 }
 
+#[integration_test]
+fn test_load_unload_fentry() {
+    let _bpfman_guard = start_bpfman().unwrap();
+
+    debug!("Installing fentry program");
+
+    let globals = vec!["GLOBAL_u8=63", "GLOBAL_u32=0D0C0B0A"];
+
+    let mut loaded_ids = vec![];
+
+    for lt in LOAD_TYPES {
+        let prog_id =
+            add_fentry(Some(globals.clone()), lt, FENTRY_IMAGE_LOC, FENTRY_FILE_LOC).unwrap();
+        loaded_ids.push(prog_id);
+    }
+
+    verify_and_delete_programs(loaded_ids);
+}
+
+#[integration_test]
+fn test_load_unload_fexit() {
+    let _bpfman_guard = start_bpfman().unwrap();
+
+    debug!("Installing fexit program");
+
+    let globals = vec!["GLOBAL_u8=63", "GLOBAL_u32=0D0C0B0A"];
+
+    let mut loaded_ids = vec![];
+
+    for lt in LOAD_TYPES {
+        let prog_id =
+            add_fexit(Some(globals.clone()), lt, FEXIT_IMAGE_LOC, FEXIT_FILE_LOC).unwrap();
+        loaded_ids.push(prog_id);
+    }
+
+    verify_and_delete_programs(loaded_ids);
+}
+
 #[integration_test]
 fn test_pull_bytecode() {
     let _bpfman_guard = start_bpfman().unwrap();
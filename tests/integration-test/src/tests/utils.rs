@@ -40,6 +40,8 @@ pub const UPROBE_IMAGE_LOC: &str = "quay.io/bpfman-bytecode/uprobe:latest";
 pub const URETPROBE_IMAGE_LOC: &str = "quay.io/bpfman-bytecode/uretprobe:latest";
 pub const KPROBE_IMAGE_LOC: &str = "quay.io/bpfman-bytecode/kprobe:latest";
 pub const KRETPROBE_IMAGE_LOC: &str = "quay.io/bpfman-bytecode/kretprobe:latest";
+pub const FENTRY_IMAGE_LOC: &str = "quay.io/bpfman-bytecode/fentry:latest";
+pub const FEXIT_IMAGE_LOC: &str = "quay.io/bpfman-bytecode/fexit:latest";
 pub const XDP_COUNTER_IMAGE_LOC: &str = "quay.io/bpfman-bytecode/go-xdp-counter";
 pub const TC_COUNTER_IMAGE_LOC: &str = "quay.io/bpfman-bytecode/go-tc-counter";
 pub const TRACEPOINT_COUNTER_IMAGE_LOC: &str = "quay.io/bpfman-bytecode/go-tracepoint-counter";
@@ -51,6 +53,8 @@ pub const UPROBE_FILE_LOC: &str = "tests/integration-test/bpf/.output/uprobe.bpf
 pub const URETPROBE_FILE_LOC: &str = "tests/integration-test/bpf/.output/uprobe.bpf.o";
 pub const KPROBE_FILE_LOC: &str = "tests/integration-test/bpf/.output/kprobe.bpf.o";
 pub const KRETPROBE_FILE_LOC: &str = "tests/integration-test/bpf/.output/kprobe.bpf.o";
+pub const FENTRY_FILE_LOC: &str = "tests/integration-test/bpf/.output/fentry.bpf.o";
+pub const FEXIT_FILE_LOC: &str = "tests/integration-test/bpf/.output/fentry.bpf.o";
 
 /// Exit on panic as well as the passing of a test
 #[derive(Debug)]
@@ -459,6 +463,86 @@ pub fn add_kretprobe(
     Ok(prog_id)
 }
 
+/// Install a fentry program with bpfman
+pub fn add_fentry(
+    globals: Option<Vec<&str>>,
+    load_type: &LoadType,
+    image_url: &str,
+    file_path: &str,
+) -> Result<String> {
+    let mut args = vec!["load"];
+    match load_type {
+        LoadType::Image => {
+            args.push("image");
+        }
+        LoadType::File => {
+            args.push("file");
+        }
+    }
+
+    if let Some(g) = globals {
+        args.push("--global");
+        args.extend(g);
+    }
+
+    match load_type {
+        LoadType::Image => args.extend(["--image-url", image_url, "--pull-policy", "Always"]),
+        LoadType::File => args.extend(["-n", "test_fentry", "--path", file_path]),
+    }
+
+    args.extend(["fentry", "-f", "do_unlinkat"]);
+
+    let output = Command::cargo_bin("bpfman")?.args(args).ok();
+    let stdout = String::from_utf8(output.unwrap().stdout).unwrap();
+    let prog_id = bpfman_output_parse_id(&stdout);
+    assert!(!prog_id.is_empty());
+    debug!(
+        "Successfully added fentry program: {:?} from: {:?}",
+        prog_id, load_type
+    );
+    Ok(prog_id)
+}
+
+/// Install a fexit program with bpfman
+pub fn add_fexit(
+    globals: Option<Vec<&str>>,
+    load_type: &LoadType,
+    image_url: &str,
+    file_path: &str,
+) -> Result<String> {
+    let mut args = vec!["load"];
+    match load_type {
+        LoadType::Image => {
+            args.push("image");
+        }
+        LoadType::File => {
+            args.push("file");
+        }
+    }
+
+    if let Some(g) = globals {
+        args.push("--global");
+        args.extend(g);
+    }
+
+    match load_type {
+        LoadType::Image => args.extend(["--image-url", image_url, "--pull-policy", "Always"]),
+        LoadType::File => args.extend(["-n", "test_fexit", "--path", file_path]),
+    }
+
+    args.extend(["fexit", "-f", "do_unlinkat"]);
+
+    let output = Command::cargo_bin("bpfman")?.args(args).ok();
+    let stdout = String::from_utf8(output.unwrap().stdout).unwrap();
+    let prog_id = bpfman_output_parse_id(&stdout);
+    assert!(!prog_id.is_empty());
+    debug!(
+        "Successfully added fexit program: {:?} from: {:?}",
+        prog_id, load_type
+    );
+    Ok(prog_id)
+}
+
 /// Delete a bpfman program using bpfman
 pub fn bpfman_del_program(prog_id: &str) {
     Command::cargo_bin("bpfman")
@@ -471,6 +555,58 @@ pub fn bpfman_del_program(prog_id: &str) {
     debug!("Successfully deleted program: \"{}\"", prog_id.trim());
 }
 
+/// Load a kprobe program with bpfman without attaching it
+pub fn add_kprobe_load_only(file_path: &str) -> Result<String> {
+    let args = vec![
+        "load",
+        "file",
+        "-n",
+        "my_kprobe",
+        "--path",
+        file_path,
+        "--program-type",
+        "probe",
+    ];
+
+    let output = Command::cargo_bin("bpfman")?.args(args).ok();
+    let stdout = String::from_utf8(output.unwrap().stdout).unwrap();
+    let prog_id = bpfman_output_parse_id(&stdout);
+    assert!(!prog_id.is_empty());
+    debug!("Successfully loaded kprobe program: {:?}", prog_id);
+    Ok(prog_id)
+}
+
+/// Attach a loaded kprobe program with bpfman, returning the link id
+pub fn bpfman_attach_kprobe(prog_id: &str) -> Result<String> {
+    let output = Command::cargo_bin("bpfman")?
+        .args(["attach", prog_id.trim(), "kprobe", "-f", "try_to_wake_up"])
+        .ok();
+    let stdout = String::from_utf8(output.unwrap().stdout).unwrap();
+    let link_id = stdout
+        .trim()
+        .strip_prefix("Link ID: ")
+        .unwrap_or_default()
+        .to_string();
+    assert!(!link_id.is_empty());
+    debug!(
+        "Successfully attached program: {:?} with link: {:?}",
+        prog_id, link_id
+    );
+    Ok(link_id)
+}
+
+/// Detach a link with bpfman
+pub fn bpfman_detach(link_id: &str) {
+    Command::cargo_bin("bpfman")
+        .unwrap()
+        .args(["detach", link_id.trim()])
+        .assert()
+        .success()
+        .stdout(is_empty());
+
+    debug!("Successfully detached link: \"{}\"", link_id.trim());
+}
+
 /// Retrieve the output of bpfman list
 pub fn bpfman_list(metadata_selector: Option<Vec<&str>>) -> Result<String> {
     let mut args = vec!["list"];